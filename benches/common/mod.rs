@@ -0,0 +1,25 @@
+use std::fs;
+
+/// concatenates all examples until the input is at least `size` bytes large
+pub fn examples(size: usize) -> String {
+    let mut examples = String::new();
+
+    let mut paths: Vec<_> = fs::read_dir("./examples")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "crap"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        examples.push_str(&fs::read_to_string(path).unwrap());
+        examples.push_str(";\n");
+    }
+
+    let mut buffer = String::with_capacity(size + examples.len());
+    while buffer.len() < size {
+        buffer.push_str(&examples);
+    }
+
+    buffer
+}
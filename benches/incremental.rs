@@ -0,0 +1,93 @@
+use std::time::Instant;
+
+mod common;
+
+use crap_lang::incremental::{Document, Edit};
+
+const TARGET_SIZE: usize = 256 * 1024;
+const EDITS: usize = 500;
+
+/// xorshift, the edits are the same on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+/// an edit that keeps the source valid, it starts searching at a random offset
+fn random_edit(buffer: &str, random: &mut Random) -> Edit {
+    let bytes = buffer.as_bytes();
+    let from = random.next(bytes.len());
+
+    let find = |predicate: fn(&u8) -> bool| match bytes[from..].iter().position(predicate) {
+        Some(position) => from + position,
+        None => bytes.iter().position(predicate).unwrap(),
+    };
+
+    match random.next(4) {
+        // change a digit
+        0 => {
+            let index = find(|byte| byte.is_ascii_digit());
+            let text = ((b'0' + random.next(10) as u8) as char).to_string();
+            Edit { range: index..index + 1, text }
+        }
+
+        // add a comment or a doc comment in front of a line
+        1 | 2 => {
+            let index = find(|&byte| byte == b'\n') + 1;
+            let text = match random.next(2) {
+                0 => "// note\n",
+                _ => "/// docs\n",
+            };
+            Edit { range: index..index, text: text.to_string() }
+        }
+
+        // remove a comment again
+        _ => match buffer[from..].find("// note\n").map(|index| from + index) {
+            Some(index) => Edit { range: index..index + 8, text: String::new() },
+            None => Edit { range: 0..0, text: " ".to_string() },
+        },
+    }
+}
+
+fn main() {
+    let mut document = Document::new(common::examples(TARGET_SIZE));
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+
+    let mut incremental = 0.0;
+    let mut full = 0.0;
+
+    for _ in 0..EDITS {
+        let edit = random_edit(document.buffer(), &mut random);
+
+        let start = Instant::now();
+        document.apply(&edit);
+        incremental += start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let expected = Document::new(document.buffer().to_string());
+        full += start.elapsed().as_secs_f64();
+
+        // the incremental result has to match a full relex and reparse
+        assert!(document.tokens() == expected.tokens(), "tokens differ after {:?}", edit);
+        assert!(document.statements() == expected.statements(), "statements differ after {:?}", edit);
+        assert!(
+            document.diagnostics().eq(expected.diagnostics()),
+            "diagnostics differ after {:?}",
+            edit
+        );
+    }
+
+    println!(
+        "{} edits on {:.0} KiB: incremental {:.3}ms, full {:.3}ms per edit",
+        EDITS,
+        document.buffer().len() as f64 / 1024.0,
+        incremental * 1000.0 / EDITS as f64,
+        full * 1000.0 / EDITS as f64,
+    );
+}
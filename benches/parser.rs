@@ -0,0 +1,76 @@
+use std::{hint::black_box, mem, time::Instant};
+
+mod common;
+
+use crap_lang::{
+    ast::{expressions::ExpressionNode, statements::Statement},
+    diagnostics::Severity,
+    lexer::tokenize,
+    parser::parse,
+};
+
+const TARGET_SIZE: usize = 8 * 1024 * 1024;
+const RUNS: usize = 5;
+
+/// generated lookup tables, every struct literal has more than `u16::MAX` nodes
+fn tables() -> String {
+    let fields: Vec<String> = (0..40_000).map(|i| format!("f{}: {}", i, i)).collect();
+    let table = format!("let table = Table {{ {} }}\n", fields.join(", "));
+
+    let mut buffer = String::with_capacity(TARGET_SIZE + table.len());
+    while buffer.len() < TARGET_SIZE {
+        buffer.push_str(&table);
+    }
+
+    buffer
+}
+
+fn bench(name: &str, buffer: &str) {
+    let megabytes = buffer.len() as f64 / (1024.0 * 1024.0);
+
+    let mut best = f64::MAX;
+    let mut nodes = 0;
+    let mut escapes = 0;
+
+    for _ in 0..RUNS {
+        let tokens = tokenize(buffer);
+
+        let start = Instant::now();
+        let (stmts, diagnostics) = black_box(parse(black_box(tokens)));
+        best = best.min(start.elapsed().as_secs_f64());
+
+        // the examples have warnings on purpose
+        let errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect();
+        assert!(errors.is_empty(), "{}: {:?}", name, errors);
+
+        nodes = stmts.iter().map(|stmt| stmt.expr().len()).sum();
+        escapes = stmts
+            .iter()
+            .flat_map(Statement::expr)
+            .filter(|node| node.is_escaped())
+            .count();
+    }
+
+    let node_size = mem::size_of::<ExpressionNode>();
+
+    println!(
+        "parse {}: {:.1} MiB, {} nodes ({} escaped) of {} bytes = {:.1} MiB, best of {}: {:.3}s ({:.1} MiB/s)",
+        name,
+        megabytes,
+        nodes,
+        escapes,
+        node_size,
+        (nodes * node_size) as f64 / (1024.0 * 1024.0),
+        RUNS,
+        best,
+        megabytes / best
+    );
+}
+
+fn main() {
+    bench("examples", &common::examples(TARGET_SIZE));
+    bench("tables", &tables());
+}
@@ -0,0 +1,54 @@
+// runs a module of `crap build --target wasm`, e.g.
+//   crap build --target wasm -o out.wasm file.crap && node examples/wasm/run.mjs out.wasm
+import { readFileSync, writeSync } from "node:fs";
+
+// the shortest digits that read back as the same value, formatted like `{:?}` in rust
+function formatF64(value) {
+  if (Number.isNaN(value)) return "NaN";
+  if (value === Infinity) return "inf";
+  if (value === -Infinity) return "-inf";
+  if (value === 0) return Object.is(value, -0) ? "-0.0" : "0.0";
+
+  const [mantissa, e] = value.toExponential().split("e");
+  const sign = value < 0 ? "-" : "";
+  const digits = mantissa.replace("-", "").replace(".", "");
+  const exponent = Number(e);
+
+  if (exponent < -4 || exponent >= 16) {
+    const fraction = digits.length > 1 ? "." + digits.slice(1) : "";
+    return `${sign}${digits[0]}${fraction}e${exponent}`;
+  }
+  if (exponent < 0) {
+    return `${sign}0.${"0".repeat(-exponent - 1)}${digits}`;
+  }
+
+  const integer = digits.slice(0, exponent + 1).padEnd(exponent + 1, "0");
+  return `${sign}${integer}.${digits.slice(exponent + 1) || "0"}`;
+}
+
+const [path, name = "main"] = process.argv.slice(2);
+if (!path) {
+  console.error("usage: node run.mjs <file.wasm> [function]");
+  process.exit(2);
+}
+
+let memory;
+const text = (offset, length) =>
+  new TextDecoder().decode(new Uint8Array(memory.buffer, offset, length));
+
+const imports = {
+  crap: {
+    print: (offset, length) => writeSync(1, text(offset, length)),
+    print_i64: (value) => writeSync(1, value.toString()),
+    print_f64: (value) => writeSync(1, formatF64(value)),
+    fmod: (lhs, rhs) => lhs % rhs,
+    error: (offset, length) => {
+      writeSync(2, `error: ${text(offset, length)}\n`);
+      process.exit(1);
+    },
+  },
+};
+
+const { instance } = await WebAssembly.instantiate(readFileSync(path), imports);
+memory = instance.exports.memory;
+instance.exports[name]();
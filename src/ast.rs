@@ -1,2 +1,8 @@
+pub mod dot;
 pub mod expressions;
-pub mod statements;
\ No newline at end of file
+pub mod json;
+pub mod printer;
+pub mod sexpr;
+pub mod statements;
+pub mod view;
+pub mod visitor;
\ No newline at end of file
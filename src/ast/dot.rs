@@ -0,0 +1,255 @@
+use std::fmt::Write;
+
+use crate::{lexer::lexeme, utils::bit_array::BitArray};
+
+use super::{
+    expressions::ExpressionKind,
+    statements::{Statement, StatementFlag},
+    view::ExprRef,
+};
+
+// --- format --- //
+// statement: `s<n>`, an ellipse labeled with the kind and the flags of a `Variable`
+// node:      `n<n>`, a box labeled with the `ExpressionKind` and the source text of its token
+//
+// edges go from the parent to its children in source order, the parts of a statement
+// and of a `Ternary` are labeled, e.g. `name`, `type` and `value` of a `Variable`
+
+/// the syntax tree of a program as a graphviz graph, see `dot -Tsvg`
+pub fn to_dot(buffer: &str, stmts: &[Statement]) -> String {
+    let mut graph = Graph {
+        buffer,
+        output: String::new(),
+        count: 0,
+    };
+
+    graph.output.push_str("digraph ast {\n");
+    graph.output.push_str("  ordering=out;\n");
+    graph
+        .output
+        .push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        graph.write_statement(i, stmt);
+    }
+
+    graph.output.push_str("}\n");
+    graph.output
+}
+
+struct Graph<'a> {
+    buffer: &'a str,
+    output: String,
+
+    // expression nodes written so far, used for the ids
+    count: usize,
+}
+
+impl Graph<'_> {
+    fn write_statement(&mut self, i: usize, stmt: &Statement) {
+        use Statement::*;
+
+        let mut label = stmt.name().to_string();
+
+        if let Variable { flags, .. } = stmt {
+            if StatementFlag::IsConst.has(*flags) {
+                label.push_str("\nIsConst");
+            }
+
+            if StatementFlag::HasType.has(*flags) {
+                label.push_str("\nHasType");
+            }
+        }
+
+        _ = writeln!(
+            self.output,
+            "  s{} [label=\"{}\", shape=ellipse];",
+            i,
+            escape(&label)
+        );
+
+        let parts = stmt.expressions();
+        let labeled: Vec<(&str, ExprRef)> = match stmt {
+            // [E value][E type]?[E symbol]
+            Variable { .. } => match parts[..] {
+                [value, ty, name] => vec![("name", name), ("type", ty), ("value", value)],
+                [value, name] => vec![("name", name), ("value", value)],
+                _ => unreachable!("invalid variable statement"),
+            },
+            // [E value][E target]
+            Assignment { .. } => vec![("target", parts[1]), ("value", parts[0])],
+            // [E type][E symbol]
+            Alias { .. } => vec![("name", parts[1]), ("type", parts[0])],
+            _ => parts.into_iter().map(|part| ("", part)).collect(),
+        };
+
+        for (name, part) in labeled {
+            let id = self.write_node(part);
+            self.write_edge(&format!("s{}", i), &id, name);
+        }
+    }
+
+    /// writes the subtree, returns the id of its root
+    fn write_node(&mut self, expr: ExprRef) -> String {
+        if *expr.kind() == ExpressionKind::Binary {
+            return self.write_chain(expr);
+        }
+
+        let id = self.write_label(expr);
+
+        let children = expr.children();
+        let labeled: Vec<(&str, ExprRef)> = match expr.node().kind {
+            // [R][M][L]
+            ExpressionKind::Ternary => {
+                vec![
+                    ("cond", children[2]),
+                    ("then", children[1]),
+                    ("else", children[0]),
+                ]
+            }
+            _ => children.into_iter().map(|child| ("", child)).collect(),
+        };
+
+        for (name, child) in labeled {
+            let child = self.write_node(child);
+            self.write_edge(&id, &child, name);
+        }
+
+        id
+    }
+
+    /// a chain of binary operators, written with loops in the same order as the other
+    /// nodes, the operators from the outermost one in and then their operands
+    fn write_chain(&mut self, expr: ExprRef) -> String {
+        let (first, chain) = expr.chain();
+
+        let mut ids: Vec<String> = chain
+            .iter()
+            .rev()
+            .map(|expr| self.write_label(*expr))
+            .collect();
+        let mut lhs = self.write_node(first);
+
+        for expr in chain {
+            let id = ids.pop().unwrap();
+            self.write_edge(&id, &lhs, "");
+
+            let rhs = self.write_node(expr.rhs());
+            self.write_edge(&id, &rhs, "");
+            lhs = id;
+        }
+
+        lhs
+    }
+
+    /// writes the box of the root of a subtree, returns its id
+    fn write_label(&mut self, expr: ExprRef) -> String {
+        let node = expr.node();
+        let id = format!("n{}", self.count);
+        self.count += 1;
+
+        let text = lexeme(self.buffer, node.index, &node.token);
+        let label = format!("{:?}\n{}", node.kind, text);
+        _ = writeln!(self.output, "  {} [label=\"{}\"];", id, escape(&label));
+
+        id
+    }
+
+    fn write_edge(&mut self, from: &str, to: &str, label: &str) {
+        match label {
+            "" => _ = writeln!(self.output, "  {} -> {};", from, to),
+            label => _ = writeln!(self.output, "  {} -> {} [label=\"{}\"];", from, to, label),
+        }
+    }
+}
+
+/// a dot string without the quotes, line breaks become `\n`
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for char in text.chars() {
+        match char {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            '\n' => escaped.push_str("\\n"),
+            char => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::tokenize, parser::parse};
+
+    use super::to_dot;
+
+    /// the lines of the graph between the header and the closing `}`
+    fn graph(buffer: &str) -> Vec<String> {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        let dot = to_dot(buffer, &stmts);
+        let lines: Vec<&str> = dot.lines().collect();
+        lines[3..lines.len() - 1]
+            .iter()
+            .map(|line| line.trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn statements_and_ternaries() {
+        let buffer = "const a: i64 = 1\na ? 1 : 2\n";
+
+        assert_eq!(
+            graph(buffer),
+            [
+                "s0 [label=\"Variable\\nIsConst\\nHasType\", shape=ellipse];",
+                "n0 [label=\"Literal\\na\"];",
+                "s0 -> n0 [label=\"name\"];",
+                "n1 [label=\"Literal\\ni64\"];",
+                "s0 -> n1 [label=\"type\"];",
+                "n2 [label=\"Literal\\n1\"];",
+                "s0 -> n2 [label=\"value\"];",
+                "s1 [label=\"Expression\", shape=ellipse];",
+                "n3 [label=\"Ternary\\n?\"];",
+                "n4 [label=\"Literal\\na\"];",
+                "n3 -> n4 [label=\"cond\"];",
+                "n5 [label=\"Literal\\n1\"];",
+                "n3 -> n5 [label=\"then\"];",
+                "n6 [label=\"Literal\\n2\"];",
+                "n3 -> n6 [label=\"else\"];",
+                "s1 -> n3;",
+            ]
+        );
+    }
+
+    #[test]
+    fn chains() {
+        // the operators are numbered from the outermost one in, like any parent
+        // before its children
+        assert_eq!(
+            graph("1 - 2 * 3 + 4\n"),
+            [
+                "s0 [label=\"Expression\", shape=ellipse];",
+                "n0 [label=\"Binary\\n+\"];",
+                "n1 [label=\"Binary\\n-\"];",
+                "n2 [label=\"Literal\\n1\"];",
+                "n1 -> n2;",
+                "n3 [label=\"Binary\\n*\"];",
+                "n4 [label=\"Literal\\n2\"];",
+                "n3 -> n4;",
+                "n5 [label=\"Literal\\n3\"];",
+                "n3 -> n5;",
+                "n1 -> n3;",
+                "n0 -> n1;",
+                "n6 [label=\"Literal\\n4\"];",
+                "n0 -> n6;",
+                "s0 -> n0;",
+            ]
+        );
+    }
+}
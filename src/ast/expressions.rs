@@ -1,8 +1,14 @@
 use core::fmt;
+use std::io::{self, Write};
 
-use crate::lexer::tokens::Token;
+use crate::{
+    lexer::tokens::{Token, TokenNode, TokenValue},
+    utils::interner::Symbol,
+};
 
-#[derive(Debug)]
+use super::view::ExprRef;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionKind {
     // --- legend --- //
     // [E]: expression
@@ -10,6 +16,7 @@ pub enum ExpressionKind {
     // [M]: middle hand side expression
     // [R]: right hand side expression
     // [T]: token
+    // [S]: size of a subtree with more than `u16::MAX` nodes, see `push_root`
 
     // --- literal --- //
     Literal, // [T]
@@ -21,31 +28,56 @@ pub enum ExpressionKind {
 
     // --- binary --- //
     Binary, // [L][T][R] -> [L][R][T]
+    Member, // [L][T][R] -> [L][R][T]
 
     // --- ternary --- //
 
     // @TODO: check if this is the best storing method
     Ternary, // [L][T][M][T][R] -> [R][M][L][T]
+
+    // --- composite --- //
+    Struct,  // [T][T]([E],...)[T] -> [E]...[T]
+    Field,   // [T][T][E] -> [E][T]
+    Enum,    // [T][T]([E],...)[T] -> [E]...[T]
+    Variant, // [T][T]([E],...)[T] -> [E]...[T]
+    Call,    // [E][T]([E],...)[T] -> [E][E]...[T]
+    Match,   // [T][E][T]([E],...)[T] -> [E][E]...[T]
+    Arm,     // [L][T][R] -> [L][R][T]
 }
 
+#[derive(Clone, PartialEq)]
 pub struct ExpressionNode {
     pub index: u32,
     pub size: u16,
     pub token: Token,
     pub kind: ExpressionKind,
+    pub value: TokenValue,
 }
 
-impl ExpressionNode {
-    pub fn new(index: u32, size: usize, token: Token, kind: ExpressionKind) -> ExpressionNode {
-        if size > u16::MAX.into() {
-            panic!("max expression size reached");
-        }
+/// `size` of a root whose subtree does not fit into `u16`
+pub const ESCAPED_SIZE: u16 = 0;
 
+impl ExpressionNode {
+    pub fn new(node: TokenNode, size: u16, kind: ExpressionKind) -> ExpressionNode {
         ExpressionNode {
-            index,
-            size: size.try_into().unwrap(),
-            token,
+            index: node.index,
+            size,
+            token: node.token,
             kind,
+            value: node.value,
+        }
+    }
+
+    /// `true` when the real size is stored in the `[S]` node in front of this one
+    pub fn is_escaped(&self) -> bool {
+        self.size == ESCAPED_SIZE
+    }
+
+    /// interned name of an identifier or string node
+    pub fn symbol(&self) -> Symbol {
+        match self.value {
+            TokenValue::Symbol(symbol) => symbol,
+            _ => panic!("expected a symbol: {:?}", self),
         }
     }
 }
@@ -60,57 +92,90 @@ impl fmt::Debug for ExpressionNode {
 pub type Expression = Box<[ExpressionNode]>;
 pub type MutExpression = Vec<ExpressionNode>;
 
-pub fn debug_expr(expr: &Expression, deph: &mut Vec<isize>) {
-    use ExpressionKind::*;
+/// pushes the root of a subtree made of the last `size - 1` nodes of `expr`,
+/// returns the size including the `[S]` node of an escaped subtree
+///
+/// almost all subtrees fit into the `u16` of the root, bigger ones get an
+/// extra `[S]` node in front of the root, `[E]...[S][T]`, so the common case
+/// stays at 24 bytes per node
+pub fn push_root(
+    expr: &mut MutExpression,
+    node: TokenNode,
+    size: usize,
+    kind: ExpressionKind,
+) -> Result<usize, String> {
+    if let Ok(size) = u16::try_from(size) {
+        expr.push(ExpressionNode::new(node, size, kind));
+        return Ok(size.into());
+    }
 
-    let mut i = expr.len();
+    // a subtree never has more nodes than its source has bytes
+    let size = size + 1;
+    if size > u32::MAX as usize {
+        return Err("expression is too large".to_string());
+    }
 
-    while i > 0 {
-        i -= 1;
+    expr.push(ExpressionNode {
+        index: node.index,
+        size: 1,
+        token: Token::Eof,
+        kind: ExpressionKind::Literal,
+        value: TokenValue::Int(size as i64),
+    });
+    expr.push(ExpressionNode::new(node, ESCAPED_SIZE, kind));
 
-        let index = deph.len() - 1;
-        let node = expr.get(i).unwrap();
+    Ok(size)
+}
 
-        let mut indent = String::new();
-        if deph.len() > 0 {
-            for i in 0..(deph.len() - 1) {
-                if let Some(0) = deph.get(i) {
-                    indent.push_str("    ");
-                } else {
-                    indent.push_str("  │ ");
-                }
-            }
+/// the number of nodes of the subtree whose root is right in front of `end`
+pub fn subtree_size(nodes: &[ExpressionNode], end: usize) -> usize {
+    let root = &nodes[end - 1];
 
-            if let Some(1) = deph.last() {
-                indent.push_str("  └─");
-            } else {
-                indent.push_str("  ├─");
-            }
-        }
+    if !root.is_escaped() {
+        return root.size.into();
+    }
 
-        if let Some(_) = deph.get(index) {
-            deph[index] = deph[index] - 1;
-        }
+    match nodes[end - 2].value {
+        TokenValue::Int(size) => size as usize,
+        _ => panic!("expected the size of {:?}", root),
+    }
+}
 
-        match node.kind {
-            Ternary => {
-                deph.push(3);
-            }
-            Binary => {
-                deph.push(2);
-            }
-            Literal => {
-                // deph.push(0);
-            }
-            _ => {
-                deph.push(1);
-            }
+/// prints a subtree as an ascii tree, the children last to first like they are stored
+///
+/// a chain of binary operators is printed flat from the outermost operator in, every
+/// operator with its right side under it and the first operand of the chain last
+pub fn debug_expr(
+    output: &mut impl Write,
+    expr: ExprRef,
+    indent: &str,
+    is_last: bool,
+) -> io::Result<()> {
+    let (branch, nested) = match is_last {
+        true => ("  └─", "    "),
+        false => ("  ├─", "  │ "),
+    };
+
+    if *expr.kind() == ExpressionKind::Binary {
+        let (first, chain) = expr.chain();
+        let indent_rhs = format!("{}{}", indent, nested);
+
+        for expr in chain.iter().rev() {
+            writeln!(output, "{}{}{:?}", indent, branch, expr.node())?;
+            debug_expr(output, expr.rhs(), &indent_rhs, true)?;
         }
 
-        while let Some(0) = deph.last() {
-            deph.pop();
-        }
+        return debug_expr(output, first, indent, is_last);
+    }
+
+    writeln!(output, "{}{}{:?}", indent, branch, expr.node())?;
 
-        println!("{}{:?}", indent, node);
+    let indent = format!("{}{}", indent, nested);
+    let children = expr.children();
+
+    for (i, child) in children.iter().rev().enumerate() {
+        debug_expr(output, *child, &indent, i + 1 == children.len())?;
     }
+
+    Ok(())
 }
@@ -0,0 +1,341 @@
+use serde_json::{json, Map, Value};
+
+use crate::{
+    lexer::{
+        doc_text, lexeme,
+        tokens::{Token, TokenValue},
+    },
+    utils::{
+        bit_array::{BitArray, Byte},
+        interner::Symbol,
+    },
+};
+
+use super::{
+    expressions::{ExpressionKind, ExpressionNode, MutExpression},
+    statements::{Docs, Statement, StatementFlag},
+};
+
+// --- format (version 3) --- //
+// program:    { "version": 3, "statements": [statement, ...] }
+// statement:  { "kind", "flags"?, "docs"?, "nodes": [node, ...] }
+//   kind:     "Expression" | "Variable" | "Assignment" | "Struct" | "Enum" | "Alias" | "Error"
+//   flags:    "IsConst" and "HasType", only on `Variable`
+//   docs:     the doc comments, only on declarations, [{ "text", "span": [start, end] }, ...]
+//             with the text without the markers and the span of the whole comment
+//   nodes:    the nodes of the expressions in postfix order like they are stored, the top
+//             level subtrees follow each other, e.g. [value, type?, name] of a `Variable`,
+//             see `Statement`
+// node:       { "kind", "token", "text", "span": [start, end], "value"?, "size" }
+//   kind:     the `ExpressionKind`, e.g. "Binary"
+//   token:    the `Token`, e.g. "Plus"
+//   text:     the source text of the token, e.g. "+"
+//   span:     byte offsets of the token in the source, literals made by the optimizer
+//             keep the token of the subtree they were folded from
+//   value:    the integer, float or name of a literal
+//   size:     the number of nodes of the subtree the node is the root of, itself included,
+//             0 for a subtree with more than 65,535 nodes whose size is the value of the
+//             `Eof` node in front of it, see `push_root`
+//
+// the list is flat, so it is as long as the tree is big, not as deep
+
+const VERSION: u64 = 3;
+
+const KINDS: &[ExpressionKind] = {
+    use ExpressionKind::*;
+    &[
+        Literal, Prefix, Sufix, Block, Binary, Member, Ternary, Struct, Field, Enum, Variant, Call,
+        Match, Arm,
+    ]
+};
+
+const TOKENS: &[Token] = {
+    use Token::*;
+    &[
+        Number, String, Null, OpenParen, CloseParen, OpenBrace, CloseBrace, Comma, Dot, Semi,
+        Question, QuestionDot, Colon, Equal, FatArrow, Plus, Minus, Star, Slash, Percent,
+        QuestionQuestion, Identifier, Let, Const, Struct, Enum, Match, Type, Comment, DocComment,
+        Error, Eol, Eof,
+    ]
+};
+
+/// the syntax tree of a program, `buffer` is the source it was parsed from
+pub fn to_json(buffer: &str, stmts: &[Statement]) -> Value {
+    let statements: Vec<Value> = stmts
+        .iter()
+        .map(|stmt| statement_to_json(buffer, stmt))
+        .collect();
+
+    json!({ "version": VERSION, "statements": statements })
+}
+
+fn statement_to_json(buffer: &str, stmt: &Statement) -> Value {
+    use Statement::*;
+
+    let mut object = Map::new();
+
+    object.insert("kind".into(), stmt.name().into());
+
+    if let Variable { flags, .. } = stmt {
+        let mut names = vec![];
+
+        if StatementFlag::IsConst.has(*flags) {
+            names.push("IsConst");
+        }
+
+        if StatementFlag::HasType.has(*flags) {
+            names.push("HasType");
+        }
+
+        object.insert("flags".into(), names.into());
+    }
+
+    if let Some(docs) = stmt.docs() {
+        let docs: Vec<Value> = docs
+            .iter()
+            .map(|&index| {
+                let end = index as usize + lexeme(buffer, index, &Token::DocComment).len();
+                json!({ "text": doc_text(buffer, index), "span": [index, end] })
+            })
+            .collect();
+        object.insert("docs".into(), docs.into());
+    }
+
+    let nodes: Vec<Value> = stmt.expr().iter().map(|node| node_to_json(buffer, node)).collect();
+    object.insert("nodes".into(), nodes.into());
+
+    Value::Object(object)
+}
+
+fn node_to_json(buffer: &str, node: &ExpressionNode) -> Value {
+    let text = lexeme(buffer, node.index, &node.token);
+    let end = node.index as usize + text.len();
+
+    let mut object = Map::new();
+    object.insert("kind".into(), format!("{:?}", node.kind).into());
+    object.insert("token".into(), format!("{:?}", node.token).into());
+    object.insert("text".into(), text.into());
+    object.insert("span".into(), json!([node.index, end]));
+
+    match node.value {
+        TokenValue::None => (),
+        TokenValue::Symbol(symbol) => _ = object.insert("value".into(), symbol.as_str().into()),
+        TokenValue::Int(value) => _ = object.insert("value".into(), value.into()),
+        TokenValue::Float(value) => _ = object.insert("value".into(), value.into()),
+    }
+
+    object.insert("size".into(), node.size.into());
+
+    Value::Object(object)
+}
+
+/// reads a program written by `to_json` back
+pub fn from_json(program: &Value) -> Result<Vec<Statement>, String> {
+    match program["version"].as_u64() {
+        Some(VERSION) => (),
+        Some(version) => return Err(format!("unsupported version {}", version)),
+        None => return Err("missing version".to_string()),
+    }
+
+    let Some(statements) = program["statements"].as_array() else {
+        return Err("missing statements".to_string());
+    };
+
+    statements.iter().map(statement_from_json).collect()
+}
+
+fn statement_from_json(stmt: &Value) -> Result<Statement, String> {
+    let expr = stmt["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(node_from_json)
+        .collect::<Result<MutExpression, String>>()?;
+
+    check_sizes(&expr)?;
+
+    let expr = expr.into_boxed_slice();
+    let docs: Docs = match stmt.get("docs") {
+        Some(Value::Array(docs)) => docs
+            .iter()
+            .map(|doc| match doc["span"][0].as_u64().and_then(|index| index.try_into().ok()) {
+                Some(index) => Ok(index),
+                None => Err("missing span of a doc comment".to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err("`docs` is not a list".to_string()),
+        None => Box::new([]),
+    };
+
+    let stmt = match stmt["kind"].as_str() {
+        Some("Expression") => Statement::Expression { expr },
+        Some("Variable") => {
+            let mut flags: Byte = 0;
+
+            for flag in strings(&stmt["flags"], "flags")? {
+                match flag {
+                    "IsConst" => flags |= StatementFlag::IsConst.bit(),
+                    "HasType" => flags |= StatementFlag::HasType.bit(),
+                    flag => return Err(format!("unknown statement flag `{}`", flag)),
+                };
+            }
+
+            Statement::Variable { expr, flags, docs }
+        }
+        Some("Assignment") => Statement::Assignment { expr },
+        Some("Struct") => Statement::Struct { expr, docs },
+        Some("Enum") => Statement::Enum { expr, docs },
+        Some("Alias") => Statement::Alias { expr, docs },
+        Some("Error") => Statement::Error,
+        Some(kind) => return Err(format!("unknown statement kind `{}`", kind)),
+        None => return Err("missing statement kind".to_string()),
+    };
+
+    Ok(stmt)
+}
+
+fn node_from_json(node: &Value) -> Result<ExpressionNode, String> {
+    let name = node["kind"].as_str().unwrap_or_default();
+    let Some(kind) = KINDS.iter().find(|kind| format!("{:?}", kind) == name) else {
+        return Err(format!("unknown expression kind `{}`", name));
+    };
+
+    let name = node["token"].as_str().unwrap_or_default();
+    let Some(token) = TOKENS.iter().find(|token| format!("{:?}", token) == name) else {
+        return Err(format!("unknown token `{}`", name));
+    };
+
+    let Some(index) = node["span"][0].as_u64().and_then(|index| index.try_into().ok()) else {
+        return Err(format!("missing span of `{}`", name));
+    };
+
+    let Some(size) = node["size"].as_u64().and_then(|size| size.try_into().ok()) else {
+        return Err(format!("missing size of `{}`", name));
+    };
+
+    let value = match &node["value"] {
+        Value::Null => TokenValue::None,
+        Value::String(name) => TokenValue::Symbol(Symbol::intern(name)),
+        // `2.0` is read back as a float, `2` as an integer
+        Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(value), _) => TokenValue::Int(value),
+            (None, Some(value)) => TokenValue::Float(value),
+            _ => return Err(format!("invalid value of `{}`", name)),
+        },
+        _ => return Err(format!("invalid value of `{}`", name)),
+    };
+
+    Ok(ExpressionNode { index, size, token: token.clone(), kind: kind.clone(), value })
+}
+
+/// checks that the children of every node end right where its subtree starts,
+/// so the views never read past a subtree
+fn check_sizes(nodes: &[ExpressionNode]) -> Result<(), String> {
+    // the starts of the subtrees that have no parent yet
+    let mut roots: Vec<usize> = vec![];
+
+    for (i, node) in nodes.iter().enumerate() {
+        let size = match node.is_escaped() {
+            false => node.size as usize,
+            true => match i.checked_sub(1).map(|i| &nodes[i].value) {
+                Some(TokenValue::Int(size)) => usize::try_from(*size).unwrap_or(0),
+                _ => return Err(format!("missing size of the node at {}", i)),
+            },
+        };
+
+        if size == 0 || size > i + 1 {
+            return Err(format!("invalid size {} of the node at {}", size, i));
+        }
+
+        let start = i + 1 - size;
+        let mut first = i;
+
+        while let Some(&root) = roots.last().filter(|&&root| root >= start) {
+            roots.pop();
+            first = root;
+        }
+
+        if first != start {
+            return Err(format!("the size {} of the node at {} splits a subtree", size, i));
+        }
+
+        roots.push(start);
+    }
+
+    Ok(())
+}
+
+fn strings<'a>(
+    value: &'a Value,
+    field: &'a str,
+) -> Result<impl Iterator<Item = &'a str> + 'a, String> {
+    let items = match value {
+        Value::Null => &[][..],
+        Value::Array(items) => items,
+        _ => return Err(format!("`{}` is not a list", field)),
+    };
+
+    if items.iter().any(|item| !item.is_string()) {
+        return Err(format!("`{}` has to contain strings", field));
+    }
+
+    Ok(items.iter().filter_map(Value::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+
+    use crate::{lexer::tokenize, parser::parse};
+
+    use super::{from_json, to_json};
+
+    /// parses `buffer`, writes the tree as text and reads it back
+    fn round_trip(buffer: &str) {
+        let (stmts, _) = parse(tokenize(buffer));
+
+        let text = to_json(buffer, &stmts).to_string();
+        let json: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(from_json(&json).unwrap(), stmts);
+    }
+
+    #[test]
+    fn examples() {
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|extension| extension == "crap") {
+                round_trip(&fs::read_to_string(path).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn long_chain() {
+        // more than `u16::MAX` nodes, the root has an `[S]` node
+        let buffer = format!("let t = 1{}\n", " + 1".repeat(40_000));
+        round_trip(&buffer);
+
+        let text = to_json(&buffer, &parse(tokenize(&buffer)).0).to_string();
+        assert!(text.len() < buffer.len() * 60, "{} bytes", text.len());
+    }
+
+    #[test]
+    fn invalid_sizes() {
+        let buffer = "let x = 1 + 2\n";
+        let mut json = to_json(buffer, &parse(tokenize(buffer)).0);
+
+        // the name claims the `+` in front of it
+        json["statements"][0]["nodes"][3]["size"] = 2.into();
+        assert_eq!(
+            from_json(&json),
+            Err("the size 2 of the node at 3 splits a subtree".to_string())
+        );
+
+        json["statements"][0]["nodes"][3]["size"] = 9.into();
+        assert_eq!(from_json(&json), Err("invalid size 9 of the node at 3".to_string()));
+    }
+}
@@ -0,0 +1,403 @@
+use core::fmt;
+
+use crate::{
+    lexer::{
+        doc_text, lexeme,
+        tokens::{Token, TokenValue},
+    },
+    parser::precedence::{led_power, Precedence},
+    utils::bit_array::BitArray,
+};
+
+use super::{
+    expressions::{ExpressionKind, ExpressionNode},
+    statements::{Statement, StatementFlag},
+    view::ExprRef,
+};
+
+/// a subtree or a statement printed as source text, strings and doc comments are
+/// read from `buffer`, the source it was parsed from
+pub struct Printed<'a, T> {
+    buffer: &'a str,
+    node: T,
+}
+
+pub fn print<T>(buffer: &str, node: T) -> Printed<'_, T> {
+    Printed { buffer, node }
+}
+
+/// parentheses are only added where the precedence needs them
+impl fmt::Display for Printed<'_, ExprRef<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_expr(f, self.buffer, self.node)
+    }
+}
+
+/// how strong the root of a subtree binds its operands
+fn precedence(expr: ExprRef) -> Precedence {
+    use ExpressionKind::*;
+
+    let expr = expr.unparenthesized();
+
+    match expr.kind() {
+        Prefix => Precedence::Unary,
+        Binary | Ternary => led_power(expr.token()),
+        Member | Call => Precedence::Member,
+        _ => Precedence::Primary,
+    }
+}
+
+fn write_expr(f: &mut fmt::Formatter<'_>, buffer: &str, expr: ExprRef) -> fmt::Result {
+    use ExpressionKind::*;
+
+    // `(...)` is printed again where needed
+    let expr = expr.unparenthesized();
+    let node = expr.node();
+
+    match node.kind {
+        Literal => write_token(f, buffer, node),
+        Prefix => {
+            write_token(f, buffer, node)?;
+
+            // `- -1` instead of `--1`
+            let operand = expr.operand();
+            if *operand.unparenthesized().kind() == Prefix {
+                write!(f, " ")?;
+            }
+
+            write_operand(f, buffer, operand, precedence(operand) < Precedence::Unary)
+        }
+        Sufix => {
+            write_expr(f, buffer, expr.operand())?;
+            write_token(f, buffer, node)
+        }
+        Block => unreachable!("blocks are unwrapped"),
+        Binary => write_chain(f, buffer, expr),
+        Ternary => {
+            let power = Precedence::Ternary;
+            write_operand(f, buffer, expr.cond(), precedence(expr.cond()) < power)?;
+            write!(f, " ? ")?;
+            write_operand(f, buffer, expr.mhs(), precedence(expr.mhs()) <= power)?;
+            write!(f, " : ")?;
+            write_operand(f, buffer, expr.rhs(), precedence(expr.rhs()) <= power)
+        }
+        Member => {
+            // `1.x` would lex as the number `1.`
+            let lhs = expr.lhs();
+            let is_number = *lhs.unparenthesized().token() == Token::Number;
+
+            let is_weaker = precedence(lhs) < Precedence::Member;
+            write_operand(f, buffer, lhs, is_weaker || is_number)?;
+            write_token(f, buffer, node)?;
+            write_expr(f, buffer, expr.rhs())
+        }
+        Call => {
+            let operands = expr.children();
+
+            let is_weaker = precedence(operands[0]) < Precedence::Member;
+            write_operand(f, buffer, operands[0], is_weaker)?;
+            write!(f, "(")?;
+            write_list(f, buffer, &operands[1..])?;
+            write!(f, ")")
+        }
+        Struct => {
+            write_token(f, buffer, node)?;
+            write!(f, " ")?;
+            write_body(f, buffer, &expr.children())
+        }
+        Field => {
+            write_token(f, buffer, node)?;
+            write!(f, ": ")?;
+            write_expr(f, buffer, expr.operand())
+        }
+        Enum => {
+            write!(f, "enum ")?;
+            write_token(f, buffer, node)?;
+            write!(f, " ")?;
+            write_body(f, buffer, &expr.children())
+        }
+        Variant => {
+            write_token(f, buffer, node)?;
+
+            let payload = expr.children();
+            if payload.is_empty() {
+                return Ok(());
+            }
+
+            write!(f, "(")?;
+            write_list(f, buffer, &payload)?;
+            write!(f, ")")
+        }
+        Match => {
+            let operands = expr.children();
+
+            // struct literals are only allowed inside `(...)` in the subject
+            let subject = operands[0];
+            let has_struct = subject.nodes().iter().any(|node| node.kind == Struct);
+
+            write!(f, "match ")?;
+            write_operand(f, buffer, subject, has_struct)?;
+            write!(f, " ")?;
+            write_body(f, buffer, &operands[1..])
+        }
+        Arm => {
+            write_expr(f, buffer, expr.lhs())?;
+            write!(f, " => ")?;
+            write_expr(f, buffer, expr.rhs())
+        }
+    }
+}
+
+/// a chain of binary operators, left associative so `a - (b - c)` keeps its parentheses
+///
+/// the chain is written with a loop, the `(` around a weaker left side are all opened
+/// before its first operand
+fn write_chain(f: &mut fmt::Formatter<'_>, buffer: &str, expr: ExprRef) -> fmt::Result {
+    let (first, chain) = expr.chain();
+
+    // whether the left side of the operator at `i` is a weaker operator of the chain
+    let is_weaker =
+        |i: usize| i > 0 && led_power(chain[i - 1].token()) < led_power(chain[i].token());
+
+    let opened = (0..chain.len()).filter(|&i| is_weaker(i)).count();
+    write!(f, "{}", "(".repeat(opened))?;
+
+    let power = led_power(chain[0].token());
+    write_operand(f, buffer, first, precedence(first) < power)?;
+
+    for (i, expr) in chain.iter().enumerate() {
+        if is_weaker(i) {
+            write!(f, ")")?;
+        }
+
+        let power = led_power(expr.token());
+        write!(f, " ")?;
+        write_token(f, buffer, expr.node())?;
+        write!(f, " ")?;
+        write_operand(f, buffer, expr.rhs(), precedence(expr.rhs()) <= power)?;
+    }
+
+    Ok(())
+}
+
+fn write_operand(
+    f: &mut fmt::Formatter<'_>,
+    buffer: &str,
+    expr: ExprRef,
+    has_parens: bool,
+) -> fmt::Result {
+    match has_parens {
+        true => {
+            write!(f, "(")?;
+            write_expr(f, buffer, expr)?;
+            write!(f, ")")
+        }
+        false => write_expr(f, buffer, expr),
+    }
+}
+
+/// `a, b, c` without the surrounding brackets
+fn write_list(f: &mut fmt::Formatter<'_>, buffer: &str, items: &[ExprRef]) -> fmt::Result {
+    separated(f, items, |f| write!(f, ", "), |f, item| write_expr(f, buffer, *item))
+}
+
+/// calls `write_item` for every item and `write_separator` between two of them,
+/// the formatter prints its lists with it as well
+pub fn separated<W: ?Sized, T, E>(
+    output: &mut W,
+    items: &[T],
+    mut write_separator: impl FnMut(&mut W) -> Result<(), E>,
+    mut write_item: impl FnMut(&mut W, &T) -> Result<(), E>,
+) -> Result<(), E> {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write_separator(output)?;
+        }
+
+        write_item(output, item)?;
+    }
+
+    Ok(())
+}
+
+/// `{ a, b }` or `{}`
+fn write_body(f: &mut fmt::Formatter<'_>, buffer: &str, items: &[ExprRef]) -> fmt::Result {
+    if items.is_empty() {
+        return write!(f, "{{}}");
+    }
+
+    write!(f, "{{ ")?;
+    write_list(f, buffer, items)?;
+    write!(f, " }}")
+}
+
+fn write_token(f: &mut fmt::Formatter<'_>, buffer: &str, node: &ExpressionNode) -> fmt::Result {
+    match (&node.token, &node.value) {
+        (Token::String, _) => write!(f, "{}", lexeme(buffer, node.index, &node.token)),
+        (_, TokenValue::Symbol(symbol)) => write!(f, "{}", symbol),
+        (_, TokenValue::Int(value)) => write!(f, "{}", value),
+        (_, TokenValue::Float(value)) => {
+            let text = value.to_string();
+
+            // keep it a float literal
+            match text.contains('.') {
+                true => write!(f, "{}", text),
+                false => write!(f, "{}.0", text),
+            }
+        }
+        (token, TokenValue::None) => match token.text() {
+            Some(text) => write!(f, "{}", text),
+            None => write!(f, "<{}>", token),
+        },
+    }
+}
+
+impl fmt::Display for Printed<'_, &Statement> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Statement::*;
+
+        let Printed { buffer, node: stmt } = *self;
+
+        for index in stmt.docs().into_iter().flatten() {
+            for line in doc_text(buffer, *index).lines() {
+                writeln!(f, "/// {}", line)?;
+            }
+        }
+
+        let parts = stmt.expressions();
+        let part = |i: usize| print(buffer, parts[i]);
+
+        match stmt {
+            Expression { .. } | Enum { .. } => write_expr(f, buffer, parts[0]),
+            Variable { flags, .. } => {
+                // [E value][E type]?[E symbol]
+                match StatementFlag::IsConst.has(*flags) {
+                    true => write!(f, "const {}", part(parts.len() - 1))?,
+                    false => write!(f, "let {}", part(parts.len() - 1))?,
+                }
+
+                if StatementFlag::HasType.has(*flags) {
+                    write!(f, ": {}", part(1))?;
+                }
+
+                write!(f, " = {}", part(0))
+            }
+            // [E value][E target]
+            Assignment { .. } => write!(f, "{} = {}", part(1), part(0)),
+            Struct { .. } => {
+                write!(f, "struct ")?;
+                write_token(f, buffer, parts[0].node())?;
+                write!(f, " ")?;
+                write_body(f, buffer, &parts[0].children())
+            }
+            // [E type][E symbol]
+            Alias { .. } => write!(f, "type {} = {}", part(1), part(0)),
+            Error => write!(f, "<error>"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{statements::Statement, view::ExprRef},
+        lexer::{lexeme, tokenize},
+        parser::parse,
+    };
+
+    use super::print;
+
+    fn parse_one(buffer: &str) -> Statement {
+        let (mut stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}: {:?}", buffer, diagnostics);
+        assert_eq!(stmts.len(), 1, "{:?}", buffer);
+        stmts.pop().unwrap()
+    }
+
+    /// the tree without positions and parentheses, like `(+ 1 (* 2 3))`
+    fn shape(buffer: &str, expr: ExprRef) -> String {
+        let expr = expr.unparenthesized();
+        let node = expr.node();
+        let text = lexeme(buffer, node.index, &node.token);
+
+        // children are stored last to first
+        let children: Vec<String> =
+            expr.children().iter().rev().map(|child| shape(buffer, *child)).collect();
+
+        match children.is_empty() {
+            true => format!("{:?}:{}", node.kind, text),
+            false => format!("({:?}:{} {})", node.kind, text, children.join(" ")),
+        }
+    }
+
+    fn shapes(buffer: &str, stmt: &Statement) -> Vec<String> {
+        stmt.expressions().iter().map(|expr| shape(buffer, *expr)).collect()
+    }
+
+    /// prints `source`, expects `expected` and the same tree back when it is parsed again
+    fn round_trip(source: &str, expected: &str) {
+        let stmt = parse_one(source);
+        let printed = print(source, &stmt).to_string();
+        assert_eq!(printed, expected, "printed from {:?}", source);
+
+        let reparsed = parse_one(&printed);
+        assert_eq!(shapes(source, &stmt), shapes(&printed, &reparsed), "{:?}", source);
+    }
+
+    #[test]
+    fn binary_operators_keep_the_needed_parentheses() {
+        round_trip("1 + 2 * 3", "1 + 2 * 3");
+        round_trip("(1 + 2) * 3", "(1 + 2) * 3");
+        round_trip("1 + (2 * 3)", "1 + 2 * 3");
+        round_trip("(a - b) - c", "a - b - c");
+        round_trip("a - (b - c)", "a - (b - c)");
+        round_trip("a / (b * c) % d", "a / (b * c) % d");
+        round_trip("(a ?? b) ?? c", "a ?? b ?? c");
+        round_trip("a ?? (b ?? c)", "a ?? (b ?? c)");
+        round_trip("(a + b) ?? c", "a + b ?? c");
+        round_trip("a + (b ?? c)", "a + (b ?? c)");
+    }
+
+    #[test]
+    fn ternary_is_left_associative() {
+        round_trip("a ? b : c ? d : e", "a ? b : c ? d : e");
+        round_trip("(a ? b : c) ? d : e", "a ? b : c ? d : e");
+        round_trip("a ? b : (c ? d : e)", "a ? b : (c ? d : e)");
+        round_trip("a ? (b ? c : d) : e", "a ? (b ? c : d) : e");
+        round_trip("(a ?? b) ? c + d : e", "a ?? b ? c + d : e");
+        round_trip("a ?? (b ? c : d)", "a ?? (b ? c : d)");
+        round_trip("1 + (2 ? 3 : 4)", "1 + (2 ? 3 : 4)");
+    }
+
+    #[test]
+    fn prefix_member_and_call() {
+        round_trip("- -1", "- -1");
+        round_trip("-(-1)", "- -1");
+        round_trip("-(a + b)", "-(a + b)");
+        round_trip("(-a).b", "(-a).b");
+        round_trip("(1).x", "(1).x");
+        round_trip("(a + b).c", "(a + b).c");
+        round_trip("a?.b.c", "a?.b.c");
+        round_trip("(f)(1, 2 + 3)", "f(1, 2 + 3)");
+        round_trip("(a + b)(c)", "(a + b)(c)");
+    }
+
+    #[test]
+    fn literals_and_declarations() {
+        round_trip("let x = 2.0", "let x = 2.0");
+        round_trip(
+            "const p: Point = Point { x: 1, y: -2 }",
+            "const p: Point = Point { x: 1, y: -2 }",
+        );
+        round_trip("Shape.Rect(1.5, 2.0)", "Shape.Rect(1.5, 2.0)");
+        round_trip(
+            "match (P { x: 1 }) { P { x: 1 } => 1, _ => 2 }",
+            "match (P { x: 1 }) { P { x: 1 } => 1, _ => 2 }",
+        );
+        round_trip("match a { }", "match a {}");
+        round_trip("enum E { A, B(f64, i64) }", "enum E { A, B(f64, i64) }");
+        round_trip("struct S { a: i64, b: S? }", "struct S { a: i64, b: S? }");
+        round_trip("type T = f64", "type T = f64");
+        round_trip("/// docs\nlet x = 1", "/// docs\nlet x = 1");
+    }
+}
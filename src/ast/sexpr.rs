@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+use crate::{
+    lexer::{doc_text, lexeme, tokens::TokenValue},
+    utils::bit_array::BitArray,
+};
+
+use super::{
+    expressions::ExpressionKind,
+    statements::{Statement, StatementFlag},
+    view::ExprRef,
+};
+
+// --- format --- //
+// statement: (kind (flags name...)? (docs "text"...)? node...)
+// node:      (kind token "text" start:end value? node...)
+//
+// the names are the same as in the json format, strings are quoted with `\` escapes,
+// children are in storage order and indented by two spaces per level, except for the
+// left side of a chain of binary operators, which stays at the level of its operator
+
+/// the syntax tree of a program, one statement per paragraph
+pub fn to_sexpr(buffer: &str, stmts: &[Statement]) -> String {
+    let mut output = String::new();
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+
+        write_statement(&mut output, buffer, stmt);
+        output.push('\n');
+    }
+
+    output
+}
+
+fn write_statement(output: &mut String, buffer: &str, stmt: &Statement) {
+    output.push('(');
+    output.push_str(stmt.name());
+
+    if let Statement::Variable { flags, .. } = stmt {
+        output.push_str(" (flags");
+
+        if StatementFlag::IsConst.has(*flags) {
+            output.push_str(" IsConst");
+        }
+
+        if StatementFlag::HasType.has(*flags) {
+            output.push_str(" HasType");
+        }
+
+        output.push(')');
+    }
+
+    if let Some(docs) = stmt.docs().filter(|docs| !docs.is_empty()) {
+        output.push_str(" (docs");
+
+        for doc in docs.iter() {
+            _ = write!(output, " {:?}", doc_text(buffer, *doc));
+        }
+
+        output.push(')');
+    }
+
+    for expr in stmt.expressions() {
+        write_node(output, buffer, expr, 1);
+    }
+
+    output.push(')');
+}
+
+fn write_node(output: &mut String, buffer: &str, expr: ExprRef, depth: usize) {
+    if *expr.kind() != ExpressionKind::Binary {
+        write_header(output, buffer, expr, depth);
+
+        for child in expr.children() {
+            write_node(output, buffer, child, depth + 1);
+        }
+
+        output.push(')');
+        return;
+    }
+
+    // the chain is as deep as it is long, it is written with loops
+    let (first, chain) = expr.chain();
+
+    for expr in chain.iter().rev() {
+        write_header(output, buffer, *expr, depth);
+    }
+
+    write_node(output, buffer, first, depth + 1);
+
+    for expr in chain {
+        write_node(output, buffer, expr.rhs(), depth + 1);
+        output.push(')');
+    }
+}
+
+/// the node without its children and the closing parenthesis
+fn write_header(output: &mut String, buffer: &str, expr: ExprRef, depth: usize) {
+    let node = expr.node();
+    let text = lexeme(buffer, node.index, &node.token);
+    let end = node.index as usize + text.len();
+
+    output.push('\n');
+    output.push_str(&"  ".repeat(depth));
+
+    _ = write!(
+        output,
+        "({:?} {:?} {:?} {}:{}",
+        node.kind, node.token, text, node.index, end
+    );
+
+    match node.value {
+        TokenValue::None => (),
+        TokenValue::Symbol(symbol) => _ = write!(output, " {:?}", symbol.as_str()),
+        TokenValue::Int(value) => _ = write!(output, " {}", value),
+        TokenValue::Float(value) => _ = write!(output, " {:?}", value),
+    }
+}
@@ -1,19 +1,32 @@
-use crate::utils::bit_array::{Bit, BitArray, Byte, BIT_1};
+use std::io::{self, Write};
 
-use super::expressions::{debug_expr, Expression};
+use crate::utils::bit_array::{Bit, BitArray, Byte, BIT_1, BIT_2};
+
+use super::{
+    expressions::{debug_expr, subtree_size, Expression, ExpressionNode},
+    printer::print,
+    view::{ExprMut, ExprRef},
+};
 
 pub enum StatementFlag {
     IsConst,
+    HasType,
 }
 
 impl BitArray for StatementFlag {
     fn bit(&self) -> Bit {
         match self {
             StatementFlag::IsConst => BIT_1,
+            StatementFlag::HasType => BIT_2,
         }
     }
 }
 
+// the `///` and `/** */` comments in front of a declaration by their byte offset,
+// see `lexer::doc_text`
+pub type Docs = Box<[u32]>;
+
+#[derive(Debug, PartialEq)]
 pub enum Statement {
     // --- legend --- //
     // [E]: expression
@@ -29,33 +42,156 @@ pub enum Statement {
     },
 
     Variable {
-        expr: Expression, // [E value][E symbol]
+        expr: Expression, // [E value][E type]?[E symbol]
         flags: Byte,
+        docs: Docs,
+    },
+
+    Assignment {
+        expr: Expression, // [E value][E target]
     },
+
+    Struct {
+        expr: Expression, // [E field]...[T name]
+        docs: Docs,
+    },
+
+    Enum {
+        expr: Expression, // [E variant]...[T name]
+        docs: Docs,
+    },
+
+    Alias {
+        expr: Expression, // [E type][E symbol]
+        docs: Docs,
+    },
+
+    // a statement with a syntax error, the parser reports it
+    Error,
 }
 
-pub fn debug_stmt(stmt: &Statement) {
-    use Statement::*;
+impl Statement {
+    pub fn expr(&self) -> &[ExpressionNode] {
+        use Statement::*;
 
-    match stmt {
-        Expression { expr } => {
-            println!("Expression Statement");
-            debug_expr(expr, &mut vec![1]);
+        match self {
+            Expression { expr }
+            | Variable { expr, .. }
+            | Assignment { expr }
+            | Struct { expr, .. }
+            | Enum { expr, .. }
+            | Alias { expr, .. } => expr,
+            Error => &[],
+        }
+    }
+
+    /// the top level expressions in storage order, e.g. `[value, type?, symbol]`
+    pub fn expressions(&self) -> Vec<ExprRef<'_>> {
+        let mut expressions = vec![];
+        let mut rest = self.expr();
+
+        while !rest.is_empty() {
+            let (front, expr) = rest.split_at(rest.len() - subtree_size(rest, rest.len()));
+            expressions.push(ExprRef::new(expr));
+            rest = front;
+        }
+
+        expressions.reverse();
+        expressions
+    }
+
+    pub fn expressions_mut(&mut self) -> Vec<ExprMut<'_>> {
+        let mut expressions = vec![];
+        let mut rest = self.expr_mut();
+
+        while !rest.is_empty() {
+            let start = rest.len() - subtree_size(rest, rest.len());
+            let (front, expr) = rest.split_at_mut(start);
+            expressions.push(ExprMut::new(expr));
+            rest = front;
+        }
+
+        expressions.reverse();
+        expressions
+    }
+
+    /// the name of the variant, as written by the json and s-expression dumps
+    pub fn name(&self) -> &'static str {
+        use Statement::*;
+
+        match self {
+            Expression { .. } => "Expression",
+            Variable { .. } => "Variable",
+            Assignment { .. } => "Assignment",
+            Struct { .. } => "Struct",
+            Enum { .. } => "Enum",
+            Alias { .. } => "Alias",
+            Error => "Error",
         }
-        Variable { expr, flags } => {
-            print!("Variable Statement ");
+    }
 
-            let is_const = StatementFlag::IsConst.has(*flags);
+    /// the doc comments of a declaration
+    pub fn docs(&self) -> Option<&Docs> {
+        use Statement::*;
 
-            if is_const {
-                print!("(const)")
-            } else {
-                print!("(let)")
+        match self {
+            Variable { docs, .. } | Struct { docs, .. } | Enum { docs, .. } | Alias { docs, .. } => {
+                Some(docs)
             }
+            Expression { .. } | Assignment { .. } | Error => None,
+        }
+    }
 
-            print!("\n");
+    pub fn docs_mut(&mut self) -> Option<&mut Docs> {
+        use Statement::*;
 
-            debug_expr(expr, &mut vec![2]);
+        match self {
+            Variable { docs, .. } | Struct { docs, .. } | Enum { docs, .. } | Alias { docs, .. } => {
+                Some(docs)
+            }
+            Expression { .. } | Assignment { .. } | Error => None,
         }
     }
+
+    pub fn expr_mut(&mut self) -> &mut [ExpressionNode] {
+        use Statement::*;
+
+        match self {
+            Expression { expr }
+            | Variable { expr, .. }
+            | Assignment { expr }
+            | Struct { expr, .. }
+            | Enum { expr, .. }
+            | Alias { expr, .. } => expr,
+            Error => &mut [],
+        }
+    }
+}
+
+/// prints the statement as source above its syntax tree
+pub fn debug_stmt(output: &mut impl Write, buffer: &str, stmt: &Statement) -> io::Result<()> {
+    use Statement::*;
+
+    writeln!(output, "{}", print(buffer, stmt))?;
+
+    match stmt {
+        Expression { .. } => writeln!(output, "Expression Statement")?,
+        Variable { flags, .. } => match StatementFlag::IsConst.has(*flags) {
+            true => writeln!(output, "Variable Statement (const)")?,
+            false => writeln!(output, "Variable Statement (let)")?,
+        },
+        Assignment { .. } => writeln!(output, "Assignment Statement")?,
+        Struct { .. } => writeln!(output, "Struct Statement")?,
+        Enum { .. } => writeln!(output, "Enum Statement")?,
+        Alias { .. } => writeln!(output, "Alias Statement")?,
+        Error => writeln!(output, "Error Statement")?,
+    }
+
+    // last to first, like they are stored
+    let expressions = stmt.expressions();
+    for (i, expr) in expressions.iter().rev().enumerate() {
+        debug_expr(output, *expr, "", i + 1 == expressions.len())?;
+    }
+
+    Ok(())
 }
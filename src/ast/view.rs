@@ -0,0 +1,217 @@
+use core::fmt;
+
+use crate::{lexer::tokens::Token, utils::interner::Symbol};
+
+use super::expressions::{subtree_size, ExpressionKind, ExpressionNode};
+
+/// a subtree of a flattened expression, the root node is the last one
+#[derive(Clone, Copy, PartialEq)]
+pub struct ExprRef<'a> {
+    nodes: &'a [ExpressionNode],
+}
+
+impl<'a> ExprRef<'a> {
+    pub fn new(nodes: &'a [ExpressionNode]) -> ExprRef<'a> {
+        debug_assert_eq!(subtree_size(nodes, nodes.len()), nodes.len());
+        ExprRef { nodes }
+    }
+
+    /// all nodes of the subtree in postfix order
+    pub fn nodes(self) -> &'a [ExpressionNode] {
+        self.nodes
+    }
+
+    /// the root node
+    pub fn node(self) -> &'a ExpressionNode {
+        &self.nodes[self.nodes.len() - 1]
+    }
+
+    pub fn kind(self) -> &'a ExpressionKind {
+        &self.node().kind
+    }
+
+    pub fn token(self) -> &'a Token {
+        &self.node().token
+    }
+
+    pub fn index(self) -> u32 {
+        self.node().index
+    }
+
+    pub fn symbol(self) -> Symbol {
+        self.node().symbol()
+    }
+
+    /// the end of the last child, `[S]` and the root are not part of the children
+    fn operands_end(self) -> usize {
+        match self.node().is_escaped() {
+            true => self.nodes.len() - 2,
+            false => self.nodes.len() - 1,
+        }
+    }
+
+    /// the child whose root is right in front of `end`
+    fn child(self, end: usize) -> ExprRef<'a> {
+        let start = end - subtree_size(self.nodes, end);
+        ExprRef::new(&self.nodes[start..end])
+    }
+
+    /// the direct children in storage order, e.g. `[R, M, L]` of a `Ternary`
+    pub fn children(self) -> Vec<ExprRef<'a>> {
+        let mut children = vec![];
+        let mut end = self.operands_end();
+
+        while end > 0 {
+            let child = self.child(end);
+            end -= child.nodes.len();
+            children.push(child);
+        }
+
+        children.reverse();
+        children
+    }
+
+    pub fn arity(self) -> usize {
+        let mut end = self.operands_end();
+        let mut count = 0;
+
+        while end > 0 {
+            end -= subtree_size(self.nodes, end);
+            count += 1;
+        }
+
+        count
+    }
+
+    /// the only child of a `Prefix`, `Sufix`, `Block` or `Field`
+    pub fn operand(self) -> ExprRef<'a> {
+        use ExpressionKind::*;
+
+        match self.kind() {
+            Prefix | Sufix | Block | Field => self.child(self.operands_end()),
+            kind => panic!("expected a unary expression, found {:?}", kind),
+        }
+    }
+
+    /// `L` of a `Binary`, `Member` or `Arm`, the condition of a `Ternary`
+    /// and the operand of the unary kinds
+    pub fn lhs(self) -> ExprRef<'a> {
+        use ExpressionKind::*;
+
+        match self.kind() {
+            Binary | Member | Arm => self.child(self.operands_end() - self.rhs().nodes.len()),
+            Ternary => self.cond(),
+            _ => self.operand(),
+        }
+    }
+
+    /// `M` of a `Ternary`, the value when the condition holds
+    pub fn mhs(self) -> ExprRef<'a> {
+        match self.kind() {
+            ExpressionKind::Ternary => self.child(self.operands_end() - self.cond().nodes.len()),
+            kind => panic!("expected a ternary expression, found {:?}", kind),
+        }
+    }
+
+    /// `R` of a `Binary`, `Member`, `Arm` or `Ternary`
+    pub fn rhs(self) -> ExprRef<'a> {
+        use ExpressionKind::*;
+
+        match self.kind() {
+            Binary | Member | Arm => self.child(self.operands_end()),
+            Ternary => {
+                let end = self.operands_end() - self.cond().nodes.len();
+                self.child(end - self.mhs().nodes.len())
+            }
+            kind => panic!("expected a binary expression, found {:?}", kind),
+        }
+    }
+
+    /// the condition of a `Ternary`
+    pub fn cond(self) -> ExprRef<'a> {
+        match self.kind() {
+            ExpressionKind::Ternary => self.child(self.operands_end()),
+            kind => panic!("expected a ternary expression, found {:?}", kind),
+        }
+    }
+
+    /// a chain of binary operators like `1 + 2 + 3` and the operand at its start,
+    /// the operators from the innermost one out
+    ///
+    /// the tree of a chain is as deep as it is long, it is walked with a loop
+    /// instead of a recursion down the left side
+    pub fn chain(self) -> (ExprRef<'a>, Vec<ExprRef<'a>>) {
+        let mut chain = vec![self];
+        let mut lhs = self.lhs();
+
+        while *lhs.kind() == ExpressionKind::Binary {
+            chain.push(lhs);
+            lhs = lhs.lhs();
+        }
+
+        chain.reverse();
+        (lhs, chain)
+    }
+
+    /// the subtree without the `(...)` around it
+    pub fn unparenthesized(mut self) -> ExprRef<'a> {
+        while *self.kind() == ExpressionKind::Block {
+            self = self.operand();
+        }
+
+        self
+    }
+}
+
+impl fmt::Debug for ExprRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.node())
+    }
+}
+
+/// a subtree of a flattened expression whose nodes can be changed in place
+pub struct ExprMut<'a> {
+    nodes: &'a mut [ExpressionNode],
+}
+
+impl<'a> ExprMut<'a> {
+    pub fn new(nodes: &'a mut [ExpressionNode]) -> ExprMut<'a> {
+        debug_assert_eq!(subtree_size(nodes, nodes.len()), nodes.len());
+        ExprMut { nodes }
+    }
+
+    pub fn as_ref(&self) -> ExprRef<'_> {
+        ExprRef::new(self.nodes)
+    }
+
+    /// a shorter lived copy, to keep using `self` after passing it on
+    pub fn reborrow(&mut self) -> ExprMut<'_> {
+        ExprMut::new(self.nodes)
+    }
+
+    pub fn node(&self) -> &ExpressionNode {
+        &self.nodes[self.nodes.len() - 1]
+    }
+
+    pub fn node_mut(&mut self) -> &mut ExpressionNode {
+        let last = self.nodes.len() - 1;
+        &mut self.nodes[last]
+    }
+
+    /// the direct children in storage order
+    pub fn children_mut(&mut self) -> Vec<ExprMut<'_>> {
+        let mut children = vec![];
+        let end = self.as_ref().operands_end();
+        let mut rest = &mut self.nodes[..end];
+
+        while !rest.is_empty() {
+            let start = rest.len() - subtree_size(rest, rest.len());
+            let (front, child) = rest.split_at_mut(start);
+            children.push(ExprMut::new(child));
+            rest = front;
+        }
+
+        children.reverse();
+        children
+    }
+}
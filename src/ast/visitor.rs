@@ -0,0 +1,136 @@
+use super::{
+    statements::Statement,
+    view::{ExprMut, ExprRef},
+};
+
+/// walks statements and expressions, every method defaults to visiting the children,
+/// an override calls the matching `walk_*` function to keep going deeper
+pub trait Visitor<'a> {
+    fn visit_stmt(&mut self, stmt: &'a Statement) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: ExprRef<'a>) {
+        walk_expr(self, expr);
+    }
+}
+
+/// visits the top level expressions of a statement, in storage order
+pub fn walk_stmt<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, stmt: &'a Statement) {
+    for expr in stmt.expressions() {
+        visitor.visit_expr(expr);
+    }
+}
+
+/// visits the direct children of an expression, in storage order
+pub fn walk_expr<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, expr: ExprRef<'a>) {
+    for child in expr.children() {
+        visitor.visit_expr(child);
+    }
+}
+
+/// like `Visitor`, but the nodes can be changed in place
+pub trait VisitorMut {
+    fn visit_stmt_mut(&mut self, stmt: &mut Statement) {
+        walk_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: ExprMut<'_>) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+pub fn walk_stmt_mut<V: VisitorMut + ?Sized>(visitor: &mut V, stmt: &mut Statement) {
+    for expr in stmt.expressions_mut() {
+        visitor.visit_expr_mut(expr);
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(visitor: &mut V, mut expr: ExprMut<'_>) {
+    for child in expr.children_mut() {
+        visitor.visit_expr_mut(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{statements::Statement, view::ExprMut},
+        lexer::tokenize,
+        parser::parse,
+    };
+
+    use super::{walk_expr_mut, walk_stmt_mut, VisitorMut};
+
+    fn parse_source(buffer: &str) -> Vec<Statement> {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        stmts
+    }
+
+    /// moves every node by `delta` bytes, like `incremental` moves the statements behind an edit
+    struct Shift {
+        delta: u32,
+        visited: usize,
+    }
+
+    impl VisitorMut for Shift {
+        fn visit_expr_mut(&mut self, mut expr: ExprMut<'_>) {
+            expr.node_mut().index += self.delta;
+            self.visited += 1;
+            walk_expr_mut(self, expr);
+        }
+    }
+
+    #[test]
+    fn shifted_nodes_match_the_shifted_source() {
+        let source = "\
+let p: Point? = Point { x: 1, y: -2 }
+p?.x ?? f(1, 2 * (3 + 4)) ? 5 : 6
+enum Shape { Circle(f64), Empty }
+match Shape.Circle(1.5) { Shape.Circle(r) => r, _ => 0.0 }
+";
+        let padding = "\n\n  ";
+
+        let mut stmts = parse_source(source);
+        let expected = parse_source(&format!("{}{}", padding, source));
+
+        let mut shift = Shift {
+            delta: padding.len() as u32,
+            visited: 0,
+        };
+        for stmt in stmts.iter_mut() {
+            shift.visit_stmt_mut(stmt);
+        }
+
+        assert_eq!(stmts, expected);
+
+        // every node is visited once
+        let nodes: usize = stmts.iter().map(|stmt| stmt.expr().len()).sum();
+        assert_eq!(shift.visited, nodes);
+    }
+
+    /// an override that does not walk on leaves the children alone
+    #[test]
+    fn skipped_children_stay_unchanged() {
+        struct Roots(u32);
+
+        impl VisitorMut for Roots {
+            fn visit_expr_mut(&mut self, mut expr: ExprMut<'_>) {
+                expr.node_mut().index += self.0;
+            }
+        }
+
+        let mut stmts = parse_source("1 + 2 * 3");
+        let before = stmts[0].expr().to_vec();
+
+        walk_stmt_mut(&mut Roots(10), &mut stmts[0]);
+
+        let after = stmts[0].expr();
+        assert_eq!(
+            after.last().unwrap().index,
+            before.last().unwrap().index + 10
+        );
+        assert_eq!(after[..after.len() - 1], before[..before.len() - 1]);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{
+    diagnostics::Diagnostic,
+    ir::{Function, Ty, Value},
+    utils::interner::Symbol,
+};
+
+pub mod c;
+pub mod layout;
+pub mod wasm;
+pub mod x86_64;
+
+// --- overview --- //
+// the backends translate a verified program of the ir, every value of a function
+// becomes a local or a register and every block a label
+//
+// the output of a program is the same as the one of the interpreter, a division
+// by zero reports the error after the values before it, a c program and an x86-64
+// executable write it to stderr and exit with `1`, a wasm module calls the `error`
+// import of its host
+
+/// `line:column` of the token at `index`, the index itself for hand-written ir
+pub fn location(source: Option<&str>, index: u32) -> String {
+    match source {
+        Some(source) => {
+            let (line, column) = Diagnostic::error(index, String::new()).position(source);
+            format!("{}:{}", line, column)
+        }
+        None => format!("@{}", index),
+    }
+}
+
+/// a name that only has ascii letters, digits and `_`, other characters are escaped
+pub fn identifier(name: Symbol) -> String {
+    let mut identifier = String::new();
+
+    for char in name.as_str().chars() {
+        match char {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => identifier.push(char),
+            char => identifier.push_str(&format!("_u{:x}_", char as u32)),
+        }
+    }
+
+    identifier
+}
+
+/// `x_3` for the binding `x`, `v3` for a temporary
+pub fn local(function: &Function, value: Value) -> String {
+    match function.instruction(value).name {
+        Some(name) if !name.as_str().starts_with('_') => {
+            format!("{}_{}", identifier(name), value.0)
+        }
+        _ => format!("v{}", value.0),
+    }
+}
+
+/// a string literal of c and the gnu assembler, other bytes than printable ascii
+/// are escaped
+pub fn quote(text: &str) -> String {
+    let mut literal = String::from("\"");
+
+    for byte in text.bytes() {
+        match byte {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' => literal.push(byte as char),
+            byte => literal.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+
+    literal.push('"');
+    literal
+}
+
+/// a unique name for every type, e.g. `o_s_Point` for `Point?`
+pub fn mangle(ty: &Ty) -> String {
+    match ty {
+        Ty::Unit => "unit".to_string(),
+        Ty::Int => "i64".to_string(),
+        Ty::Float => "f64".to_string(),
+        Ty::Struct(name) => format!("s_{}", identifier(*name)),
+        Ty::Enum(name) => format!("e_{}", identifier(*name)),
+        Ty::Optional(ty) => format!("o_{}", mangle(ty)),
+    }
+}
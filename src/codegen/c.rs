@@ -0,0 +1,547 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::ir::{BinaryOp, BlockId, Constant, Function, InstKind, Program, Terminator, Ty, Value};
+
+use super::{identifier, local, location, mangle, quote};
+
+// --- layout --- //
+// struct P { x: i64 }         -> typedef struct s_P { int64_t f_x; } s_P;
+// enum E { A(f64), B }        -> typedef struct e_E { int64_t tag; union { struct { double p0; } v0; } as; } e_E;
+// T?                          -> typedef struct o_T { int64_t some; T value; } o_T;
+//
+// `main` calls `crap_run`, the top-level statements, define `CRAP_NO_MAIN` to
+// link the program into other code and call `crap_run` from there
+//
+// signed overflow is undefined in c, the integer arithmetic goes through `uint64_t`
+
+const RUNTIME: &str = r#"#include <inttypes.h>
+#include <math.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+static inline int64_t crap_add(int64_t lhs, int64_t rhs) { return (int64_t)((uint64_t)lhs + (uint64_t)rhs); }
+static inline int64_t crap_sub(int64_t lhs, int64_t rhs) { return (int64_t)((uint64_t)lhs - (uint64_t)rhs); }
+static inline int64_t crap_mul(int64_t lhs, int64_t rhs) { return (int64_t)((uint64_t)lhs * (uint64_t)rhs); }
+static inline int64_t crap_neg(int64_t value) { return (int64_t)(0 - (uint64_t)value); }
+
+static inline void crap_division_by_zero(const char *location) {
+    fflush(stdout);
+    fprintf(stderr, "error: division by zero (at %s)\n", location);
+    exit(1);
+}
+
+/* `INT64_MIN / -1` overflows, it wraps to `INT64_MIN` like in the interpreter */
+static inline int64_t crap_div(int64_t lhs, int64_t rhs, const char *location) {
+    if (rhs == 0) crap_division_by_zero(location);
+    return rhs == -1 ? crap_neg(lhs) : lhs / rhs;
+}
+
+static inline int64_t crap_rem(int64_t lhs, int64_t rhs, const char *location) {
+    if (rhs == 0) crap_division_by_zero(location);
+    return rhs == -1 ? 0 : lhs % rhs;
+}
+
+static inline void print_i64(int64_t value) { printf("%" PRId64, value); }
+
+/* the shortest digits that read back as the same value, formatted like `{:?}` in rust */
+static inline void print_f64(double value) {
+    char buffer[40], digits[20];
+    int precision, count = 0, exponent, i;
+    const char *p;
+
+    if (isnan(value)) { fputs("NaN", stdout); return; }
+    if (isinf(value)) { fputs(value < 0 ? "-inf" : "inf", stdout); return; }
+    if (value == 0) { fputs(signbit(value) ? "-0.0" : "0.0", stdout); return; }
+
+    for (precision = 1; precision < 17; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+        if (strtod(buffer, NULL) == value) break;
+    }
+    snprintf(buffer, sizeof buffer, "%.*e", precision - 1, value);
+
+    p = buffer;
+    if (*p == '-') { putchar('-'); p++; }
+    for (; *p != 'e'; p++) if (*p != '.') digits[count++] = *p;
+    exponent = atoi(p + 1);
+    while (count > 1 && digits[count - 1] == '0') count--;
+
+    if (exponent < -4 || exponent >= 16) {
+        putchar(digits[0]);
+        if (count > 1) { putchar('.'); fwrite(digits + 1, 1, count - 1, stdout); }
+        printf("e%d", exponent);
+    } else if (exponent < 0) {
+        fputs("0.", stdout);
+        for (i = -1; i > exponent; i--) putchar('0');
+        fwrite(digits, 1, count, stdout);
+    } else {
+        for (i = 0; i <= exponent; i++) putchar(i < count ? digits[i] : '0');
+        putchar('.');
+        if (count > exponent + 1) fwrite(digits + exponent + 1, 1, count - exponent - 1, stdout);
+        else putchar('0');
+    }
+}
+"#;
+
+/// a verified program as c99 source, `source` is the code it was lowered from
+pub fn to_c(program: &Program, source: Option<&str>) -> String {
+    let mut generator = Generator {
+        program,
+        source,
+        output: String::new(),
+        types: HashSet::new(),
+        printers: HashSet::new(),
+    };
+
+    generator.output.push_str(RUNTIME);
+
+    let mut types = vec![];
+    for decl in program.structs.iter() {
+        types.push(Ty::Struct(decl.name));
+    }
+    for decl in program.enums.iter() {
+        types.push(Ty::Enum(decl.name));
+    }
+    for function in program.functions.iter() {
+        types.extend(
+            function
+                .instructions
+                .iter()
+                .map(|instruction| instruction.ty.clone()),
+        );
+    }
+
+    for ty in types.iter() {
+        generator.define_type(ty);
+    }
+
+    for function in program.functions.iter() {
+        for block in function.blocks.iter() {
+            for value in block.instructions.iter() {
+                if let InstKind::Print(printed) = function.instruction(*value).kind {
+                    generator.define_printer(&function.instruction(printed).ty);
+                }
+            }
+        }
+    }
+
+    for function in program.functions.iter() {
+        generator.write_function(function);
+    }
+
+    generator.output.push_str("\n#ifndef CRAP_NO_MAIN\n");
+    generator
+        .output
+        .push_str("int main(void) { return crap_run(); }\n");
+    generator.output.push_str("#endif\n");
+
+    generator.output
+}
+
+struct Generator<'a> {
+    program: &'a Program,
+    source: Option<&'a str>,
+    output: String,
+
+    // the types and print functions that are already defined
+    types: HashSet<Ty>,
+    printers: HashSet<Ty>,
+}
+
+/// the c type of a value
+fn c_type(ty: &Ty) -> String {
+    match ty {
+        Ty::Int => "int64_t".to_string(),
+        Ty::Float => "double".to_string(),
+        ty => mangle(ty),
+    }
+}
+
+impl Generator<'_> {
+    /// defines `ty` after the types it contains
+    fn define_type(&mut self, ty: &Ty) {
+        if matches!(ty, Ty::Unit | Ty::Int | Ty::Float) || !self.types.insert(ty.clone()) {
+            return;
+        }
+
+        let name = mangle(ty);
+        let mut body = String::new();
+
+        match ty {
+            Ty::Struct(struct_name) => {
+                let decl = self.program.struct_decl(*struct_name);
+
+                for (field, ty) in decl.fields.iter() {
+                    self.define_type(ty);
+                    _ = writeln!(body, "    {} f_{};", c_type(ty), identifier(*field));
+                }
+
+                // c has no empty structs
+                if decl.fields.is_empty() {
+                    body.push_str("    char unused;\n");
+                }
+            }
+            Ty::Enum(enum_name) => {
+                let decl = self.program.enum_decl(*enum_name);
+
+                body.push_str("    int64_t tag;\n");
+                body.push_str("    union {\n");
+                body.push_str("        char unused;\n");
+
+                for (i, (variant, payload)) in decl.variants.iter().enumerate() {
+                    if payload.is_empty() {
+                        continue;
+                    }
+
+                    let mut fields = String::new();
+                    for (j, ty) in payload.iter().enumerate() {
+                        self.define_type(ty);
+                        _ = write!(fields, "{} p{}; ", c_type(ty), j);
+                    }
+
+                    _ = writeln!(
+                        body,
+                        "        struct {{ {}}} v{}; /* {} */",
+                        fields, i, variant
+                    );
+                }
+
+                body.push_str("    } as;\n");
+            }
+            Ty::Optional(inner) => {
+                self.define_type(inner);
+                body.push_str("    int64_t some;\n");
+
+                if **inner != Ty::Unit {
+                    _ = writeln!(body, "    {} value;", c_type(inner));
+                }
+            }
+            Ty::Unit | Ty::Int | Ty::Float => unreachable!(),
+        }
+
+        _ = write!(
+            self.output,
+            "\ntypedef struct {} {{\n{}}} {};\n",
+            name, body, name
+        );
+    }
+
+    /// defines `print_<type>` after the ones it calls
+    fn define_printer(&mut self, ty: &Ty) {
+        if matches!(ty, Ty::Int | Ty::Float) || !self.printers.insert(ty.clone()) {
+            return;
+        }
+
+        let mut body = String::new();
+        let puts = |body: &mut String, text: &str| {
+            _ = writeln!(body, "    fputs({}, stdout);", quote(text));
+        };
+
+        match ty {
+            Ty::Struct(name) => {
+                let decl = self.program.struct_decl(*name);
+                puts(&mut body, &format!("{} {{ ", name));
+
+                for (i, (field, ty)) in decl.fields.iter().enumerate() {
+                    self.define_printer(ty);
+
+                    let separator = if i == 0 { "" } else { ", " };
+                    puts(&mut body, &format!("{}{}: ", separator, field));
+                    _ = writeln!(
+                        body,
+                        "    print_{}(value.f_{});",
+                        mangle(ty),
+                        identifier(*field)
+                    );
+                }
+
+                puts(&mut body, " }");
+            }
+            Ty::Enum(name) => {
+                let decl = self.program.enum_decl(*name);
+                body.push_str("    switch (value.tag) {\n");
+
+                for (i, (variant, payload)) in decl.variants.iter().enumerate() {
+                    _ = writeln!(body, "    case {}:", i);
+
+                    match payload.is_empty() {
+                        true => puts(&mut body, &format!("{}.{}", name, variant)),
+                        false => puts(&mut body, &format!("{}.{}(", name, variant)),
+                    }
+
+                    for (j, ty) in payload.iter().enumerate() {
+                        self.define_printer(ty);
+
+                        if j > 0 {
+                            puts(&mut body, ", ");
+                        }
+                        _ = writeln!(body, "    print_{}(value.as.v{}.p{});", mangle(ty), i, j);
+                    }
+
+                    if !payload.is_empty() {
+                        puts(&mut body, ")");
+                    }
+
+                    body.push_str("        break;\n");
+                }
+
+                body.push_str("    }\n");
+            }
+            Ty::Optional(inner) if **inner == Ty::Unit => {
+                body.push_str("    (void)value;\n");
+                puts(&mut body, "none");
+            }
+            Ty::Optional(inner) => {
+                self.define_printer(inner);
+
+                body.push_str("    if (!value.some) {\n");
+                body.push_str("        fputs(\"none\", stdout);\n");
+                body.push_str("        return;\n");
+                body.push_str("    }\n");
+                _ = writeln!(body, "    print_{}(value.value);", mangle(inner));
+            }
+            Ty::Unit | Ty::Int | Ty::Float => unreachable!(),
+        }
+
+        _ = write!(
+            self.output,
+            "\nstatic void print_{}({} value) {{\n{}}}\n",
+            mangle(ty),
+            c_type(ty),
+            body
+        );
+    }
+
+    fn write_function(&mut self, function: &Function) {
+        _ = write!(self.output, "\nint crap_run(void) {{\n");
+
+        // every value is a local, the bindings keep their name
+        for block in function.blocks.iter() {
+            for value in block.instructions.iter() {
+                let ty = &function.instruction(*value).ty;
+
+                if *ty != Ty::Unit {
+                    _ = writeln!(
+                        self.output,
+                        "    {} {};",
+                        c_type(ty),
+                        local(function, *value)
+                    );
+                }
+            }
+        }
+
+        let predecessors = function.predecessors();
+
+        for (i, block) in function.blocks.iter().enumerate() {
+            // the entry is only entered from the top
+            if !predecessors[i].is_empty() {
+                _ = writeln!(self.output, "bb{}:;", i);
+            }
+
+            for value in block.instructions.iter() {
+                self.write_instruction(function, *value);
+            }
+
+            let from = BlockId(i as u32);
+
+            match block.terminator {
+                Terminator::Jump(target) => {
+                    self.write_phis(function, from, target, "    ");
+                    _ = writeln!(self.output, "    goto {};", target);
+                }
+                Terminator::Branch {
+                    cond,
+                    then,
+                    otherwise,
+                } => {
+                    _ = writeln!(self.output, "    if ({}) {{", local(function, cond));
+                    self.write_phis(function, from, then, "        ");
+                    _ = writeln!(self.output, "        goto {};", then);
+                    self.output.push_str("    } else {\n");
+                    self.write_phis(function, from, otherwise, "        ");
+                    _ = writeln!(self.output, "        goto {};", otherwise);
+                    self.output.push_str("    }\n");
+                }
+                Terminator::Return => self.output.push_str("    return 0;\n"),
+                Terminator::Unreachable => self.output.push_str("    abort();\n"),
+            }
+        }
+
+        self.output.push_str("}\n");
+    }
+
+    /// assigns the phis of `to` for the edge from `from`,
+    /// through temporaries when a phi reads another one of the same block
+    fn write_phis(&mut self, function: &Function, from: BlockId, to: BlockId, indent: &str) {
+        let copies = function.phi_moves(from, to);
+
+        let phis: Vec<Value> = copies.iter().map(|(phi, _)| *phi).collect();
+        let is_parallel = copies.iter().any(|(_, source)| phis.contains(source));
+
+        if !is_parallel {
+            for (phi, source) in copies {
+                let (phi, source) = (local(function, phi), local(function, source));
+                _ = writeln!(self.output, "{}{} = {};", indent, phi, source);
+            }
+
+            return;
+        }
+
+        _ = writeln!(self.output, "{}{{", indent);
+        for (i, (phi, source)) in copies.iter().enumerate() {
+            let ty = c_type(&function.instruction(*phi).ty);
+            _ = writeln!(
+                self.output,
+                "{}    {} t{} = {};",
+                indent,
+                ty,
+                i,
+                local(function, *source)
+            );
+        }
+        for (i, (phi, _)) in copies.iter().enumerate() {
+            _ = writeln!(
+                self.output,
+                "{}    {} = t{};",
+                indent,
+                local(function, *phi),
+                i
+            );
+        }
+        _ = writeln!(self.output, "{}}}", indent);
+    }
+
+    fn write_instruction(&mut self, function: &Function, value: Value) {
+        let instruction = function.instruction(value);
+        let name = local(function, value);
+        let ty = &instruction.ty;
+        let operand = |value: &Value| local(function, *value);
+
+        let line = match &instruction.kind {
+            InstKind::Const(Constant::Int(constant)) => match *constant {
+                i64::MIN => format!("{} = INT64_MIN;", name),
+                constant => format!("{} = INT64_C({});", name, constant),
+            },
+            InstKind::Const(Constant::Float(constant)) => {
+                format!("{} = {};", name, float(*constant))
+            }
+            InstKind::Const(Constant::None) => format!("{} = ({}){{ 0 }};", name, c_type(ty)),
+            InstKind::Copy(source) => format!("{} = {};", name, operand(source)),
+            InstKind::Neg(source) if *ty == Ty::Int => {
+                format!("{} = crap_neg({});", name, operand(source))
+            }
+            InstKind::Neg(source) => format!("{} = -{};", name, operand(source)),
+            InstKind::Binary {
+                op,
+                lhs,
+                rhs,
+                index,
+            } => {
+                let (lhs, rhs) = (operand(lhs), operand(rhs));
+                let at = quote(&location(self.source, *index));
+
+                let expression = match (op, self.operand_type(function, value)) {
+                    (BinaryOp::Eq, _) => format!("{} == {}", lhs, rhs),
+                    (BinaryOp::Ne, _) => format!("{} != {}", lhs, rhs),
+                    (BinaryOp::Add, Ty::Int) => format!("crap_add({}, {})", lhs, rhs),
+                    (BinaryOp::Sub, Ty::Int) => format!("crap_sub({}, {})", lhs, rhs),
+                    (BinaryOp::Mul, Ty::Int) => format!("crap_mul({}, {})", lhs, rhs),
+                    (BinaryOp::Div, Ty::Int) => format!("crap_div({}, {}, {})", lhs, rhs, at),
+                    (BinaryOp::Rem, Ty::Int) => format!("crap_rem({}, {}, {})", lhs, rhs, at),
+                    (BinaryOp::Add, _) => format!("{} + {}", lhs, rhs),
+                    (BinaryOp::Sub, _) => format!("{} - {}", lhs, rhs),
+                    (BinaryOp::Mul, _) => format!("{} * {}", lhs, rhs),
+                    (BinaryOp::Div, _) => format!("{} / {}", lhs, rhs),
+                    (BinaryOp::Rem, _) => format!("fmod({}, {})", lhs, rhs),
+                };
+
+                format!("{} = {};", name, expression)
+            }
+            InstKind::Struct(fields) => {
+                let fields: Vec<String> = fields.iter().map(operand).collect();
+                match fields.is_empty() {
+                    true => format!("{} = ({}){{ 0 }};", name, c_type(ty)),
+                    false => format!("{} = ({}){{ {} }};", name, c_type(ty), fields.join(", ")),
+                }
+            }
+            InstKind::Field { value, field } => {
+                format!(
+                    "{} = {}.f_{};",
+                    name,
+                    operand(value),
+                    self.field(function, *value, *field)
+                )
+            }
+            InstKind::SetField { value, field, to } => format!(
+                "{} = {}; {}.f_{} = {};",
+                name,
+                operand(value),
+                name,
+                self.field(function, *value, *field),
+                operand(to)
+            ),
+            InstKind::Variant { variant, payload } => {
+                let mut line = format!("{}.tag = {};", name, variant);
+                for (i, value) in payload.iter().enumerate() {
+                    _ = write!(
+                        line,
+                        " {}.as.v{}.p{} = {};",
+                        name,
+                        variant,
+                        i,
+                        operand(value)
+                    );
+                }
+                line
+            }
+            InstKind::Tag(value) => format!("{} = {}.tag;", name, operand(value)),
+            InstKind::Payload {
+                value,
+                variant,
+                index,
+            } => format!("{} = {}.as.v{}.p{};", name, operand(value), variant, index),
+            InstKind::IsNone(value) => format!("{} = !{}.some;", name, operand(value)),
+            InstKind::Wrap(value) => {
+                format!("{}.some = 1; {}.value = {};", name, name, operand(value))
+            }
+            InstKind::Unwrap(value) => format!("{} = {}.value;", name, operand(value)),
+
+            // assigned at the end of the predecessors
+            InstKind::Phi(_) => return,
+            InstKind::Print(value) => {
+                let ty = &function.instruction(*value).ty;
+                format!("print_{}({}); putchar('\\n');", mangle(ty), operand(value))
+            }
+        };
+
+        _ = writeln!(self.output, "    {}", line);
+    }
+
+    /// the type of the left operand of a binary instruction
+    fn operand_type(&self, function: &Function, value: Value) -> Ty {
+        match function.instruction(value).kind {
+            InstKind::Binary { lhs, .. } => function.instruction(lhs).ty.clone(),
+            _ => function.instruction(value).ty.clone(),
+        }
+    }
+
+    /// the c name of a field of the struct `value`
+    fn field(&self, function: &Function, value: Value, field: u32) -> String {
+        let Ty::Struct(name) = function.instruction(value).ty else {
+            unreachable!("field of a value that is not a struct");
+        };
+
+        identifier(self.program.struct_decl(name).fields[field as usize].0)
+    }
+}
+
+/// a literal that reads back as the same value
+fn float(value: f64) -> String {
+    match value {
+        value if value.is_nan() => "NAN".to_string(),
+        value if value.is_infinite() && value > 0.0 => "INFINITY".to_string(),
+        value if value.is_infinite() => "-INFINITY".to_string(),
+        value => format!("{:?}", value),
+    }
+}
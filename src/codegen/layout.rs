@@ -0,0 +1,82 @@
+use crate::ir::{Program, Ty};
+
+/// the scalars of a value, in the order of
+///   struct P { x: i64, y: f64 }     -> the fields
+///   enum E { A(f64), B(i64, i64) }  -> the tag as `Int`, then the payloads of every variant
+///   T?                              -> `some` as a `Flag`, then T
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    Int,
+    Float,
+    Flag,
+}
+
+pub fn scalars(program: &Program, ty: &Ty) -> Vec<Scalar> {
+    let mut scalars = vec![];
+    flatten(program, ty, &mut scalars);
+    scalars
+}
+
+fn flatten(program: &Program, ty: &Ty, scalars: &mut Vec<Scalar>) {
+    match ty {
+        Ty::Unit => (),
+        Ty::Int => scalars.push(Scalar::Int),
+        Ty::Float => scalars.push(Scalar::Float),
+        Ty::Struct(name) => {
+            for (_, ty) in program.struct_decl(*name).fields.iter() {
+                flatten(program, ty, scalars);
+            }
+        }
+        Ty::Enum(name) => {
+            scalars.push(Scalar::Int);
+
+            for (_, payload) in program.enum_decl(*name).variants.iter() {
+                payload.iter().for_each(|ty| flatten(program, ty, scalars));
+            }
+        }
+        Ty::Optional(inner) => {
+            scalars.push(Scalar::Flag);
+            flatten(program, inner, scalars);
+        }
+    }
+}
+
+pub fn width(program: &Program, ty: &Ty) -> usize {
+    scalars(program, ty).len()
+}
+
+/// the first scalar of a field and its width
+pub fn field(program: &Program, ty: &Ty, field: u32) -> (usize, usize) {
+    let Ty::Struct(name) = ty else {
+        unreachable!("field of a value that is not a struct");
+    };
+
+    let fields = &program.struct_decl(*name).fields;
+    let offset = fields[..field as usize]
+        .iter()
+        .map(|(_, ty)| width(program, ty))
+        .sum();
+
+    (offset, width(program, &fields[field as usize].1))
+}
+
+/// the first scalar of a payload value and its width, after the tag
+pub fn payload(program: &Program, ty: &Ty, variant: u32, index: u32) -> (usize, usize) {
+    let Ty::Enum(name) = ty else {
+        unreachable!("payload of a value that is not an enum");
+    };
+
+    let variants = &program.enum_decl(*name).variants;
+    let mut offset = 1;
+
+    for (_, payload) in variants[..variant as usize].iter() {
+        offset += payload.iter().map(|ty| width(program, ty)).sum::<usize>();
+    }
+
+    let payload = &variants[variant as usize].1;
+    for ty in payload[..index as usize].iter() {
+        offset += width(program, ty);
+    }
+
+    (offset, width(program, &payload[index as usize]))
+}
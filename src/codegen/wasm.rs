@@ -0,0 +1,815 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::ir::{
+    verifier::reverse_postorder, BinaryOp, BlockId, Constant, Function, InstKind, Program,
+    Terminator, Ty, Value,
+};
+
+use super::{
+    identifier,
+    layout::{self, Scalar},
+    local, location, mangle,
+};
+
+// --- layout --- //
+// every value is flattened into locals, e.g. `$p_3.0 i64` and `$p_3.1 f64` for
+// `struct P { x: i64, y: f64 }`, the `some` of an optional is an `i32`
+//
+// the blocks are emitted in reverse postorder, a block that is the target of a
+// `br` is the end of a wasm `block` opened at the start of the function, the ir
+// has no loops so every branch goes forward
+//
+// a branch to two small arms without side effects that join again is a `select`
+//
+// the host provides the imports of the module "crap", `examples/wasm/run.mjs`
+// runs a module with node
+
+const RUNTIME: &str = r#"  ;; writes utf-8 text from the memory
+  (import "crap" "print" (func $crap_print (param i32 i32)))
+  (import "crap" "print_i64" (func $crap_print_i64 (param i64)))
+  ;; formatted like `{:?}` in rust, e.g. `1.0` or `1e16`
+  (import "crap" "print_f64" (func $crap_print_f64 (param f64)))
+  (import "crap" "fmod" (func $crap_fmod (param f64 f64) (result f64)))
+  ;; reports the message in the memory and stops the program, it does not return
+  (import "crap" "error" (func $crap_error (param i32 i32)))
+
+  ;; `i64.div_s` traps on `i64::MIN / -1`, it wraps to `i64::MIN` like in the interpreter
+  (func $crap_div (param $lhs i64) (param $rhs i64) (param $at i32) (param $len i32) (result i64)
+    (if (i64.eqz (local.get $rhs))
+      (then (call $crap_error (local.get $at) (local.get $len)) (unreachable)))
+    (if (result i64) (i64.eq (local.get $rhs) (i64.const -1))
+      (then (i64.sub (i64.const 0) (local.get $lhs)))
+      (else (i64.div_s (local.get $lhs) (local.get $rhs)))))
+
+  (func $crap_rem (param $lhs i64) (param $rhs i64) (param $at i32) (param $len i32) (result i64)
+    (if (i64.eqz (local.get $rhs))
+      (then (call $crap_error (local.get $at) (local.get $len)) (unreachable)))
+    (i64.rem_s (local.get $lhs) (local.get $rhs)))
+"#;
+
+// the most instructions of an arm that is computed for a `select`
+const SELECT_LIMIT: usize = 4;
+
+/// a verified program without loops as a module in the text format, every
+/// function is exported, `source` is the code it was lowered from
+pub fn to_wat(program: &Program, source: Option<&str>) -> Result<String, String> {
+    let mut generator = Generator {
+        program,
+        source,
+        strings: HashMap::new(),
+        data: String::new(),
+        size: 0,
+        printers: HashSet::new(),
+        functions: String::new(),
+        body: String::new(),
+        locals: vec![],
+        depth: 0,
+    };
+
+    for function in program.functions.iter() {
+        generator.write_function(function)?;
+    }
+
+    let mut output = String::from("(module\n");
+    output.push_str(RUNTIME);
+
+    // one page is 64 KiB
+    let pages = generator.size / 65536 + 1;
+    _ = write!(output, "\n  (memory (export \"memory\") {})\n", pages);
+    _ = writeln!(output, "  (data (i32.const 0) \"{}\")", generator.data);
+
+    output.push_str(&generator.functions);
+    output.push_str(")\n");
+
+    Ok(output)
+}
+
+/// a branch whose arms are inlined, `None` is the edge straight to the join
+struct Select {
+    cond: Value,
+    then: Option<BlockId>,
+    otherwise: Option<BlockId>,
+    join: BlockId,
+}
+
+struct Generator<'a> {
+    program: &'a Program,
+    source: Option<&'a str>,
+
+    // the texts in the memory, by their offset and length
+    strings: HashMap<String, (u32, u32)>,
+    data: String,
+    size: u32,
+
+    // the print functions that are already defined
+    printers: HashSet<Ty>,
+    functions: String,
+
+    // the function being written, the locals of its values and the open `if`s
+    body: String,
+    locals: Vec<Vec<String>>,
+    depth: usize,
+}
+
+impl Generator<'_> {
+    /// the offset and length of a text in the memory
+    fn string(&mut self, text: &str) -> (u32, u32) {
+        if let Some(string) = self.strings.get(text) {
+            return *string;
+        }
+
+        let string = (self.size, text.len() as u32);
+
+        for byte in text.bytes() {
+            match byte {
+                b'"' | b'\\' => _ = write!(self.data, "\\{}", byte as char),
+                b' '..=b'~' => self.data.push(byte as char),
+                byte => _ = write!(self.data, "\\{:02x}", byte),
+            }
+        }
+
+        self.size += text.len() as u32;
+        self.strings.insert(text.to_string(), string);
+        string
+    }
+
+    /// the bodies of an `if` are indented, the `block`s of the labels are not
+    fn line(&mut self, text: &str) {
+        if matches!(text, "else" | "end") {
+            self.depth -= 1;
+        }
+
+        _ = writeln!(self.body, "{:1$}{2}", "", 4 + self.depth * 2, text);
+
+        if matches!(text, "if" | "else") {
+            self.depth += 1;
+        }
+    }
+
+    fn print_text(&mut self, text: &str) {
+        let (offset, len) = self.string(text);
+        self.line(&format!("i32.const {}", offset));
+        self.line(&format!("i32.const {}", len));
+        self.line("call $crap_print");
+    }
+
+    /// calls the printer of `ty` with the scalars of `args`
+    fn print_value(&mut self, ty: &Ty, args: &[String]) {
+        for arg in args.iter() {
+            self.line(&format!("local.get {}", arg));
+        }
+
+        match ty {
+            Ty::Int => self.line("call $crap_print_i64"),
+            Ty::Float => self.line("call $crap_print_f64"),
+            ty => {
+                self.define_printer(ty);
+                self.line(&format!("call $print_{}", mangle(ty)));
+            }
+        }
+    }
+
+    /// defines `$print_<type>`, its parameters are the scalars of the value
+    fn define_printer(&mut self, ty: &Ty) {
+        if !self.printers.insert(ty.clone()) {
+            return;
+        }
+
+        // the printer is written in between the current function
+        let body = std::mem::take(&mut self.body);
+        let params = |range: std::ops::Range<usize>| -> Vec<String> {
+            range.map(|i| i.to_string()).collect()
+        };
+
+        match ty {
+            Ty::Struct(name) => {
+                let decl = self.program.struct_decl(*name);
+                self.print_text(&format!("{} {{ ", name));
+
+                for (i, (field, ty)) in decl.fields.iter().enumerate() {
+                    let (offset, width) = layout::field(self.program, &Ty::Struct(*name), i as u32);
+                    let separator = if i == 0 { "" } else { ", " };
+
+                    self.print_text(&format!("{}{}: ", separator, field));
+                    self.print_value(ty, &params(offset..offset + width));
+                }
+
+                self.print_text(" }");
+            }
+            Ty::Enum(name) => {
+                let decl = self.program.enum_decl(*name);
+
+                for (i, (variant, payload)) in decl.variants.iter().enumerate() {
+                    self.line("local.get 0");
+                    self.line(&format!("i64.const {}", i));
+                    self.line("i64.eq");
+                    self.line("if");
+
+                    match payload.is_empty() {
+                        true => self.print_text(&format!("{}.{}", name, variant)),
+                        false => self.print_text(&format!("{}.{}(", name, variant)),
+                    }
+
+                    for (j, ty) in payload.iter().enumerate() {
+                        let (offset, width) =
+                            layout::payload(self.program, &Ty::Enum(*name), i as u32, j as u32);
+
+                        if j > 0 {
+                            self.print_text(", ");
+                        }
+                        self.print_value(ty, &params(offset..offset + width));
+                    }
+
+                    if !payload.is_empty() {
+                        self.print_text(")");
+                    }
+
+                    self.line("end");
+                }
+            }
+            Ty::Optional(inner) => {
+                self.line("local.get 0");
+                self.line("i32.eqz");
+                self.line("if");
+                self.print_text("none");
+                self.line("return");
+                self.line("end");
+
+                if **inner != Ty::Unit {
+                    let width = layout::width(self.program, inner);
+                    self.print_value(inner, &params(1..1 + width));
+                }
+            }
+            Ty::Unit | Ty::Int | Ty::Float => unreachable!(),
+        }
+
+        let printer = std::mem::replace(&mut self.body, body);
+        let params: Vec<&str> = layout::scalars(self.program, ty)
+            .iter()
+            .map(|scalar| value_type(*scalar))
+            .collect();
+
+        _ = write!(self.functions, "\n  (func $print_{}", mangle(ty));
+        if !params.is_empty() {
+            _ = write!(self.functions, " (param {})", params.join(" "));
+        }
+        _ = write!(self.functions, "\n{}  )\n", printer);
+    }
+
+    fn write_function(&mut self, function: &Function) -> Result<(), String> {
+        let order = reverse_postorder(function);
+        let predecessors = function.predecessors();
+
+        let mut position = vec![None; function.blocks.len()];
+        for (i, block) in order.iter().enumerate() {
+            position[block.0 as usize] = Some(i);
+        }
+
+        // every edge goes forward
+        for (i, block) in order.iter().enumerate() {
+            for successor in function.block(*block).terminator.successors() {
+                if position[successor.0 as usize] <= Some(i) {
+                    return Err(format!(
+                        "fn {}: {} jumps back to {}, the wasm target has no loops",
+                        function.name, block, successor
+                    ));
+                }
+            }
+        }
+
+        // the branches that become a `select`, their arms are not emitted
+        let mut selects = HashMap::new();
+        let mut inlined = HashSet::new();
+
+        for block in order.iter() {
+            if inlined.contains(block) {
+                continue;
+            }
+
+            if let Some(select) = self.select(function, &predecessors, *block) {
+                inlined.extend(select.then);
+                inlined.extend(select.otherwise);
+                selects.insert(*block, select);
+            }
+        }
+
+        let emitted: Vec<BlockId> = order
+            .iter()
+            .copied()
+            .filter(|block| !inlined.contains(block))
+            .collect();
+
+        // the targets of a `br`, a jump to the next block falls through
+        let mut labels = HashSet::new();
+        for (i, block) in emitted.iter().enumerate() {
+            let next = emitted.get(i + 1).copied();
+
+            let targets = match selects.get(block) {
+                Some(select) => vec![select.join],
+                _ => function.block(*block).terminator.successors(),
+            };
+
+            labels.extend(targets.into_iter().filter(|target| Some(*target) != next));
+        }
+
+        self.locals = vec![vec![]; function.instructions.len()];
+        let mut declarations = String::new();
+
+        for block in order.iter() {
+            for value in function.block(*block).instructions.iter() {
+                let base = local(function, *value);
+                let scalars = layout::scalars(self.program, &function.instruction(*value).ty);
+
+                let names: Vec<String> = match scalars.len() {
+                    1 => vec![format!("${}", base)],
+                    _ => (0..scalars.len())
+                        .map(|i| format!("${}.{}", base, i))
+                        .collect(),
+                };
+
+                for (name, scalar) in names.iter().zip(scalars.iter()) {
+                    _ = writeln!(declarations, "    (local {} {})", name, value_type(*scalar));
+                }
+
+                self.locals[value.0 as usize] = names;
+            }
+        }
+
+        self.body.clear();
+
+        // the outermost `block` ends before the last target
+        for block in emitted.iter().rev() {
+            if labels.contains(block) {
+                self.line(&format!("block ${}", block));
+            }
+        }
+
+        for (i, block) in emitted.iter().enumerate() {
+            let next = emitted.get(i + 1).copied();
+
+            if labels.contains(block) {
+                self.line(&format!("end ;; {}", block));
+            }
+
+            self.write_block(function, *block);
+
+            match selects.get(block) {
+                Some(select) => {
+                    for arm in [select.then, select.otherwise].into_iter().flatten() {
+                        self.write_block(function, arm);
+                    }
+
+                    let then = select.then.unwrap_or(*block);
+                    let otherwise = select.otherwise.unwrap_or(*block);
+                    self.write_select(function, select.cond, then, otherwise, select.join);
+                    self.write_jump(select.join, next);
+                }
+                None => self.write_terminator(function, *block, next),
+            }
+        }
+
+        let body = std::mem::take(&mut self.body);
+        let name = identifier(function.name);
+
+        _ = write!(
+            self.functions,
+            "\n  (func $f_{} (export \"{}\")\n{}{}  )\n",
+            name, function.name, declarations, body
+        );
+
+        Ok(())
+    }
+
+    /// a branch of `block` to arms without side effects that join again
+    fn select(
+        &self,
+        function: &Function,
+        predecessors: &[Vec<BlockId>],
+        block: BlockId,
+    ) -> Option<Select> {
+        let Terminator::Branch {
+            cond,
+            then,
+            otherwise,
+        } = function.block(block).terminator
+        else {
+            return None;
+        };
+
+        // `None` if the arm is the join itself
+        let arm = |target: BlockId| -> Option<(Option<BlockId>, BlockId)> {
+            let Terminator::Jump(join) = function.block(target).terminator else {
+                return Some((None, target));
+            };
+
+            let instructions = &function.block(target).instructions;
+            let is_pure = predecessors[target.0 as usize] == [block]
+                && instructions.len() <= SELECT_LIMIT
+                && instructions
+                    .iter()
+                    .all(|value| is_speculatable(function, *value));
+
+            match is_pure {
+                true => Some((Some(target), join)),
+                false => Some((None, target)),
+            }
+        };
+
+        let (then_arm, then_join) = arm(then)?;
+        let (otherwise_arm, otherwise_join) = arm(otherwise)?;
+
+        let is_select = then != otherwise
+            && then_join == otherwise_join
+            && (then_arm.is_some() || otherwise_arm.is_some());
+
+        is_select.then_some(Select {
+            cond,
+            then: then_arm,
+            otherwise: otherwise_arm,
+            join: then_join,
+        })
+    }
+
+    fn write_block(&mut self, function: &Function, block: BlockId) {
+        for value in function.block(block).instructions.iter() {
+            self.write_instruction(function, *value);
+        }
+    }
+
+    fn write_terminator(&mut self, function: &Function, block: BlockId, next: Option<BlockId>) {
+        match function.block(block).terminator {
+            Terminator::Jump(target) => {
+                self.write_phis(function, block, target);
+                self.write_jump(target, next);
+            }
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let is_then_empty = function.phi_moves(block, then).is_empty();
+                let is_otherwise_empty = function.phi_moves(block, otherwise).is_empty();
+
+                self.write_cond(cond);
+
+                // one side falls through to the next block
+                if is_then_empty && is_otherwise_empty && Some(then) == next {
+                    self.line("i32.eqz");
+                    return self.line(&format!("br_if ${}", otherwise));
+                }
+                if is_then_empty && is_otherwise_empty && Some(otherwise) == next {
+                    return self.line(&format!("br_if ${}", then));
+                }
+
+                self.line("if");
+                self.write_phis(function, block, then);
+                self.write_jump(then, next);
+                self.line("else");
+                self.write_phis(function, block, otherwise);
+                self.write_jump(otherwise, next);
+                self.line("end");
+            }
+            Terminator::Return => self.line("return"),
+            Terminator::Unreachable => self.line("unreachable"),
+        }
+    }
+
+    fn write_jump(&mut self, target: BlockId, next: Option<BlockId>) {
+        if Some(target) != next {
+            self.line(&format!("br ${}", target));
+        }
+    }
+
+    /// `1` as `i32` if the `Int` is not zero
+    fn write_cond(&mut self, cond: Value) {
+        let cond = self.locals[cond.0 as usize][0].clone();
+        self.line(&format!("local.get {}", cond));
+        self.line("i64.const 0");
+        self.line("i64.ne");
+    }
+
+    /// assigns the phis of `to` for the edge from `from`, all sources are read
+    /// before the first phi is written
+    fn write_phis(&mut self, function: &Function, from: BlockId, to: BlockId) {
+        let mut targets = vec![];
+
+        for (phi, source) in function.phi_moves(from, to) {
+            for (target, source) in self.locals[phi.0 as usize]
+                .clone()
+                .into_iter()
+                .zip(self.locals[source.0 as usize].clone())
+            {
+                self.line(&format!("local.get {}", source));
+                targets.push(target);
+            }
+        }
+
+        for target in targets.iter().rev() {
+            self.line(&format!("local.set {}", target));
+        }
+    }
+
+    /// assigns the phis of `join` by the edge the condition picks
+    fn write_select(
+        &mut self,
+        function: &Function,
+        cond: Value,
+        then: BlockId,
+        otherwise: BlockId,
+        join: BlockId,
+    ) {
+        let then = function.phi_moves(then, join);
+        let otherwise = function.phi_moves(otherwise, join);
+        let mut targets = vec![];
+
+        for ((phi, then), (_, otherwise)) in then.into_iter().zip(otherwise) {
+            let scalars = self.locals[phi.0 as usize].len();
+
+            for i in 0..scalars {
+                let then = self.locals[then.0 as usize][i].clone();
+                let otherwise = self.locals[otherwise.0 as usize][i].clone();
+
+                self.line(&format!("local.get {}", then));
+                self.line(&format!("local.get {}", otherwise));
+                self.write_cond(cond);
+                self.line("select");
+                targets.push(self.locals[phi.0 as usize][i].clone());
+            }
+        }
+
+        for target in targets.iter().rev() {
+            self.line(&format!("local.set {}", target));
+        }
+    }
+
+    /// copies `width` scalars of `from` to `to`, starting at the offsets
+    fn copy(&mut self, to: Value, to_offset: usize, from: Value, from_offset: usize, width: usize) {
+        for i in 0..width {
+            let source = self.locals[from.0 as usize][from_offset + i].clone();
+            let target = self.locals[to.0 as usize][to_offset + i].clone();
+
+            self.line(&format!("local.get {}", source));
+            self.line(&format!("local.set {}", target));
+        }
+    }
+
+    fn write_instruction(&mut self, function: &Function, value: Value) {
+        let instruction = function.instruction(value);
+        let ty = &instruction.ty;
+        let locals = self.locals[value.0 as usize].clone();
+        let get = |generator: &Generator, value: &Value| {
+            format!("local.get {}", generator.locals[value.0 as usize][0])
+        };
+
+        match &instruction.kind {
+            InstKind::Const(Constant::Int(constant)) => {
+                self.line(&format!("i64.const {}", constant));
+            }
+            InstKind::Const(Constant::Float(constant)) => {
+                self.line(&format!("f64.const {}", float(*constant)));
+            }
+
+            // the other scalars are never read
+            InstKind::Const(Constant::None) => self.line("i32.const 0"),
+            InstKind::Copy(source) => return self.copy(value, 0, *source, 0, locals.len()),
+            InstKind::Neg(source) if *ty == Ty::Int => {
+                self.line("i64.const 0");
+                self.line(&get(self, source));
+                self.line("i64.sub");
+            }
+            InstKind::Neg(source) => {
+                self.line(&get(self, source));
+                self.line("f64.neg");
+            }
+            InstKind::Binary {
+                op,
+                lhs,
+                rhs,
+                index,
+            } => {
+                let kind = function.instruction(*lhs).ty.clone();
+                self.line(&get(self, lhs));
+                self.line(&get(self, rhs));
+
+                match (op, kind) {
+                    (BinaryOp::Div | BinaryOp::Rem, Ty::Int) => {
+                        let message =
+                            format!("division by zero (at {})", location(self.source, *index));
+                        let (offset, len) = self.string(&message);
+
+                        self.line(&format!("i32.const {}", offset));
+                        self.line(&format!("i32.const {}", len));
+
+                        match op {
+                            BinaryOp::Div => self.line("call $crap_div"),
+                            _ => self.line("call $crap_rem"),
+                        }
+                    }
+                    (BinaryOp::Rem, _) => self.line("call $crap_fmod"),
+                    (op, kind) => {
+                        let scalar = if kind == Ty::Int { "i64" } else { "f64" };
+                        let name = match op {
+                            BinaryOp::Add => "add",
+                            BinaryOp::Sub => "sub",
+                            BinaryOp::Mul => "mul",
+                            BinaryOp::Div => "div",
+                            BinaryOp::Eq => "eq",
+                            BinaryOp::Ne => "ne",
+                            BinaryOp::Rem => unreachable!(),
+                        };
+
+                        self.line(&format!("{}.{}", scalar, name));
+
+                        // the comparisons are `i32`
+                        if matches!(op, BinaryOp::Eq | BinaryOp::Ne) {
+                            self.line("i64.extend_i32_u");
+                        }
+                    }
+                }
+            }
+            InstKind::Struct(fields) => {
+                let mut offset = 0;
+
+                for field in fields.iter() {
+                    let width = self.locals[field.0 as usize].len();
+                    self.copy(value, offset, *field, 0, width);
+                    offset += width;
+                }
+
+                return;
+            }
+            InstKind::Field {
+                value: source,
+                field,
+            } => {
+                let (offset, width) =
+                    layout::field(self.program, &function.instruction(*source).ty, *field);
+                return self.copy(value, 0, *source, offset, width);
+            }
+            InstKind::SetField {
+                value: source,
+                field,
+                to,
+            } => {
+                let (offset, width) = layout::field(self.program, ty, *field);
+                self.copy(value, 0, *source, 0, locals.len());
+                return self.copy(value, offset, *to, 0, width);
+            }
+            InstKind::Variant { variant, payload } => {
+                self.line(&format!("i64.const {}", variant));
+                self.line(&format!("local.set {}", locals[0]));
+
+                for (i, field) in payload.iter().enumerate() {
+                    let (offset, width) = layout::payload(self.program, ty, *variant, i as u32);
+                    self.copy(value, offset, *field, 0, width);
+                }
+
+                return;
+            }
+            InstKind::Tag(source) => return self.copy(value, 0, *source, 0, 1),
+            InstKind::Payload {
+                value: source,
+                variant,
+                index,
+            } => {
+                let ty = function.instruction(*source).ty.clone();
+                let (offset, width) = layout::payload(self.program, &ty, *variant, *index);
+                return self.copy(value, 0, *source, offset, width);
+            }
+            InstKind::IsNone(source) => {
+                self.line(&get(self, source));
+                self.line("i32.eqz");
+                self.line("i64.extend_i32_u");
+            }
+            InstKind::Wrap(source) => {
+                self.line("i32.const 1");
+                self.line(&format!("local.set {}", locals[0]));
+
+                let width = self.locals[source.0 as usize].len();
+                return self.copy(value, 1, *source, 0, width);
+            }
+            InstKind::Unwrap(source) => return self.copy(value, 0, *source, 1, locals.len()),
+
+            // assigned at the end of the predecessors
+            InstKind::Phi(_) => return,
+            InstKind::Print(printed) => {
+                let ty = function.instruction(*printed).ty.clone();
+                let args = self.locals[printed.0 as usize].clone();
+
+                self.print_value(&ty, &args);
+                return self.print_text("\n");
+            }
+        }
+
+        self.line(&format!("local.set {}", locals[0]));
+    }
+}
+
+/// the instructions that can run even if their value is not used
+fn is_speculatable(function: &Function, value: Value) -> bool {
+    let instruction = function.instruction(value);
+
+    match instruction.kind {
+        InstKind::Print(_) | InstKind::Phi(_) => false,
+        InstKind::Binary {
+            op: BinaryOp::Div | BinaryOp::Rem,
+            ..
+        } => instruction.ty != Ty::Int,
+        _ => true,
+    }
+}
+
+fn value_type(scalar: Scalar) -> &'static str {
+    match scalar {
+        Scalar::Int => "i64",
+        Scalar::Float => "f64",
+        Scalar::Flag => "i32",
+    }
+}
+
+/// a literal that reads back as the same value
+fn float(value: f64) -> String {
+    match value {
+        value if value.is_nan() => "nan".to_string(),
+        value if value.is_infinite() && value > 0.0 => "inf".to_string(),
+        value if value.is_infinite() => "-inf".to_string(),
+        value => format!("{:?}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        diagnostics::{Diagnostic, Severity},
+        ir::lower::lower,
+        lexer::tokenize,
+        parser::parse,
+        semantic::analyze,
+    };
+
+    use super::to_wat;
+
+    fn is_error(diagnostic: &Diagnostic) -> bool {
+        diagnostic.severity == Severity::Error
+    }
+
+    /// the module of a checked `buffer`, it has to be valid wasm
+    fn module(buffer: &str) -> String {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(!diagnostics.iter().any(is_error), "{:?}", diagnostics);
+
+        let analysis = analyze(&stmts);
+        assert!(
+            !analysis.diagnostics.iter().any(is_error),
+            "{:?}",
+            analysis.diagnostics
+        );
+
+        let program = lower(&stmts, &analysis).unwrap();
+        let wat = to_wat(&program, Some(buffer)).unwrap();
+
+        if let Err(error) = wat::parse_str(&wat) {
+            panic!("{}\n{}", error, wat);
+        }
+
+        wat
+    }
+
+    #[test]
+    fn examples() {
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "crap")
+            {
+                module(&fs::read_to_string(path).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn ternaries() {
+        // both arms are constants, they are computed and selected
+        let wat = module("let a = 4\nlet b = a ? 1 : 2\nb\n");
+        assert!(wat.contains("(func $f_main (export \"main\")"), "{}", wat);
+        assert!(wat.contains("    select\n"), "{}", wat);
+        assert!(!wat.contains("br_if"), "{}", wat);
+
+        // a division may stop the program, it only runs on its own branch
+        let wat = module("let a = 4\nlet b = a ? 8 / a : 2\nb\n");
+        assert!(!wat.contains("    select\n"), "{}", wat);
+        assert!(wat.contains("    br_if $bb2\n"), "{}", wat);
+        assert!(
+            wat.contains("\"division by zero (at 2:15)\\0a\""),
+            "{}",
+            wat
+        );
+    }
+}
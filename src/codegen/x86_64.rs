@@ -0,0 +1,1302 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Write},
+};
+
+use crate::ir::{
+    verifier::reverse_postorder, BinaryOp, BlockId, Constant, Function, InstKind, Program,
+    Terminator, Ty, Value,
+};
+
+use super::{
+    identifier,
+    layout::{self, Scalar},
+    location, mangle, quote,
+};
+
+// --- overview --- //
+// gnu assembler text in at&t syntax for x86-64 linux and the system v calling
+// convention, `_start` calls `f_main` and exits, the runtime calls into libc
+//   as out.s -o out.o
+//   ld -dynamic-linker /lib64/ld-linux-x86-64.so.2 out.o -o out -lc -lm
+//
+// every value is flattened into scalars like in `layout.rs`, a scalar lives in a
+// register or in a stack slot below `%rbp` for all of its live range:
+// - the live ranges cover the positions of the blocks in reverse postorder from
+//   the first to the last position a value is live at
+// - linear scan assigns the registers in the order the ranges start, when none
+//   is free the range that ends last is spilled
+// - a range that crosses a call prefers a callee-saved register, the caller-saved
+//   ones are stored before the call and loaded after it
+//
+// the phis are assigned at the end of the predecessors by parallel moves
+//
+// an aggregate is printed through memory, the scalars are stored in 8 bytes each
+// and `print_<type>` gets their address
+
+const RUNTIME: &str = r#"    .text
+    .globl _start
+_start:
+    xorl %ebp, %ebp
+    andq $-16, %rsp
+    call f_main
+    xorl %edi, %edi
+    call exit@PLT
+
+# %rdi: the value
+crap_print_i64:
+    subq $8, %rsp
+    movq %rdi, %rsi
+    leaq .Lformat_i64(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $8, %rsp
+    ret
+
+# %rdi: a string
+crap_print_str:
+    subq $8, %rsp
+    movq %rdi, %rsi
+    leaq .Lformat_str(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    addq $8, %rsp
+    ret
+
+crap_print_newline:
+    movl $10, %edi
+    jmp putchar@PLT
+
+# %xmm0: the value, the shortest digits that read back as the same value,
+# formatted like `{:?}` in rust
+crap_print_f64:
+    pushq %rbx
+    pushq %r12
+    subq $56, %rsp
+    movsd %xmm0, 40(%rsp)
+    ucomisd %xmm0, %xmm0
+    jp .Lf64_nan
+    # `inf - inf` is nan, `%.1f` prints `inf` and `-inf`
+    movapd %xmm0, %xmm1
+    subsd %xmm0, %xmm1
+    ucomisd %xmm1, %xmm1
+    jp .Lf64_one
+    movl $1, %ebx
+.Lf64_digits:
+    movq %rsp, %rdi
+    movl $40, %esi
+    leaq .Lformat_e(%rip), %rdx
+    leal -1(%rbx), %ecx
+    movsd 40(%rsp), %xmm0
+    movl $1, %eax
+    call snprintf@PLT
+    cmpl $17, %ebx
+    je .Lf64_found
+    movq %rsp, %rdi
+    xorl %esi, %esi
+    call strtod@PLT
+    ucomisd 40(%rsp), %xmm0
+    jp .Lf64_next
+    je .Lf64_found
+.Lf64_next:
+    incl %ebx
+    jmp .Lf64_digits
+.Lf64_found:
+    movq %rsp, %rdi
+    movl $101, %esi
+    call strchr@PLT
+    movq %rax, %r12
+    leaq 1(%rax), %rdi
+    call atoi@PLT
+    cmpl $-4, %eax
+    jl .Lf64_scientific
+    cmpl $16, %eax
+    jge .Lf64_scientific
+    # `digits - 1 - exponent` decimals, at least one
+    leal -1(%rbx), %esi
+    subl %eax, %esi
+    cmpl $1, %esi
+    jge .Lf64_fixed
+.Lf64_one:
+    movl $1, %esi
+.Lf64_fixed:
+    leaq .Lformat_f(%rip), %rdi
+    movsd 40(%rsp), %xmm0
+    movl $1, %eax
+    call printf@PLT
+    jmp .Lf64_done
+.Lf64_scientific:
+    # the digits end at the `e`
+    movb $0, (%r12)
+    movl %eax, %edx
+    movq %rsp, %rsi
+    leaq .Lformat_scientific(%rip), %rdi
+    xorl %eax, %eax
+    call printf@PLT
+    jmp .Lf64_done
+.Lf64_nan:
+    leaq .Lnan(%rip), %rdi
+    call crap_print_str
+.Lf64_done:
+    addq $56, %rsp
+    popq %r12
+    popq %rbx
+    ret
+
+# %rdi: the location, writes the values before the error and exits with `1`
+crap_division_by_zero:
+    pushq %rbx
+    movq %rdi, %rbx
+    xorl %edi, %edi
+    call fflush@PLT
+    movl $2, %edi
+    leaq .Lformat_division(%rip), %rsi
+    movq %rbx, %rdx
+    xorl %eax, %eax
+    call dprintf@PLT
+    movl $1, %edi
+    call exit@PLT
+
+    .section .rodata
+.Lformat_i64: .string "%ld"
+.Lformat_str: .string "%s"
+.Lformat_e: .string "%.*e"
+.Lformat_f: .string "%.*f"
+.Lformat_scientific: .string "%se%d"
+.Lformat_division: .string "error: division by zero (at %s)\n"
+.Lnan: .string "NaN"
+    .balign 16
+.Lsign: .quad 0x8000000000000000, 0
+"#;
+
+// the registers the allocator hands out, `%rax`, `%rcx`, `%rdx`, `%r11`, `%xmm0`,
+// `%xmm1`, `%xmm14` and `%xmm15` are left for the instructions
+const CALLEE_SAVED: [&str; 5] = ["rbx", "r12", "r13", "r14", "r15"];
+const CALLER_SAVED: [&str; 5] = ["rsi", "rdi", "r8", "r9", "r10"];
+const FLOAT: [&str; 12] = [
+    "xmm2", "xmm3", "xmm4", "xmm5", "xmm6", "xmm7", "xmm8", "xmm9", "xmm10", "xmm11", "xmm12",
+    "xmm13",
+];
+
+/// a verified program as gnu assembler text, `source` is the code it was lowered from
+pub fn to_asm(program: &Program, source: Option<&str>) -> String {
+    let mut generator = Generator {
+        program,
+        source,
+        functions: String::new(),
+        data: String::new(),
+        strings: HashMap::new(),
+        floats: HashMap::new(),
+        printers: HashSet::new(),
+        labels: 0,
+    };
+
+    for function in program.functions.iter() {
+        generator.write_function(function);
+    }
+
+    let mut output = String::from(RUNTIME);
+    output.push_str(&generator.data);
+    output.push_str("\n    .text\n");
+    output.push_str(&generator.functions);
+    output.push_str("\n    .section .note.GNU-stack,\"\",@progbits\n");
+
+    output
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Int,
+    Float,
+}
+
+impl From<Scalar> for Class {
+    fn from(scalar: Scalar) -> Class {
+        match scalar {
+            Scalar::Float => Class::Float,
+            Scalar::Int | Scalar::Flag => Class::Int,
+        }
+    }
+}
+
+/// where a scalar lives, a stack slot is an offset from `%rbp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Location {
+    Register(&'static str),
+    Stack(i32),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Register(name) => write!(f, "%{}", name),
+            Location::Stack(offset) => write!(f, "{}(%rbp)", offset),
+        }
+    }
+}
+
+/// the positions a scalar is live at, from its first to its last one
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    value: Value,
+    scalar: usize,
+    class: Class,
+    start: usize,
+    end: usize,
+}
+
+/// the locations of a function and its frame below `%rbp`
+struct Frame {
+    locations: Vec<Vec<Location>>,
+    ranges: Vec<Vec<Range>>,
+
+    // the callee-saved registers pushed after `%rbp`
+    saved: Vec<&'static str>,
+
+    // the slots for the caller-saved registers around a call and for printing
+    spills: HashMap<&'static str, i32>,
+    print: i32,
+    size: i32,
+}
+
+impl Frame {
+    fn location(&self, value: Value, scalar: usize) -> Location {
+        self.locations[value.0 as usize][scalar]
+    }
+
+    fn class(&self, value: Value, scalar: usize) -> Class {
+        self.ranges[value.0 as usize][scalar].class
+    }
+}
+
+struct Generator<'a> {
+    program: &'a Program,
+    source: Option<&'a str>,
+    functions: String,
+
+    // the read-only data, the strings and floats by their label
+    data: String,
+    strings: HashMap<String, String>,
+    floats: HashMap<u64, String>,
+
+    // the print functions that are already defined
+    printers: HashSet<Ty>,
+    labels: usize,
+}
+
+impl Generator<'_> {
+    /// the label of a zero terminated string
+    fn string(&mut self, text: &str) -> String {
+        if let Some(label) = self.strings.get(text) {
+            return label.clone();
+        }
+
+        let label = format!(".Lstring{}", self.strings.len());
+        _ = writeln!(self.data, "{}: .string {}", label, quote(text));
+
+        self.strings.insert(text.to_string(), label.clone());
+        label
+    }
+
+    /// the label of a float constant
+    fn float(&mut self, value: f64) -> String {
+        if let Some(label) = self.floats.get(&value.to_bits()) {
+            return label.clone();
+        }
+
+        let label = format!(".Lfloat{}", self.floats.len());
+        _ = writeln!(self.data, "    .balign 8");
+        _ = writeln!(
+            self.data,
+            "{}: .quad {:#x} # {:?}",
+            label,
+            value.to_bits(),
+            value
+        );
+
+        self.floats.insert(value.to_bits(), label.clone());
+        label
+    }
+
+    fn label(&mut self) -> String {
+        self.labels += 1;
+        format!(".L{}", self.labels)
+    }
+
+    /// defines `print_<type>`, `%rdi` is the address of the scalars of the value
+    fn define_printer(&mut self, ty: &Ty) {
+        if !self.printers.insert(ty.clone()) {
+            return;
+        }
+
+        let mut code = String::new();
+        let name = mangle(ty);
+
+        match ty {
+            Ty::Struct(struct_name) => {
+                let decl = self.program.struct_decl(*struct_name);
+                self.print_text(&mut code, &format!("{} {{ ", struct_name));
+
+                for (i, (field, field_ty)) in decl.fields.iter().enumerate() {
+                    let (offset, _) = layout::field(self.program, ty, i as u32);
+                    let separator = if i == 0 { "" } else { ", " };
+
+                    self.print_text(&mut code, &format!("{}{}: ", separator, field));
+                    self.print_scalars(&mut code, field_ty, offset);
+                }
+
+                self.print_text(&mut code, " }");
+            }
+            Ty::Enum(enum_name) => {
+                let decl = self.program.enum_decl(*enum_name);
+                let done = self.label();
+
+                for (i, (variant, payload)) in decl.variants.iter().enumerate() {
+                    let next = self.label();
+                    _ = writeln!(code, "    cmpq ${}, (%rbx)", i);
+                    _ = writeln!(code, "    jne {}", next);
+
+                    match payload.is_empty() {
+                        true => self.print_text(&mut code, &format!("{}.{}", enum_name, variant)),
+                        false => self.print_text(&mut code, &format!("{}.{}(", enum_name, variant)),
+                    }
+
+                    for (j, payload_ty) in payload.iter().enumerate() {
+                        let (offset, _) = layout::payload(self.program, ty, i as u32, j as u32);
+
+                        if j > 0 {
+                            self.print_text(&mut code, ", ");
+                        }
+                        self.print_scalars(&mut code, payload_ty, offset);
+                    }
+
+                    if !payload.is_empty() {
+                        self.print_text(&mut code, ")");
+                    }
+
+                    _ = writeln!(code, "    jmp {}", done);
+                    _ = writeln!(code, "{}:", next);
+                }
+
+                _ = writeln!(code, "{}:", done);
+            }
+            Ty::Optional(inner) => {
+                let some = self.label();
+                let done = self.label();
+
+                _ = writeln!(code, "    cmpq $0, (%rbx)");
+                _ = writeln!(code, "    jne {}", some);
+                self.print_text(&mut code, "none");
+                _ = writeln!(code, "    jmp {}", done);
+                _ = writeln!(code, "{}:", some);
+                self.print_scalars(&mut code, inner, 1);
+                _ = writeln!(code, "{}:", done);
+            }
+            Ty::Unit | Ty::Int | Ty::Float => unreachable!(),
+        }
+
+        // `%rbx` keeps the address across the calls and the stack stays aligned
+        _ = write!(
+            self.functions,
+            "\nprint_{}:\n    pushq %rbx\n    movq %rdi, %rbx\n{}    popq %rbx\n    ret\n",
+            name, code
+        );
+    }
+
+    fn print_text(&mut self, code: &mut String, text: &str) {
+        let label = self.string(text);
+        _ = writeln!(code, "    leaq {}(%rip), %rdi", label);
+        _ = writeln!(code, "    call crap_print_str");
+    }
+
+    /// prints a value of `ty` at the scalar `offset` of `%rbx`
+    fn print_scalars(&mut self, code: &mut String, ty: &Ty, offset: usize) {
+        let address = format!("{}(%rbx)", offset * 8);
+
+        match ty {
+            Ty::Unit => (),
+            Ty::Int => {
+                _ = writeln!(code, "    movq {}, %rdi", address);
+                _ = writeln!(code, "    call crap_print_i64");
+            }
+            Ty::Float => {
+                _ = writeln!(code, "    movsd {}, %xmm0", address);
+                _ = writeln!(code, "    call crap_print_f64");
+            }
+            ty => {
+                self.define_printer(ty);
+                _ = writeln!(code, "    leaq {}, %rdi", address);
+                _ = writeln!(code, "    call print_{}", mangle(ty));
+            }
+        }
+    }
+
+    fn write_function(&mut self, function: &Function) {
+        let order = reverse_postorder(function);
+        let frame = self.allocate(function, &order);
+
+        let name = format!("f_{}", identifier(function.name));
+        let mut code = String::new();
+
+        _ = writeln!(code, "\n    .globl {}", name);
+        _ = writeln!(code, "{}:", name);
+
+        // the allocation of the values
+        for block in order.iter() {
+            for value in function.block(*block).instructions.iter() {
+                let locations = &frame.locations[value.0 as usize];
+                if locations.is_empty() {
+                    continue;
+                }
+
+                let locations: Vec<String> = locations.iter().map(Location::to_string).collect();
+                match function.instruction(*value).name {
+                    Some(binding) => _ = write!(code, "    # {} {}:", value, binding),
+                    None => _ = write!(code, "    # {}:", value),
+                }
+                _ = writeln!(code, " {}", locations.join(", "));
+            }
+        }
+
+        code.push_str("    pushq %rbp\n");
+        code.push_str("    movq %rsp, %rbp\n");
+        for register in frame.saved.iter() {
+            _ = writeln!(code, "    pushq %{}", register);
+        }
+        if frame.size > 0 {
+            _ = writeln!(code, "    subq ${}, %rsp", frame.size);
+        }
+
+        let mut writer = FunctionWriter {
+            generator: self,
+            function,
+            frame: &frame,
+            name: name.clone(),
+            code,
+            positions: Positions::new(function, &order),
+            position: 0,
+        };
+
+        for (i, block) in order.iter().enumerate() {
+            let next = order.get(i + 1).copied();
+            writer.write_block(*block, next);
+        }
+
+        let code = writer.code;
+        self.functions.push_str(&code);
+    }
+
+    /// the live ranges of the scalars and their locations by linear scan
+    fn allocate(&mut self, function: &Function, order: &[BlockId]) -> Frame {
+        let positions = Positions::new(function, order);
+        let count = function.instructions.len();
+
+        // the first and last position every value is live at
+        let mut hulls: Vec<Option<(usize, usize)>> = vec![None; count];
+        let mut mark = |value: Value, position: usize| {
+            let hull = &mut hulls[value.0 as usize];
+            *hull = match *hull {
+                Some((start, end)) => Some((start.min(position), end.max(position))),
+                None => Some((position, position)),
+            };
+        };
+
+        let (live_in, live_out) = liveness(function, order);
+
+        for block in order.iter() {
+            let (start, end) = positions.blocks[block.0 as usize];
+            let instructions = &function.block(*block).instructions;
+
+            live_in[block.0 as usize]
+                .iter()
+                .for_each(|value| mark(*value, start));
+            live_out[block.0 as usize]
+                .iter()
+                .for_each(|value| mark(*value, end));
+
+            for (i, value) in instructions.iter().enumerate() {
+                let kind = &function.instruction(*value).kind;
+                if let InstKind::Phi(entries) = kind {
+                    // assigned at the end of the predecessors
+                    for (predecessor, source) in entries.iter() {
+                        let (_, end) = positions.blocks[predecessor.0 as usize];
+                        mark(*value, end);
+                        mark(*source, end);
+                    }
+                    continue;
+                }
+
+                mark(*value, start + 1 + i);
+                kind.operands()
+                    .into_iter()
+                    .for_each(|operand| mark(operand, start + 1 + i));
+            }
+
+            if let Terminator::Branch { cond, .. } = function.block(*block).terminator {
+                mark(cond, end);
+            }
+        }
+
+        // the calls to libc and the printers
+        let mut calls = vec![];
+        for block in order.iter() {
+            let (start, _) = positions.blocks[block.0 as usize];
+
+            for (i, value) in function.block(*block).instructions.iter().enumerate() {
+                if is_call(function, *value) {
+                    calls.push(start + 1 + i);
+                }
+            }
+        }
+
+        let mut ranges: Vec<Vec<Range>> = vec![vec![]; count];
+        let mut sorted = vec![];
+
+        for (i, hull) in hulls.iter().enumerate() {
+            let Some((start, end)) = *hull else {
+                continue;
+            };
+
+            let ty = &function.instructions[i].ty;
+            for (scalar, kind) in layout::scalars(self.program, ty).into_iter().enumerate() {
+                let range = Range {
+                    value: Value(i as u32),
+                    scalar,
+                    class: Class::from(kind),
+                    start,
+                    end,
+                };
+
+                ranges[i].push(range);
+                sorted.push(range);
+            }
+        }
+
+        sorted.sort_by_key(|range| (range.start, range.end));
+
+        let crosses_call = |range: &Range| {
+            calls
+                .iter()
+                .any(|call| range.start < *call && *call < range.end)
+        };
+
+        // linear scan, the active ranges hold a register
+        let mut registers: Vec<Vec<Option<&'static str>>> = ranges
+            .iter()
+            .map(|ranges| vec![None; ranges.len()])
+            .collect();
+        let mut active: Vec<Range> = vec![];
+        let mut spilled: Vec<Range> = vec![];
+
+        for range in sorted.iter() {
+            active.retain(|active| active.end >= range.start);
+
+            let used: HashSet<&str> = active
+                .iter()
+                .filter_map(|active| registers[active.value.0 as usize][active.scalar])
+                .collect();
+
+            let candidates: Vec<&'static str> = match (range.class, crosses_call(range)) {
+                (Class::Float, _) => FLOAT.to_vec(),
+                (Class::Int, true) => [CALLEE_SAVED, CALLER_SAVED].concat(),
+                (Class::Int, false) => [CALLER_SAVED, CALLEE_SAVED].concat(),
+            };
+
+            if let Some(register) = candidates.iter().find(|register| !used.contains(*register)) {
+                registers[range.value.0 as usize][range.scalar] = Some(register);
+                active.push(*range);
+                continue;
+            }
+
+            // the range of the same class that ends last gives up its register
+            let last = active
+                .iter()
+                .enumerate()
+                .filter(|(_, active)| active.class == range.class)
+                .max_by_key(|(_, active)| active.end)
+                .map(|(i, active)| (i, *active));
+
+            match last {
+                Some((i, last)) if last.end > range.end => {
+                    let register = registers[last.value.0 as usize][last.scalar].take();
+                    registers[range.value.0 as usize][range.scalar] = register;
+
+                    active.remove(i);
+                    active.push(*range);
+                    spilled.push(last);
+                }
+                _ => spilled.push(*range),
+            }
+        }
+
+        // the frame below the pushed callee-saved registers
+        let saved: Vec<&'static str> = CALLEE_SAVED
+            .iter()
+            .copied()
+            .filter(|register| {
+                registers
+                    .iter()
+                    .flatten()
+                    .any(|used| *used == Some(*register))
+            })
+            .collect();
+
+        let mut slots = 0;
+        let slot = |slots: &mut i32| {
+            *slots += 1;
+            -(saved.len() as i32 + *slots) * 8
+        };
+
+        let mut locations: Vec<Vec<Location>> = registers
+            .iter()
+            .map(|registers| {
+                registers
+                    .iter()
+                    .map(|register| Location::Register(register.unwrap_or("")))
+                    .collect()
+            })
+            .collect();
+
+        for range in spilled.iter() {
+            locations[range.value.0 as usize][range.scalar] = Location::Stack(slot(&mut slots));
+        }
+
+        let mut spills = HashMap::new();
+        for register in CALLER_SAVED.iter().chain(FLOAT.iter()) {
+            if registers
+                .iter()
+                .flatten()
+                .any(|used| *used == Some(*register))
+            {
+                spills.insert(*register, slot(&mut slots));
+            }
+        }
+
+        // the printed aggregates are stored upwards from the lowest slot
+        let width = function
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction.kind {
+                InstKind::Print(value) => {
+                    Some(layout::width(self.program, &function.instruction(value).ty))
+                }
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        let mut print = 0;
+        for _ in 0..width {
+            print = slot(&mut slots);
+        }
+
+        // `%rsp` is 16 byte aligned at every call
+        let mut size = slots * 8;
+        if (saved.len() as i32 * 8 + size) % 16 != 0 {
+            size += 8;
+        }
+
+        Frame {
+            locations,
+            ranges,
+            saved,
+            spills,
+            print,
+            size,
+        }
+    }
+}
+
+/// the first and last position of every block, its instructions are in between
+struct Positions {
+    blocks: Vec<(usize, usize)>,
+}
+
+impl Positions {
+    fn new(function: &Function, order: &[BlockId]) -> Positions {
+        let mut blocks = vec![(0, 0); function.blocks.len()];
+        let mut position = 0;
+
+        for block in order.iter() {
+            let start = position;
+            position += function.block(*block).instructions.len() + 1;
+            blocks[block.0 as usize] = (start, position);
+            position += 1;
+        }
+
+        Positions { blocks }
+    }
+}
+
+/// the values live at the start and at the end of every block, a phi is live
+/// from the end of its predecessors
+fn liveness(function: &Function, order: &[BlockId]) -> (Vec<HashSet<Value>>, Vec<HashSet<Value>>) {
+    let mut live_in = vec![HashSet::new(); function.blocks.len()];
+    let mut live_out: Vec<HashSet<Value>> = vec![HashSet::new(); function.blocks.len()];
+
+    let mut is_changed = true;
+    while is_changed {
+        is_changed = false;
+
+        for block in order.iter().rev() {
+            let mut out = HashSet::new();
+            let terminator = &function.block(*block).terminator;
+
+            for successor in terminator.successors() {
+                for value in live_in[successor.0 as usize].iter() {
+                    if !matches!(function.instruction(*value).kind, InstKind::Phi(_))
+                        || !function.block(successor).instructions.contains(value)
+                    {
+                        out.insert(*value);
+                    }
+                }
+
+                for (_, source) in function.phi_moves(*block, successor) {
+                    out.insert(source);
+                }
+            }
+
+            if let Terminator::Branch { cond, .. } = terminator {
+                out.insert(*cond);
+            }
+
+            let mut live = out.clone();
+            for value in function.block(*block).instructions.iter().rev() {
+                live.remove(value);
+
+                match &function.instruction(*value).kind {
+                    InstKind::Phi(_) => (),
+                    kind => live.extend(kind.operands()),
+                }
+            }
+
+            // the phis are defined before the block
+            for value in function.block(*block).instructions.iter() {
+                if matches!(function.instruction(*value).kind, InstKind::Phi(_)) {
+                    live.insert(*value);
+                }
+            }
+
+            if live != live_in[block.0 as usize] || out != live_out[block.0 as usize] {
+                live_in[block.0 as usize] = live;
+                live_out[block.0 as usize] = out;
+                is_changed = true;
+            }
+        }
+    }
+
+    (live_in, live_out)
+}
+
+/// the instructions that call a function that may overwrite the caller-saved registers
+fn is_call(function: &Function, value: Value) -> bool {
+    let instruction = function.instruction(value);
+
+    match instruction.kind {
+        InstKind::Print(_) => true,
+        InstKind::Binary {
+            op: BinaryOp::Rem, ..
+        } => instruction.ty == Ty::Float,
+        _ => false,
+    }
+}
+
+/// writes the blocks of a function with the locations of its frame
+struct FunctionWriter<'a, 'b> {
+    generator: &'a mut Generator<'b>,
+    function: &'a Function,
+    frame: &'a Frame,
+    name: String,
+    code: String,
+
+    // the position of the instruction being written
+    positions: Positions,
+    position: usize,
+}
+
+impl FunctionWriter<'_, '_> {
+    fn line(&mut self, text: &str) {
+        _ = writeln!(self.code, "    {}", text);
+    }
+
+    fn block_label(&self, block: BlockId) -> String {
+        format!(".L{}_{}", self.name, block)
+    }
+
+    fn write_block(&mut self, block: BlockId, next: Option<BlockId>) {
+        let label = self.block_label(block);
+        _ = writeln!(self.code, "{}:", label);
+
+        let (start, _) = self.positions.blocks[block.0 as usize];
+
+        for (i, value) in self.function.block(block).instructions.iter().enumerate() {
+            self.position = start + 1 + i;
+            self.write_instruction(*value);
+        }
+
+        match self.function.block(block).terminator {
+            Terminator::Jump(target) => {
+                self.write_phis(block, target);
+                self.write_jump(target, next);
+            }
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let location = self.frame.location(cond, 0);
+                self.line(&format!("cmpq $0, {}", location));
+
+                let is_then_empty = self.function.phi_moves(block, then).is_empty();
+                let is_otherwise_empty = self.function.phi_moves(block, otherwise).is_empty();
+
+                if is_then_empty && is_otherwise_empty {
+                    match next {
+                        Some(next) if next == then => {
+                            self.line(&format!("je {}", self.block_label(otherwise)));
+                        }
+                        _ => {
+                            self.line(&format!("jne {}", self.block_label(then)));
+                            self.write_jump(otherwise, next);
+                        }
+                    }
+                    return;
+                }
+
+                // the moves of an edge only run on that edge
+                let edge = self.generator.label();
+                self.line(&format!("je {}", edge));
+                self.write_phis(block, then);
+                self.line(&format!("jmp {}", self.block_label(then)));
+                _ = writeln!(self.code, "{}:", edge);
+                self.write_phis(block, otherwise);
+                self.write_jump(otherwise, next);
+            }
+            Terminator::Return => {
+                self.line("xorl %eax, %eax");
+
+                let saved = self.frame.saved.len() as i32 * 8;
+                match saved {
+                    0 => self.line("movq %rbp, %rsp"),
+                    saved => self.line(&format!("leaq -{}(%rbp), %rsp", saved)),
+                }
+                for register in self.frame.saved.iter().rev() {
+                    self.line(&format!("popq %{}", register));
+                }
+                self.line("popq %rbp");
+                self.line("ret");
+            }
+            Terminator::Unreachable => self.line("ud2"),
+        }
+    }
+
+    fn write_jump(&mut self, target: BlockId, next: Option<BlockId>) {
+        if Some(target) != next {
+            self.line(&format!("jmp {}", self.block_label(target)));
+        }
+    }
+
+    /// assigns the phis of `to` for the edge from `from` as parallel moves
+    fn write_phis(&mut self, from: BlockId, to: BlockId) {
+        let mut moves = vec![];
+
+        for (phi, source) in self.function.phi_moves(from, to) {
+            for scalar in 0..self.frame.locations[phi.0 as usize].len() {
+                moves.push((
+                    self.frame.location(phi, scalar),
+                    self.frame.location(source, scalar),
+                    self.frame.class(phi, scalar),
+                ));
+            }
+        }
+
+        moves.retain(|(target, source, _)| target != source);
+
+        while !moves.is_empty() {
+            // a move whose target no other move reads
+            let free = moves
+                .iter()
+                .position(|(target, _, _)| moves.iter().all(|(_, source, _)| source != target));
+
+            if let Some(i) = free {
+                let (target, source, class) = moves.remove(i);
+                self.mov(class, source, target);
+                continue;
+            }
+
+            // every move is on a cycle, the first target is kept in a scratch register
+            let (target, _, class) = moves[0];
+            let scratch = match class {
+                Class::Int => Location::Register("r11"),
+                Class::Float => Location::Register("xmm15"),
+            };
+
+            self.mov(class, target, scratch);
+            for (_, source, _) in moves.iter_mut() {
+                if *source == target {
+                    *source = scratch;
+                }
+            }
+        }
+    }
+
+    /// a move that takes a scratch register when both are in memory
+    fn mov(&mut self, class: Class, source: Location, target: Location) {
+        if source == target {
+            return;
+        }
+
+        let is_memory = matches!((source, target), (Location::Stack(_), Location::Stack(_)));
+
+        match (class, is_memory) {
+            (Class::Int, false) => self.line(&format!("movq {}, {}", source, target)),
+            (Class::Int, true) => {
+                self.line(&format!("movq {}, %rax", source));
+                self.line(&format!("movq %rax, {}", target));
+            }
+            (Class::Float, false) => {
+                let is_register = matches!(
+                    (source, target),
+                    (Location::Register(_), Location::Register(_))
+                );
+                match is_register {
+                    true => self.line(&format!("movapd {}, {}", source, target)),
+                    false => self.line(&format!("movsd {}, {}", source, target)),
+                }
+            }
+            (Class::Float, true) => {
+                self.line(&format!("movsd {}, %xmm14", source));
+                self.line(&format!("movsd %xmm14, {}", target));
+            }
+        }
+    }
+
+    /// copies `width` scalars of `from` to `to`, starting at the offsets
+    fn copy(&mut self, to: Value, to_offset: usize, from: Value, from_offset: usize, width: usize) {
+        for i in 0..width {
+            let class = self.frame.class(to, to_offset + i);
+            let source = self.frame.location(from, from_offset + i);
+            let target = self.frame.location(to, to_offset + i);
+            self.mov(class, source, target);
+        }
+    }
+
+    /// the caller-saved registers of the values that are live across the call
+    fn live_across(&self) -> Vec<&'static str> {
+        let mut registers = vec![];
+
+        for ranges in self.frame.ranges.iter() {
+            for range in ranges.iter() {
+                if !(range.start < self.position && self.position < range.end) {
+                    continue;
+                }
+
+                if let Location::Register(register) = self.frame.location(range.value, range.scalar)
+                {
+                    if self.frame.spills.contains_key(register) {
+                        registers.push(register);
+                    }
+                }
+            }
+        }
+
+        registers
+    }
+
+    fn save(&mut self, registers: &[&'static str]) {
+        for register in registers.iter() {
+            let slot = Location::Stack(self.frame.spills[register]);
+            let class = if register.starts_with("xmm") {
+                Class::Float
+            } else {
+                Class::Int
+            };
+            self.mov(class, Location::Register(register), slot);
+        }
+    }
+
+    fn restore(&mut self, registers: &[&'static str]) {
+        for register in registers.iter() {
+            let slot = Location::Stack(self.frame.spills[register]);
+            let class = if register.starts_with("xmm") {
+                Class::Float
+            } else {
+                Class::Int
+            };
+            self.mov(class, slot, Location::Register(register));
+        }
+    }
+
+    fn write_instruction(&mut self, value: Value) {
+        let function = self.function;
+        let instruction = function.instruction(value);
+        let ty = &instruction.ty;
+        let width = self.frame.locations[value.0 as usize].len();
+        let target = |writer: &Self, scalar: usize| writer.frame.location(value, scalar);
+
+        match &instruction.kind {
+            InstKind::Const(Constant::Int(constant)) => {
+                let target = target(self, 0);
+
+                match i32::try_from(*constant) {
+                    Ok(constant) => self.line(&format!("movq ${}, {}", constant, target)),
+                    Err(_) => {
+                        self.line(&format!("movabsq ${}, %rax", constant));
+                        self.line(&format!("movq %rax, {}", target));
+                    }
+                }
+            }
+            InstKind::Const(Constant::Float(constant)) => {
+                let label = self.generator.float(*constant);
+                let source = format!("{}(%rip)", label);
+
+                match target(self, 0) {
+                    Location::Register(register) => {
+                        self.line(&format!("movsd {}, %{}", source, register));
+                    }
+                    target => {
+                        self.line(&format!("movsd {}, %xmm14", source));
+                        self.line(&format!("movsd %xmm14, {}", target));
+                    }
+                }
+            }
+
+            // the other scalars are never read
+            InstKind::Const(Constant::None) => {
+                self.line(&format!("movq $0, {}", target(self, 0)));
+            }
+            InstKind::Copy(source) => self.copy(value, 0, *source, 0, width),
+            InstKind::Neg(source) if *ty == Ty::Int => {
+                self.line(&format!("movq {}, %rax", self.frame.location(*source, 0)));
+                self.line("negq %rax");
+                self.line(&format!("movq %rax, {}", target(self, 0)));
+            }
+            InstKind::Neg(source) => {
+                self.line(&format!(
+                    "movsd {}, %xmm14",
+                    self.frame.location(*source, 0)
+                ));
+                self.line("xorpd .Lsign(%rip), %xmm14");
+                self.line(&format!("movsd %xmm14, {}", target(self, 0)));
+            }
+            InstKind::Binary {
+                op,
+                lhs,
+                rhs,
+                index,
+            } => {
+                let is_int = function.instruction(*lhs).ty == Ty::Int;
+                let (lhs, rhs) = (self.frame.location(*lhs, 0), self.frame.location(*rhs, 0));
+                let target = target(self, 0);
+
+                match (op, is_int) {
+                    (BinaryOp::Div | BinaryOp::Rem, true) => {
+                        self.write_division(*op, lhs, rhs, *index, target);
+                    }
+                    (BinaryOp::Eq | BinaryOp::Ne, true) => {
+                        self.line(&format!("movq {}, %rax", lhs));
+                        self.line(&format!("cmpq {}, %rax", rhs));
+
+                        match op {
+                            BinaryOp::Eq => self.line("sete %al"),
+                            _ => self.line("setne %al"),
+                        }
+                        self.line("movzbq %al, %rax");
+                        self.line(&format!("movq %rax, {}", target));
+                    }
+                    (op, true) => {
+                        let name = match op {
+                            BinaryOp::Add => "addq",
+                            BinaryOp::Sub => "subq",
+                            _ => "imulq",
+                        };
+
+                        self.line(&format!("movq {}, %rax", lhs));
+                        self.line(&format!("{} {}, %rax", name, rhs));
+                        self.line(&format!("movq %rax, {}", target));
+                    }
+                    (BinaryOp::Eq | BinaryOp::Ne, false) => {
+                        // unordered sets the parity flag, nan is not equal to itself
+                        self.line(&format!("movsd {}, %xmm14", lhs));
+                        self.line(&format!("ucomisd {}, %xmm14", rhs));
+
+                        match op {
+                            BinaryOp::Eq => {
+                                self.line("sete %al");
+                                self.line("setnp %cl");
+                                self.line("andb %cl, %al");
+                            }
+                            _ => {
+                                self.line("setne %al");
+                                self.line("setp %cl");
+                                self.line("orb %cl, %al");
+                            }
+                        }
+                        self.line("movzbq %al, %rax");
+                        self.line(&format!("movq %rax, {}", target));
+                    }
+                    (BinaryOp::Rem, false) => {
+                        let registers = self.live_across();
+                        self.save(&registers);
+                        self.line(&format!("movsd {}, %xmm0", lhs));
+                        self.line(&format!("movsd {}, %xmm1", rhs));
+                        self.line("call fmod@PLT");
+                        self.restore(&registers);
+                        self.line(&format!("movsd %xmm0, {}", target));
+                    }
+                    (op, false) => {
+                        let name = match op {
+                            BinaryOp::Add => "addsd",
+                            BinaryOp::Sub => "subsd",
+                            BinaryOp::Mul => "mulsd",
+                            _ => "divsd",
+                        };
+
+                        self.line(&format!("movsd {}, %xmm14", lhs));
+                        self.line(&format!("{} {}, %xmm14", name, rhs));
+                        self.line(&format!("movsd %xmm14, {}", target));
+                    }
+                }
+            }
+            InstKind::Struct(fields) => {
+                let mut offset = 0;
+
+                for field in fields.iter() {
+                    let width = self.frame.locations[field.0 as usize].len();
+                    self.copy(value, offset, *field, 0, width);
+                    offset += width;
+                }
+            }
+            InstKind::Field {
+                value: source,
+                field,
+            } => {
+                let ty = &function.instruction(*source).ty;
+                let (offset, width) = layout::field(self.generator.program, ty, *field);
+                self.copy(value, 0, *source, offset, width);
+            }
+            InstKind::SetField {
+                value: source,
+                field,
+                to,
+            } => {
+                let (offset, field_width) = layout::field(self.generator.program, ty, *field);
+                self.copy(value, 0, *source, 0, width);
+                self.copy(value, offset, *to, 0, field_width);
+            }
+            InstKind::Variant { variant, payload } => {
+                self.line(&format!("movq ${}, {}", variant, target(self, 0)));
+
+                for (i, field) in payload.iter().enumerate() {
+                    let program = self.generator.program;
+                    let (offset, width) = layout::payload(program, ty, *variant, i as u32);
+                    self.copy(value, offset, *field, 0, width);
+                }
+            }
+            InstKind::Tag(source) => self.copy(value, 0, *source, 0, 1),
+            InstKind::Payload {
+                value: source,
+                variant,
+                index,
+            } => {
+                let ty = &function.instruction(*source).ty;
+                let (offset, width) = layout::payload(self.generator.program, ty, *variant, *index);
+                self.copy(value, 0, *source, offset, width);
+            }
+            InstKind::IsNone(source) => {
+                self.line(&format!("cmpq $0, {}", self.frame.location(*source, 0)));
+                self.line("sete %al");
+                self.line("movzbq %al, %rax");
+                self.line(&format!("movq %rax, {}", target(self, 0)));
+            }
+            InstKind::Wrap(source) => {
+                self.line(&format!("movq $1, {}", target(self, 0)));
+
+                let width = self.frame.locations[source.0 as usize].len();
+                self.copy(value, 1, *source, 0, width);
+            }
+            InstKind::Unwrap(source) => self.copy(value, 0, *source, 1, width),
+
+            // assigned at the end of the predecessors
+            InstKind::Phi(_) => (),
+            InstKind::Print(printed) => self.write_print(*printed),
+        }
+    }
+
+    /// traps on a zero divisor, `i64::MIN / -1` wraps like in the interpreter
+    fn write_division(
+        &mut self,
+        op: BinaryOp,
+        lhs: Location,
+        rhs: Location,
+        index: u32,
+        target: Location,
+    ) {
+        let message = location(self.generator.source, index);
+        let string = self.generator.string(&message);
+        let (divide, done, is_zero) = (
+            self.generator.label(),
+            self.generator.label(),
+            self.generator.label(),
+        );
+
+        self.line(&format!("movq {}, %rcx", rhs));
+        self.line(&format!("movq {}, %rax", lhs));
+        self.line("testq %rcx, %rcx");
+        self.line(&format!("je {}", is_zero));
+        self.line("cmpq $-1, %rcx");
+        self.line(&format!("jne {}", divide));
+
+        match op {
+            BinaryOp::Div => self.line("negq %rax"),
+            _ => self.line("xorl %eax, %eax"),
+        }
+        self.line(&format!("jmp {}", done));
+
+        _ = writeln!(self.code, "{}:", is_zero);
+        self.line(&format!("leaq {}(%rip), %rdi", string));
+        self.line("call crap_division_by_zero");
+
+        _ = writeln!(self.code, "{}:", divide);
+        self.line("cqto");
+        self.line("idivq %rcx");
+        if op == BinaryOp::Rem {
+            self.line("movq %rdx, %rax");
+        }
+
+        _ = writeln!(self.code, "{}:", done);
+        self.line(&format!("movq %rax, {}", target));
+    }
+
+    fn write_print(&mut self, printed: Value) {
+        let ty = self.function.instruction(printed).ty.clone();
+        let registers = self.live_across();
+        self.save(&registers);
+
+        match ty {
+            Ty::Int => {
+                self.line(&format!("movq {}, %rdi", self.frame.location(printed, 0)));
+                self.line("call crap_print_i64");
+            }
+            Ty::Float => {
+                self.line(&format!("movsd {}, %xmm0", self.frame.location(printed, 0)));
+                self.line("call crap_print_f64");
+            }
+            ty => {
+                // the scalars go upwards from the lowest slot
+                for scalar in 0..self.frame.locations[printed.0 as usize].len() {
+                    let class = self.frame.class(printed, scalar);
+                    let slot = Location::Stack(self.frame.print + scalar as i32 * 8);
+                    self.mov(class, self.frame.location(printed, scalar), slot);
+                }
+
+                self.generator.define_printer(&ty);
+                self.line(&format!("leaq {}(%rbp), %rdi", self.frame.print));
+                self.line(&format!("call print_{}", mangle(&ty)));
+            }
+        }
+
+        self.line("call crap_print_newline");
+        self.restore(&registers);
+    }
+}
@@ -0,0 +1,51 @@
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub index: u32,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn error(index: u32, message: String) -> Diagnostic {
+        Diagnostic {
+            index,
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    pub fn warning(index: u32, message: String) -> Diagnostic {
+        Diagnostic {
+            index,
+            severity: Severity::Warning,
+            message,
+        }
+    }
+
+    /// 1-based line and column of the diagnostic inside `buffer`
+    pub fn position(&self, buffer: &str) -> (usize, usize) {
+        let before = &buffer[..self.index as usize];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+
+        (line, column)
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use std::convert::Infallible;
+
+use statements::{format_stmt, starts_ambiguously};
+
+use crate::{
+    ast::{
+        expressions::ExpressionNode,
+        printer::separated,
+        statements::{Docs, Statement},
+    },
+    diagnostics::{Diagnostic, Severity},
+    lexer::{
+        doc_text, lexeme, tokenize,
+        tokens::{Token, TokenNode},
+        validation::{validate_source, validate_tokens},
+    },
+    parser::parse,
+};
+
+mod expressions;
+mod statements;
+
+const INDENT: &str = "    ";
+
+/// prints the syntax tree back with the comments of the source in between,
+/// tokens are matched to the source in order to place the comments
+struct Formatter<'a> {
+    buffer: &'a str,
+    tokens: &'a [TokenNode],
+
+    // the next source token that is not printed yet
+    cursor: usize,
+
+    output: String,
+    indent: usize,
+    is_line_start: bool,
+
+    // the `(...)` open around the line, a comment inside them breaks the line
+    // one level deeper
+    nesting: usize,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(buffer: &'a str, tokens: &'a [TokenNode]) -> Formatter<'a> {
+        Formatter {
+            buffer,
+            tokens,
+            cursor: 0,
+            output: String::new(),
+            indent: 0,
+            is_line_start: true,
+            nesting: 0,
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+        self.is_line_start = false;
+    }
+
+    fn space(&mut self) {
+        if !self.is_line_start && !self.output.ends_with(' ') {
+            self.write(" ");
+        }
+    }
+
+    /// starts a new line, or only fixes the indentation when already at one
+    fn newline(&mut self) {
+        let length = self.output.trim_end_matches(' ').len();
+        self.output.truncate(length);
+
+        if !self.is_line_start {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&INDENT.repeat(self.indent + self.nesting));
+        self.is_line_start = true;
+    }
+
+    /// keeps one empty line, the formatter collapses runs of them
+    fn blank_line(&mut self) {
+        let length = self.output.trim_end_matches(' ').len();
+        self.output.truncate(length);
+
+        if !self.output.is_empty() && !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+
+        self.output.push_str(&INDENT.repeat(self.indent + self.nesting));
+    }
+
+    /// prints the comments in front of the token at `position` and moves past them,
+    /// line breaks next to a comment are kept, all others are up to the caller
+    fn flush(&mut self, position: usize) {
+        let mut breaks = 0;
+        let mut comment: Option<&str> = None;
+
+        while self.cursor < position {
+            let node = &self.tokens[self.cursor];
+            let text = lexeme(self.buffer, node.index, &node.token);
+            self.cursor += 1;
+
+            match node.token {
+                Token::Eol => breaks += text.matches('\n').count(),
+                Token::Comment | Token::DocComment => {
+                    if breaks > 0 || comment.is_some_and(is_line_comment) {
+                        if !self.is_line_start {
+                            self.newline();
+                        }
+
+                        if breaks > 1 {
+                            self.blank_line();
+                        }
+                    } else {
+                        self.space();
+                    }
+
+                    self.write(text);
+                    breaks = 0;
+                    comment = Some(text);
+                }
+                // `,` and `;` the formatter leaves out
+                _ => (),
+            }
+        }
+
+        // only a line comment needs the line break behind it
+        match comment {
+            Some(text) if is_line_comment(text) => self.newline(),
+            Some(_) if breaks > 0 => self.newline(),
+            Some(_) => self.space(),
+            None => (),
+        }
+
+        if breaks > 1 && self.is_line_start {
+            self.blank_line();
+        }
+    }
+
+    /// prints a node of the syntax tree with its source text
+    fn node(&mut self, node: &ExpressionNode) {
+        let position = self.tokens.partition_point(|token| token.index < node.index);
+        self.flush(position);
+        self.cursor = position + 1;
+
+        self.write(lexeme(self.buffer, node.index, &node.token));
+    }
+
+    /// prints a token that is not part of the syntax tree, like `)` or `,`
+    fn token(&mut self, token: Token, text: &str) {
+        if let Some(position) = self.find(&token) {
+            self.flush(position);
+            self.cursor = position + 1;
+        }
+
+        // `/* ... */,` like any other operand
+        if matches!(token, Token::Comma | Token::CloseParen) && self.output.ends_with("*/ ") {
+            self.output.pop();
+        }
+
+        self.write(text);
+    }
+
+    /// the position of the next source token, if it is `token`
+    fn find(&self, token: &Token) -> Option<usize> {
+        for (i, node) in self.tokens.iter().enumerate().skip(self.cursor) {
+            match &node.token {
+                found if found == token => return Some(i),
+                Token::Eol | Token::Comment | Token::DocComment => continue,
+
+                // optional separators the formatter may leave out
+                Token::Comma | Token::Semi => continue,
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    /// `true` when the next source token starts on a new line, e.g. behind a `{`
+    fn is_broken(&self) -> bool {
+        self.tokens[self.cursor..]
+            .iter()
+            .find(|node| !matches!(node.token, Token::Comment | Token::DocComment))
+            .is_some_and(|node| node.token == Token::Eol)
+    }
+
+    /// `true` when a line comment comes before the `}` that closes the body behind
+    /// the cursor
+    fn has_line_comment(&self) -> bool {
+        let mut depth = 0;
+
+        for node in &self.tokens[self.cursor..] {
+            match node.token {
+                Token::OpenBrace => depth += 1,
+                Token::CloseBrace if depth == 0 => return false,
+                Token::CloseBrace => depth -= 1,
+                Token::Comment | Token::DocComment
+                    if is_line_comment(lexeme(self.buffer, node.index, &node.token)) =>
+                {
+                    return true
+                }
+                _ => (),
+            }
+        }
+
+        false
+    }
+
+    /// prints the comments behind a statement or field on the same line,
+    /// skips the `,` and `;` in between
+    fn trailing(&mut self) {
+        while let Some(node) = self.tokens.get(self.cursor) {
+            match node.token {
+                Token::Comma | Token::Semi => (),
+                Token::Comment | Token::DocComment => {
+                    self.space();
+                    self.write(lexeme(self.buffer, node.index, &node.token));
+                }
+                _ => break,
+            }
+
+            self.cursor += 1;
+        }
+    }
+
+    /// prints what is between a `(` and its `)`
+    fn nested(&mut self, format: impl FnOnce(&mut Formatter)) {
+        self.nesting += 1;
+        format(self);
+        self.nesting -= 1;
+    }
+
+    /// `a, b, c` without the surrounding brackets
+    fn list<T>(&mut self, items: &[T], mut format_item: impl FnMut(&mut Formatter, &T)) {
+        let separator = |formatter: &mut Formatter| {
+            formatter.token(Token::Comma, ",");
+            formatter.write(" ");
+            Ok::<_, Infallible>(())
+        };
+
+        let Ok(()) = separated(self, items, separator, |formatter, item| {
+            format_item(formatter, item);
+            Ok(())
+        });
+    }
+
+    /// prints `{ item, ... }` on one line, or each item on its own line when the
+    /// source starts them on a new one or a line comment would break the line
+    fn body<T>(&mut self, items: &[T], mut format_item: impl FnMut(&mut Formatter, &T)) {
+        self.token(Token::OpenBrace, "{");
+
+        if items.is_empty() {
+            return self.token(Token::CloseBrace, "}");
+        }
+
+        if !self.is_broken() && !self.has_line_comment() {
+            self.write(" ");
+            self.list(items, format_item);
+            self.write(" ");
+            return self.token(Token::CloseBrace, "}");
+        }
+
+        // the items start their own lines, the `(...)` around the body do not count
+        let nesting = std::mem::take(&mut self.nesting);
+        self.indent += 1;
+        self.trailing();
+
+        for item in items {
+            self.newline();
+            format_item(self, item);
+            self.token(Token::Comma, ",");
+            self.trailing();
+        }
+
+        // comments in front of the `}` stay inside
+        if let Some(position) = self.find(&Token::CloseBrace) {
+            self.flush(position);
+        }
+
+        self.indent -= 1;
+        self.newline();
+        self.token(Token::CloseBrace, "}");
+        self.nesting = nesting;
+    }
+
+    fn finish(mut self) -> String {
+        let length = self.output.trim_end().len();
+        self.output.truncate(length);
+
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+
+        self.output
+    }
+}
+
+/// formats a source file, files with syntax errors are left alone
+pub fn format(buffer: &str) -> Result<String, Vec<Diagnostic>> {
+    let diagnostics = validate_source(buffer);
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let tokens = tokenize(buffer);
+    let mut diagnostics = validate_tokens(buffer, &tokens);
+
+    let (stmts, parser_diagnostics) = parse(tokens.clone());
+    diagnostics.extend(
+        parser_diagnostics
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error),
+    );
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+
+    let mut formatter = Formatter::new(buffer, &tokens);
+
+    for (i, stmt) in stmts.iter().enumerate() {
+        format_stmt(&mut formatter, stmt);
+
+        // a line that starts with `(`, `+` or `-` needs an explicit `;` in front
+        if stmts.get(i + 1).is_some_and(starts_ambiguously) {
+            formatter.write(";");
+        }
+
+        formatter.trailing();
+        formatter.newline();
+    }
+
+    formatter.flush(tokens.len());
+    let output = formatter.finish();
+
+    // the printed program has to parse to the same syntax tree
+    let (formatted, _) = parse(tokenize(&output));
+    if !is_same_syntax((buffer, &stmts), (&output, &formatted)) {
+        let message = "the formatted source would change the program".to_string();
+        return Err(vec![Diagnostic::error(0, message)]);
+    }
+
+    Ok(output)
+}
+
+fn is_line_comment(text: &str) -> bool {
+    text.starts_with("//")
+}
+
+/// compares two programs without the source positions, the doc comments and
+/// strings by their text in the source of each
+fn is_same_syntax(lhs: (&str, &[Statement]), rhs: (&str, &[Statement])) -> bool {
+    use Statement::*;
+
+    let ((lhs_buffer, lhs), (rhs_buffer, rhs)) = (lhs, rhs);
+
+    let is_same_docs = |lhs: &Docs, rhs: &Docs| {
+        lhs.len() == rhs.len()
+            && lhs.iter().zip(rhs.iter()).all(|(lhs, rhs)| {
+                doc_text(lhs_buffer, *lhs) == doc_text(rhs_buffer, *rhs)
+            })
+    };
+
+    let is_same_stmt = |lhs: &Statement, rhs: &Statement| {
+        let is_same_header = match (lhs, rhs) {
+            (
+                Variable { flags, docs, .. },
+                Variable {
+                    flags: other_flags,
+                    docs: other_docs,
+                    ..
+                },
+            ) => flags == other_flags && is_same_docs(docs, other_docs),
+            (Struct { docs, .. }, Struct { docs: other, .. })
+            | (Enum { docs, .. }, Enum { docs: other, .. })
+            | (Alias { docs, .. }, Alias { docs: other, .. }) => is_same_docs(docs, other),
+            (Expression { .. }, Expression { .. }) | (Assignment { .. }, Assignment { .. }) => true,
+            _ => false,
+        };
+
+        let is_same_node = |(lhs, rhs): (&ExpressionNode, &ExpressionNode)| {
+            let is_same_text = lhs.token != Token::String
+                || lexeme(lhs_buffer, lhs.index, &lhs.token)
+                    == lexeme(rhs_buffer, rhs.index, &rhs.token);
+
+            lhs.size == rhs.size
+                && lhs.token == rhs.token
+                && lhs.kind == rhs.kind
+                && lhs.value == rhs.value
+                && is_same_text
+        };
+
+        is_same_header
+            && lhs.expr().len() == rhs.expr().len()
+            && lhs.expr().iter().zip(rhs.expr()).all(is_same_node)
+    };
+
+    lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| is_same_stmt(lhs, rhs))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{lexer::tokenize, parser::parse};
+
+    use super::{format, is_same_syntax};
+
+    /// formats `buffer` twice, the second time changes nothing and both
+    /// parse to the tree of `buffer`
+    fn format_twice(buffer: &str) -> String {
+        let formatted = format(buffer).unwrap();
+        assert_eq!(format(&formatted).unwrap(), formatted);
+
+        let (stmts, _) = parse(tokenize(buffer));
+        let (reparsed, diagnostics) = parse(tokenize(&formatted));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(is_same_syntax((buffer, &stmts), (&formatted, &reparsed)));
+
+        formatted
+    }
+
+    #[test]
+    fn examples() {
+        for entry in fs::read_dir("examples").unwrap() {
+            let path = entry.unwrap().path();
+
+            if path.extension().is_some_and(|extension| extension == "crap") {
+                format_twice(&fs::read_to_string(path).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn comments_inside_expressions() {
+        let formatted = format_twice("let b = (1 +\n// inside\n2)\n");
+        assert_eq!(formatted, "let b = (1 +\n    // inside\n    2)\n");
+
+        let formatted = format_twice("let a = 3\nmatch a { 3 => 1, // arm\n_ => 2 }\n");
+        assert_eq!(formatted, "let a = 3\nmatch a {\n    3 => 1, // arm\n    _ => 2,\n}\n");
+
+        let formatted = format_twice("let x = f(1,\n// second\n2)\n");
+        assert_eq!(formatted, "let x = f(1,\n    // second\n    2)\n");
+
+        let buffer = "let a = 3\nmatch a {\n3 => (a *\n// deep\n2),\n_ => 2\n}\n";
+        let formatted = format_twice(buffer);
+        assert_eq!(
+            formatted,
+            "let a = 3\nmatch a {\n    3 => (a *\n        // deep\n        2),\n    _ => 2,\n}\n"
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{
+    ast::{expressions::ExpressionKind, view::ExprRef},
+    lexer::tokens::Token,
+};
+
+use super::Formatter;
+
+/// prints a subtree (root node last), parentheses only come from `Block` nodes
+pub fn format_expr(formatter: &mut Formatter, expr: ExprRef) {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+    let operands = expr.children();
+
+    match node.kind {
+        Literal => formatter.node(node),
+        Prefix => {
+            formatter.node(node);
+
+            // `- -1` instead of `--1`
+            if *operands[0].kind() == Prefix {
+                formatter.write(" ");
+            }
+
+            format_expr(formatter, operands[0]);
+        }
+        Sufix => {
+            format_expr(formatter, operands[0]);
+            formatter.node(node);
+        }
+        Block => {
+            formatter.node(node);
+            formatter.nested(|formatter| format_expr(formatter, operands[0]));
+            formatter.token(Token::CloseParen, ")");
+        }
+        Binary => {
+            let (first, chain) = expr.chain();
+            format_expr(formatter, first);
+
+            for expr in chain {
+                formatter.write(" ");
+                formatter.node(expr.node());
+                formatter.write(" ");
+                format_expr(formatter, expr.rhs());
+            }
+        }
+        Arm => {
+            format_expr(formatter, operands[0]);
+            formatter.write(" ");
+            formatter.node(node);
+            formatter.write(" ");
+            format_expr(formatter, operands[1]);
+        }
+        Member => {
+            format_expr(formatter, operands[0]);
+            formatter.node(node);
+            format_expr(formatter, operands[1]);
+        }
+        Ternary => {
+            // [R][M][L][T]
+            format_expr(formatter, operands[2]);
+            formatter.write(" ");
+            formatter.node(node);
+            formatter.write(" ");
+            format_expr(formatter, operands[1]);
+            formatter.write(" ");
+            formatter.token(Token::Colon, ":");
+            formatter.write(" ");
+            format_expr(formatter, operands[0]);
+        }
+        Call => {
+            format_expr(formatter, operands[0]);
+            formatter.node(node);
+            formatter.nested(|formatter| {
+                formatter.list(&operands[1..], |formatter, arg| format_expr(formatter, *arg));
+            });
+            formatter.token(Token::CloseParen, ")");
+        }
+        Struct => {
+            formatter.node(node);
+            formatter.write(" ");
+            formatter.body(&operands, |formatter, field| format_expr(formatter, *field));
+        }
+        Field => {
+            formatter.node(node);
+            formatter.token(Token::Colon, ":");
+            formatter.write(" ");
+            format_expr(formatter, operands[0]);
+        }
+        Match => {
+            formatter.node(node);
+            formatter.write(" ");
+            format_expr(formatter, operands[0]);
+            formatter.write(" ");
+            formatter.body(&operands[1..], |formatter, arm| format_expr(formatter, *arm));
+        }
+        Enum | Variant => panic!("declarations are formatted as statements"),
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    ast::{
+        statements::{Statement, StatementFlag},
+        view::ExprRef,
+    },
+    lexer::tokens::Token,
+    utils::bit_array::{BitArray, Byte},
+};
+
+use super::{expressions::format_expr, Formatter};
+
+pub fn format_stmt(formatter: &mut Formatter, stmt: &Statement) {
+    use Statement::*;
+
+    let parts = stmt.expressions();
+
+    match stmt {
+        Expression { .. } => format_expr(formatter, parts[0]),
+        Variable { flags, .. } => format_variable_stmt(formatter, &parts, *flags),
+        Assignment { .. } => format_assignment_stmt(formatter, parts[0], parts[1]),
+        Struct { .. } => format_struct_stmt(formatter, parts[0]),
+        Enum { .. } => format_enum_stmt(formatter, parts[0]),
+        Alias { .. } => format_alias_stmt(formatter, parts[0], parts[1]),
+
+        // `format` refuses sources with syntax errors
+        Error => panic!("cannot format a syntax error"),
+    }
+}
+
+/// `true` when the statement begins with `(`, `+` or `-`, on a new line
+/// these would also continue the statement in front of it
+pub fn starts_ambiguously(stmt: &Statement) -> bool {
+    let first = match stmt {
+        Statement::Expression { expr } | Statement::Assignment { expr } => {
+            expr.iter().min_by_key(|node| node.index)
+        }
+        _ => None,
+    };
+
+    first.is_some_and(|node| matches!(node.token, Token::OpenParen | Token::Plus | Token::Minus))
+}
+
+/// `[E value][E type]?[E symbol]`
+fn format_variable_stmt(formatter: &mut Formatter, parts: &[ExprRef], flags: Byte) {
+    match StatementFlag::IsConst.has(flags) {
+        true => formatter.token(Token::Const, "const"),
+        false => formatter.token(Token::Let, "let"),
+    }
+
+    formatter.write(" ");
+    format_expr(formatter, parts[parts.len() - 1]);
+
+    if StatementFlag::HasType.has(flags) {
+        formatter.token(Token::Colon, ":");
+        formatter.write(" ");
+        format_expr(formatter, parts[1]);
+    }
+
+    formatter.write(" ");
+    formatter.token(Token::Equal, "=");
+    formatter.write(" ");
+    format_expr(formatter, parts[0]);
+}
+
+fn format_assignment_stmt(formatter: &mut Formatter, value: ExprRef, target: ExprRef) {
+    format_expr(formatter, target);
+    formatter.write(" ");
+    formatter.token(Token::Equal, "=");
+    formatter.write(" ");
+    format_expr(formatter, value);
+}
+
+fn format_struct_stmt(formatter: &mut Formatter, expr: ExprRef) {
+    formatter.token(Token::Struct, "struct");
+    formatter.write(" ");
+    formatter.node(expr.node());
+    formatter.write(" ");
+    formatter.body(&expr.children(), |formatter, field| format_expr(formatter, *field));
+}
+
+fn format_enum_stmt(formatter: &mut Formatter, expr: ExprRef) {
+    formatter.token(Token::Enum, "enum");
+    formatter.write(" ");
+    formatter.node(expr.node());
+    formatter.write(" ");
+    formatter.body(&expr.children(), |formatter, variant| {
+        let payload = variant.children();
+        formatter.node(variant.node());
+
+        if !payload.is_empty() {
+            formatter.token(Token::OpenParen, "(");
+            formatter.nested(|formatter| {
+                formatter.list(&payload, |formatter, item| format_expr(formatter, *item));
+            });
+            formatter.token(Token::CloseParen, ")");
+        }
+    });
+}
+
+fn format_alias_stmt(formatter: &mut Formatter, ty: ExprRef, symbol: ExprRef) {
+    formatter.token(Token::Type, "type");
+    formatter.write(" ");
+    format_expr(formatter, symbol);
+    formatter.write(" ");
+    formatter.token(Token::Equal, "=");
+    formatter.write(" ");
+    format_expr(formatter, ty);
+}
@@ -0,0 +1,376 @@
+use std::{iter, mem, ops::Range};
+
+use crate::{
+    ast::statements::Statement,
+    diagnostics::Diagnostic,
+    lexer::{
+        relex, tokenize,
+        tokens::{Token, TokenNode},
+    },
+    parser::{parse_from, ParsedStatement},
+};
+
+/// replaces the bytes in `range` of the source with `text`
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+/// the tokens a statement looked at and its parser warnings
+#[derive(Debug, PartialEq)]
+struct Span {
+    tokens: Range<usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// a source that stays tokenized and parsed while it is edited,
+/// an edit only relexes and reparses the region around it
+pub struct Document {
+    buffer: String,
+    tokens: Vec<TokenNode>,
+    stmts: Vec<Statement>,
+    spans: Vec<Span>,
+
+    // the byte offsets of the strings and block comments that are never closed
+    unterminated: Vec<u32>,
+}
+
+impl Document {
+    pub fn new(buffer: String) -> Document {
+        let tokens = tokenize(&buffer);
+        let (parsed, _) = parse_from(&tokens, 0, |_| false);
+        let (stmts, spans) = split(parsed);
+        let unterminated = unterminated(buffer.as_bytes(), &tokens, 0..tokens.len());
+
+        Document {
+            buffer,
+            tokens,
+            stmts,
+            spans,
+            unterminated,
+        }
+    }
+
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    pub fn tokens(&self) -> &[TokenNode] {
+        &self.tokens
+    }
+
+    pub fn statements(&self) -> &[Statement] {
+        &self.stmts
+    }
+
+    /// the tokens of each statement, up to the start of the next one
+    pub fn statement_tokens(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let ends = self.spans.iter().skip(1).map(|span| span.tokens.start);
+        let ends = ends.chain(iter::once(self.tokens.len()));
+
+        self.spans.iter().zip(ends).map(|(span, end)| span.tokens.start..end)
+    }
+
+    /// warnings of the parser, in source order
+    pub fn diagnostics(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.spans.iter().flat_map(|span| span.diagnostics.iter())
+    }
+
+    pub fn apply(&mut self, edit: &Edit) {
+        let Edit { range, text } = edit;
+
+        self.buffer.replace_range(range.clone(), text);
+        let delta = text.len() as i64 - range.len() as i64;
+
+        let (old_end, new) = self.relex(range.start, range.start + text.len(), delta);
+        self.reparse(old_end, new, delta);
+    }
+
+    /// relexes from the last token in front of `start` until the new tokens line up
+    /// with the old ones behind `end`, returns the replaced and the new token range
+    fn relex(&mut self, start: usize, end: usize, delta: i64) -> (usize, Range<usize>) {
+        // the token in front of the edit may grow into it
+        let mut first = self
+            .tokens
+            .partition_point(|node| (node.index as usize) < start)
+            .saturating_sub(1);
+
+        // an unterminated string or comment in front may be closed by the edit,
+        // there is none in front of the first one
+        if let Some(&opener) = self.unterminated.first() {
+            first = first.min(self.tokens.partition_point(|node| node.index < opener));
+        }
+
+        let restart = match first {
+            0 => 0,
+            _ => self.tokens[first].index as usize,
+        };
+
+        let old = self.tokens.split_off(first);
+        let mut synced = None;
+
+        let (tokens, _) = relex(&self.buffer, mem::take(&mut self.tokens), restart, |node| {
+            if (node.index as usize) < end {
+                return false;
+            }
+
+            let index = (node.index as i64 - delta) as u32;
+            let position = old.partition_point(|old| old.index < index);
+
+            match old.get(position) {
+                Some(old) if old.index == index && old.token == node.token => {
+                    synced = Some(position);
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        self.tokens = tokens;
+        let new_end = self.tokens.len();
+
+        let old_end = match synced {
+            Some(position) => {
+                self.tokens.extend(old[position + 1..].iter().map(|node| TokenNode {
+                    index: shift(node.index, delta),
+                    ..node.clone()
+                }));
+
+                first + position + 1
+            }
+            None => first + old.len(),
+        };
+
+        // the ones behind the synced token only moved
+        let moved = match synced {
+            Some(position) => self
+                .unterminated
+                .iter()
+                .filter(|&&opener| opener > old[position].index)
+                .map(|&opener| shift(opener, delta))
+                .collect(),
+            None => vec![],
+        };
+
+        self.unterminated = unterminated(self.buffer.as_bytes(), &self.tokens, first..new_end);
+        self.unterminated.extend(moved);
+
+        (old_end, first..new_end)
+    }
+
+    /// reparses the statements that looked at tokens in `new`, `old_end` is the end
+    /// of the replaced tokens in the old token stream
+    fn reparse(&mut self, old_end: usize, new: Range<usize>, delta: i64) {
+        let token_delta = new.end as i64 - old_end as i64;
+
+        let mut first = self.spans.partition_point(|span| span.tokens.end <= new.start);
+        let start = match self.spans.get(first) {
+            Some(span) => span.tokens.start,
+            None => {
+                first = 0;
+                0
+            }
+        };
+
+        let old_stmts = self.stmts.split_off(first);
+        let old_spans = self.spans.split_off(first);
+
+        let (parsed, synced) = parse_from(&self.tokens, start, |index| {
+            // `docs` and line breaks look back to the previous statement,
+            // it has to end in the unchanged tokens
+            let previous = self.tokens[..index]
+                .iter()
+                .rposition(|node| !is_trivia(node))
+                .map_or(0, |previous| previous + 1);
+
+            if previous < new.end {
+                return false;
+            }
+
+            let index = index as i64 - token_delta;
+            old_spans
+                .binary_search_by_key(&index, |span| span.tokens.start as i64)
+                .is_ok()
+        });
+
+        let (stmts, spans) = split(parsed);
+        self.stmts.extend(stmts);
+        self.spans.extend(spans);
+
+        let Some(index) = synced else {
+            return;
+        };
+
+        let index = index as i64 - token_delta;
+        let position = old_spans
+            .binary_search_by_key(&index, |span| span.tokens.start as i64)
+            .unwrap();
+
+        for mut stmt in old_stmts.into_iter().skip(position) {
+            for node in stmt.expr_mut().iter_mut() {
+                node.index = shift(node.index, delta);
+            }
+
+            for index in stmt.docs_mut().into_iter().flatten() {
+                *index = shift(*index, delta);
+            }
+
+            self.stmts.push(stmt);
+        }
+
+        for span in old_spans.into_iter().skip(position) {
+            let Span { tokens, mut diagnostics } = span;
+
+            for diagnostic in diagnostics.iter_mut() {
+                diagnostic.index = shift(diagnostic.index, delta);
+            }
+
+            let start = (tokens.start as i64 + token_delta) as usize;
+            let end = (tokens.end as i64 + token_delta) as usize;
+
+            self.spans.push(Span {
+                tokens: start..end,
+                diagnostics,
+            });
+        }
+    }
+}
+
+fn split(parsed: Vec<ParsedStatement>) -> (Vec<Statement>, Vec<Span>) {
+    parsed
+        .into_iter()
+        .map(|parsed| {
+            let span = Span {
+                tokens: parsed.tokens,
+                diagnostics: parsed.diagnostics,
+            };
+
+            (parsed.stmt, span)
+        })
+        .unzip()
+}
+
+fn shift(index: u32, delta: i64) -> u32 {
+    (index as i64 + delta) as u32
+}
+
+/// the byte offsets of the unterminated strings and comments in `tokens[range]`
+fn unterminated(bytes: &[u8], tokens: &[TokenNode], range: Range<usize>) -> Vec<u32> {
+    // an unterminated string or `/*` is an `Error` token
+    let is_unterminated = |&i: &usize| {
+        tokens[i].token == Token::Error && matches!(bytes[tokens[i].index as usize], b'"' | b'/')
+    };
+
+    range.filter(is_unterminated).map(|i| tokens[i].index).collect()
+}
+
+fn is_trivia(node: &TokenNode) -> bool {
+    matches!(node.token, Token::Eol | Token::Comment | Token::DocComment | Token::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// xorshift, the edits are the same on every run
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    /// text that changes the structure around an edit
+    const PIECES: &[&str] = &[
+        ";", "\n", "\r\n", "(", ")", "{", "}", ",", "\"", "\"text\"", "/*", "*/", "//", "///", "/**",
+        "1", "2.5", "x", "let", "struct", " = ", " ", "+", "?", "?.", "??", ".", "=>", "é",
+    ];
+
+    fn examples() -> String {
+        let mut paths: Vec<_> = fs::read_dir("examples")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "crap"))
+            .collect();
+        paths.sort();
+
+        paths.into_iter().map(|path| fs::read_to_string(path).unwrap()).collect()
+    }
+
+    /// inserts, removes or replaces a few bytes at a random character boundary
+    fn random_edit(buffer: &str, random: &mut Random) -> Edit {
+        let boundary = |mut index: usize| {
+            while !buffer.is_char_boundary(index) {
+                index -= 1;
+            }
+            index
+        };
+
+        let start = boundary(random.next(buffer.len() + 1));
+        let end = boundary((start + random.next(8)).min(buffer.len())).max(start);
+        let text = PIECES[random.next(PIECES.len())].to_string();
+
+        match random.next(3) {
+            0 => Edit { range: start..start, text },
+            1 => Edit { range: start..end, text: String::new() },
+            _ => Edit { range: start..end, text },
+        }
+    }
+
+    /// the incremental result has to match a full relex and reparse
+    fn assert_reparsed(document: &Document, edit: &Edit) {
+        let expected = Document::new(document.buffer.clone());
+
+        assert_eq!(document.tokens, expected.tokens, "tokens differ after {:?}", edit);
+        assert_eq!(document.stmts, expected.stmts, "statements differ after {:?}", edit);
+        assert_eq!(document.spans, expected.spans, "spans differ after {:?}", edit);
+        assert_eq!(
+            document.unterminated, expected.unterminated,
+            "unterminated differ after {:?}",
+            edit
+        );
+    }
+
+    #[test]
+    fn random_edits_match_a_full_reparse() {
+        let source = examples();
+
+        for seed in 1..=8 {
+            let mut random = Random(0x2545_f491_4f6c_dd1d ^ seed);
+            let mut document = Document::new(source.clone());
+
+            for _ in 0..400 {
+                let edit = random_edit(document.buffer(), &mut random);
+                document.apply(&edit);
+                assert_reparsed(&document, &edit);
+            }
+        }
+    }
+
+    #[test]
+    fn edits_close_and_open_strings_and_comments() {
+        let mut document = Document::new("let a = \"x\nlet b = 1\n/* c\nlet d = 2\n".to_string());
+        assert_eq!(document.unterminated.len(), 2);
+
+        let edits = [
+            // closes the string and the comment in front of the edit
+            Edit { range: 35..35, text: "\"*/".to_string() },
+            // opens them again before the end
+            Edit { range: 0..0, text: "/* \"".to_string() },
+            Edit { range: 0..4, text: String::new() },
+            Edit { range: 10..11, text: String::new() },
+        ];
+
+        for edit in edits.iter() {
+            document.apply(edit);
+            assert_reparsed(&document, edit);
+        }
+    }
+}
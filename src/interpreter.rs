@@ -0,0 +1,127 @@
+use std::collections::{HashMap, HashSet};
+
+use statements::execute_stmt;
+use values::Value;
+
+use crate::{ast::statements::Statement, diagnostics::Diagnostic, utils::interner::Symbol};
+
+mod expressions;
+mod statements;
+pub mod values;
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+#[derive(Clone)]
+pub struct Interpreter {
+    scopes: Vec<HashMap<Symbol, Value>>,
+
+    // field names in declaration order
+    structs: HashMap<Symbol, Vec<Symbol>>,
+    enums: HashSet<Symbol>,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            scopes: vec![HashMap::new()],
+            structs: HashMap::new(),
+            enums: HashSet::new(),
+        }
+    }
+
+    /// runs a single statement, expression statements return their value
+    pub fn execute(&mut self, stmt: &Statement) -> Result<Option<Value>> {
+        execute_stmt(self, stmt)
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+
+    fn lookup_mut(&mut self, name: Symbol) -> Option<&mut Value> {
+        self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(&name))
+    }
+
+    fn declare(&mut self, name: Symbol, value: Value) {
+        self.scopes.last_mut().unwrap().insert(name, value);
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
+}
+
+/// runs a checked program and collects the values of its expression statements
+pub fn evaluate(stmts: &[Statement]) -> Result<Vec<Value>> {
+    let mut interpreter = Interpreter::new();
+    let mut values = vec![];
+
+    for stmt in stmts {
+        if let Some(value) = interpreter.execute(stmt)? {
+            values.push(value);
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::tokenize, parser::parse, semantic::check};
+
+    use super::evaluate;
+
+    /// the values of the expression statements of a checked `buffer`
+    fn values(buffer: &str) -> Vec<String> {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(check(&stmts), vec![]);
+
+        let values = evaluate(&stmts).unwrap();
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn structs() {
+        let buffer = "\
+struct Point { x: i64, y: i64 }
+struct Line { from: Point, to: Point }
+let line = Line { from: Point { x: 0, y: 0 }, to: Point { y: 4, x: 3 } }
+line.to.x = line.from.x + 1
+line.to
+line.to.y * 2
+";
+
+        assert_eq!(values(buffer), ["Point { x: 1, y: 4 }", "8"]);
+    }
+
+    #[test]
+    fn matches() {
+        let buffer = "\
+enum Shape { Circle(f64), Rect(f64, f64), Empty }
+let shapes = Shape.Rect(2.0, 3.5)
+shapes
+match shapes {
+    Shape.Circle(r) => r * 2.0,
+    Shape.Rect(w, h) => w * h,
+    Shape.Empty => 0.0,
+}
+match Shape.Empty {
+    Shape.Circle(r) => r,
+    _ => 1.0,
+}
+match 1 + 2 {
+    3 => 30,
+    n => n,
+}
+match 4 {
+    3 => 30,
+    n => n * 2,
+}
+";
+
+        assert_eq!(values(buffer), ["Shape.Rect(2.0, 3.5)", "7.0", "1.0", "30", "8"]);
+    }
+}
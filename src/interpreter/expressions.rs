@@ -0,0 +1,295 @@
+use crate::{
+    ast::{
+        expressions::{ExpressionKind, ExpressionNode},
+        view::ExprRef,
+    },
+    diagnostics::Diagnostic,
+    lexer::tokens::{Token, TokenValue},
+    utils::interner::Symbol,
+};
+
+use super::{values::Value, Interpreter, Result};
+
+/// evaluates a subtree (root node last)
+pub fn evaluate_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+
+    match node.kind {
+        Literal => evaluate_literal_expr(interpreter, node),
+        Prefix | Sufix => evaluate_unary_expr(interpreter, expr),
+        Block => evaluate_expr(interpreter, expr.operand()),
+        Binary => evaluate_binary_expr(interpreter, expr),
+        Ternary => evaluate_ternary_expr(interpreter, expr),
+        Member => evaluate_member_expr(interpreter, expr),
+        Struct => evaluate_struct_expr(interpreter, expr),
+        Call => evaluate_call_expr(interpreter, expr),
+        Match => evaluate_match_expr(interpreter, expr),
+
+        // only reachable through their parent nodes
+        Field => evaluate_expr(interpreter, expr.operand()),
+        Arm => evaluate_expr(interpreter, expr.lhs()),
+        Enum | Variant => Err(error(node, "unexpected declaration".to_string())),
+    }
+}
+
+fn error(node: &ExpressionNode, message: String) -> Diagnostic {
+    Diagnostic::error(node.index, message)
+}
+
+fn evaluate_literal_expr(interpreter: &mut Interpreter, node: &ExpressionNode) -> Result<Value> {
+    match (&node.token, node.value) {
+        (Token::Number, TokenValue::Int(value)) => Ok(Value::Int(value)),
+        (Token::Number, TokenValue::Float(value)) => Ok(Value::Float(value)),
+        (Token::Number, _) => Err(error(node, "integer literal is too large".to_string())),
+        (Token::Null, _) => Ok(Value::None),
+        (Token::Identifier, TokenValue::Symbol(name)) => match interpreter.lookup(name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(error(node, format!("unknown binding `{}`", name))),
+        },
+        (token, _) => Err(error(node, format!("unexpected literal `{:?}`", token))),
+    }
+}
+
+fn evaluate_unary_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let node = expr.node();
+    let value = evaluate_expr(interpreter, expr.operand())?;
+
+    match (&node.token, value) {
+        (Token::Plus, value @ (Value::Int(_) | Value::Float(_))) => Ok(value),
+        (Token::Minus, Value::Int(value)) => Ok(Value::Int(value.wrapping_neg())),
+        (Token::Minus, Value::Float(value)) => Ok(Value::Float(-value)),
+        (token, value) => Err(error(node, format!("cannot apply {:?} to `{}`", token, value))),
+    }
+}
+
+fn evaluate_binary_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let (first, chain) = expr.chain();
+    let mut value = evaluate_expr(interpreter, first)?;
+
+    for expr in chain {
+        value = evaluate_binary_operator(interpreter, expr, value)?;
+    }
+
+    Ok(value)
+}
+
+/// applies a binary operator to the value of its left side, integer arithmetic
+/// wraps around on overflow, division by zero is an error
+fn evaluate_binary_operator(
+    interpreter: &mut Interpreter,
+    expr: ExprRef,
+    lhs: Value,
+) -> Result<Value> {
+    use Token::*;
+
+    let node = expr.node();
+
+    // `??` only evaluates its right side when needed
+    if node.token == QuestionQuestion {
+        return match lhs {
+            Value::None => evaluate_expr(interpreter, expr.rhs()),
+            lhs => Ok(lhs),
+        };
+    }
+
+    let rhs = evaluate_expr(interpreter, expr.rhs())?;
+
+    match (&node.token, lhs, rhs) {
+        (Slash | Percent, Value::Int(_), Value::Int(0)) => {
+            Err(error(node, "division by zero".to_string()))
+        }
+
+        (Plus, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs.wrapping_add(rhs))),
+        (Minus, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs.wrapping_sub(rhs))),
+        (Star, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs.wrapping_mul(rhs))),
+        (Slash, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs.wrapping_div(rhs))),
+        (Percent, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs.wrapping_rem(rhs))),
+
+        (Plus, Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs + rhs)),
+        (Minus, Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs - rhs)),
+        (Star, Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs * rhs)),
+        (Slash, Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs / rhs)),
+        (Percent, Value::Float(lhs), Value::Float(rhs)) => Ok(Value::Float(lhs % rhs)),
+
+        (token, lhs, rhs) => Err(error(
+            node,
+            format!("cannot apply {:?} to `{}` and `{}`", token, lhs, rhs),
+        )),
+    }
+}
+
+fn evaluate_ternary_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let condition = evaluate_expr(interpreter, expr.cond())?;
+
+    match condition.is_truthy() {
+        true => evaluate_expr(interpreter, expr.mhs()),
+        false => evaluate_expr(interpreter, expr.rhs()),
+    }
+}
+
+/// `Enum.Variant` paths, `None` if `expr` does not name an enum
+fn resolve_variant(interpreter: &Interpreter, expr: ExprRef) -> Option<(Symbol, Symbol)> {
+    if *expr.kind() != ExpressionKind::Member || *expr.token() != Token::Dot {
+        return None;
+    }
+
+    let lhs = expr.lhs().node();
+    if lhs.kind != ExpressionKind::Literal || lhs.token != Token::Identifier {
+        return None;
+    }
+
+    let name = lhs.symbol();
+    if interpreter.lookup(name).is_some() || !interpreter.enums.contains(&name) {
+        return None;
+    }
+
+    Some((name, expr.rhs().symbol()))
+}
+
+fn evaluate_member_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    if let Some((name, variant)) = resolve_variant(interpreter, expr) {
+        let payload = vec![];
+        return Ok(Value::Enum { name, variant, payload });
+    }
+
+    let node = expr.node();
+    let field = expr.rhs().node();
+    let field_name = field.symbol();
+
+    match evaluate_expr(interpreter, expr.lhs())? {
+        Value::None if node.token == Token::QuestionDot => Ok(Value::None),
+        Value::Struct { fields, .. } => fields
+            .into_iter()
+            .find(|(name, _)| *name == field_name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| error(field, format!("unknown field `{}`", field_name))),
+        value => Err(error(field, format!("no field `{}` on `{}`", field_name, value))),
+    }
+}
+
+fn evaluate_struct_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let node = expr.node();
+    let name = node.symbol();
+
+    let mut values = vec![];
+    for field in expr.children() {
+        values.push((field.symbol(), evaluate_expr(interpreter, field)?));
+    }
+
+    let declared = match interpreter.structs.get(&name) {
+        Some(declared) => declared.clone(),
+        None => return Err(error(node, format!("unknown struct `{}`", name))),
+    };
+
+    // keep the declaration order
+    let mut fields = vec![];
+    for field_name in declared {
+        match values.iter().position(|(name, _)| *name == field_name) {
+            Some(position) => fields.push((field_name, values.swap_remove(position).1)),
+            None => return Err(error(node, format!("missing field `{}`", field_name))),
+        }
+    }
+
+    Ok(Value::Struct { name, fields })
+}
+
+fn evaluate_call_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let node = expr.node();
+    let operands = expr.children();
+    let (callee, arguments) = operands.split_first().unwrap();
+
+    let (name, variant) = match resolve_variant(interpreter, *callee) {
+        Some(path) => path,
+        None => return Err(error(node, "expression is not callable".to_string())),
+    };
+
+    let mut payload = vec![];
+    for argument in arguments {
+        payload.push(evaluate_expr(interpreter, *argument)?);
+    }
+
+    Ok(Value::Enum { name, variant, payload })
+}
+
+/// tries to match `value` against a pattern and collects its bindings
+fn match_pattern(
+    interpreter: &Interpreter,
+    expr: ExprRef,
+    value: &Value,
+    bindings: &mut Vec<(Symbol, Value)>,
+) -> bool {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+
+    match (&node.kind, &node.token) {
+        (Literal, Token::Identifier) => {
+            let name = node.symbol();
+
+            if name.as_str() != "_" {
+                bindings.push((name, value.clone()));
+            }
+
+            true
+        }
+        (Literal, Token::Null) => *value == Value::None,
+        (Literal, Token::Number) => match (node.value, value) {
+            (TokenValue::Int(literal), Value::Int(value)) => literal == *value,
+            (TokenValue::Float(literal), Value::Float(value)) => literal == *value,
+            _ => false,
+        },
+        (Member, _) | (Call, _) => {
+            let (path, arguments) = match node.kind {
+                Call => {
+                    let operands = expr.children();
+                    let (callee, arguments) = operands.split_first().unwrap();
+                    (*callee, arguments.to_vec())
+                }
+                _ => (expr, vec![]),
+            };
+
+            let Some((name, variant)) = resolve_variant(interpreter, path) else {
+                return false;
+            };
+
+            match value {
+                Value::Enum { name: value_name, variant: value_variant, payload }
+                    if *value_name == name && *value_variant == variant =>
+                {
+                    arguments
+                        .iter()
+                        .zip(payload.iter())
+                        .all(|(argument, value)| match_pattern(interpreter, *argument, value, bindings))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+fn evaluate_match_expr(interpreter: &mut Interpreter, expr: ExprRef) -> Result<Value> {
+    let node = expr.node();
+    let operands = expr.children();
+    let (subject, arms) = operands.split_first().unwrap();
+
+    let value = evaluate_expr(interpreter, *subject)?;
+
+    for arm in arms {
+        let mut bindings = vec![];
+
+        if !match_pattern(interpreter, arm.lhs(), &value, &mut bindings) {
+            continue;
+        }
+
+        interpreter.scopes.push(bindings.into_iter().collect());
+        let result = evaluate_expr(interpreter, arm.rhs());
+        interpreter.scopes.pop();
+
+        return result;
+    }
+
+    Err(error(node, format!("no match arm matched `{}`", value)))
+}
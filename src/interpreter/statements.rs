@@ -0,0 +1,90 @@
+use crate::{
+    ast::{
+        expressions::ExpressionKind,
+        statements::Statement,
+        view::ExprRef,
+    },
+    diagnostics::Diagnostic,
+};
+
+use super::{expressions::evaluate_expr, values::Value, Interpreter, Result};
+
+pub fn execute_stmt(interpreter: &mut Interpreter, stmt: &Statement) -> Result<Option<Value>> {
+    use Statement::*;
+
+    let parts = stmt.expressions();
+
+    match stmt {
+        Expression { .. } => return evaluate_expr(interpreter, parts[0]).map(Some),
+        Variable { .. } => {
+            // [E value][E type]?[E symbol]
+            let name = parts[parts.len() - 1].symbol();
+            let value = evaluate_expr(interpreter, parts[0])?;
+
+            interpreter.declare(name, value);
+        }
+        Assignment { .. } => execute_assignment_stmt(interpreter, parts[0], parts[1])?,
+        Struct { .. } => execute_struct_stmt(interpreter, parts[0]),
+        Enum { .. } => _ = interpreter.enums.insert(parts[0].symbol()),
+
+        // aliases only exist for the type checker
+        Alias { .. } => (),
+        Error => {
+            let message = "cannot run a statement with a syntax error".to_string();
+            return Err(Diagnostic::error(0, message));
+        }
+    };
+
+    Ok(None)
+}
+
+fn execute_assignment_stmt(
+    interpreter: &mut Interpreter,
+    value: ExprRef,
+    target: ExprRef,
+) -> Result<()> {
+    let value = evaluate_expr(interpreter, value)?;
+
+    // `a.b.c` -> root `a`, path [b, c]
+    let mut path = vec![];
+    let mut root = target;
+    while *root.kind() == ExpressionKind::Member {
+        path.push(root.rhs().symbol());
+        root = root.lhs();
+    }
+
+    let node = root.node();
+    let name = node.symbol();
+    let mut slot = match interpreter.lookup_mut(name) {
+        Some(slot) => slot,
+        None => {
+            let message = format!("unknown binding `{}`", name);
+            return Err(Diagnostic::error(node.index, message));
+        }
+    };
+
+    for field in path.into_iter().rev() {
+        slot = match slot {
+            Value::Struct { fields, .. } => match fields.iter_mut().find(|(name, _)| *name == field) {
+                Some((_, value)) => value,
+                None => {
+                    let message = format!("unknown field `{}`", field);
+                    return Err(Diagnostic::error(node.index, message));
+                }
+            },
+            _ => {
+                let message = format!("no field `{}` on `{}`", field, slot);
+                return Err(Diagnostic::error(node.index, message));
+            }
+        };
+    }
+
+    *slot = value;
+
+    Ok(())
+}
+
+fn execute_struct_stmt(interpreter: &mut Interpreter, expr: ExprRef) {
+    let fields = expr.children().into_iter().map(ExprRef::symbol).collect();
+    interpreter.structs.insert(expr.symbol(), fields);
+}
@@ -0,0 +1,55 @@
+use core::fmt;
+
+use crate::utils::interner::Symbol;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    None,
+    Int(i64),
+    Float(f64),
+    Struct {
+        name: Symbol,
+        fields: Vec<(Symbol, Value)>,
+    },
+    Enum {
+        name: Symbol,
+        variant: Symbol,
+        payload: Vec<Value>,
+    },
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Int(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::None => false,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::None => write!(f, "none"),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::Struct { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            }
+            Value::Enum { name, variant, payload } if payload.is_empty() => {
+                write!(f, "{}.{}", name, variant)
+            }
+            Value::Enum { name, variant, payload } => {
+                let payload: Vec<String> = payload.iter().map(|value| value.to_string()).collect();
+                write!(f, "{}.{}({})", name, variant, payload.join(", "))
+            }
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use core::fmt;
+
+use crate::utils::interner::Symbol;
+
+use verifier::reverse_postorder;
+
+pub mod dot;
+pub mod lower;
+pub mod parser;
+pub mod passes;
+pub mod printer;
+pub mod verifier;
+
+// --- overview --- //
+// a program is lowered into functions of basic blocks, every block is a list of
+// instructions in SSA form and ends with a single terminator
+//
+// an instruction defines at most one value, values are numbered per function and
+// only defined once, the joins of ternaries, `??`, `?.` and `match` are `phi`s
+//
+// top level statements are lowered into the body of `main`, expression statements
+// `print` their value
+
+/// a value defined by the instruction with the same index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Value(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ty {
+    // the type of instructions without a value, e.g. `print`
+    Unit,
+    Int,
+    Float,
+    Struct(Symbol),
+    Enum(Symbol),
+
+    // `none` or a value of the inner type
+    Optional(Box<Ty>),
+}
+
+impl Ty {
+    pub fn optional(ty: Ty) -> Ty {
+        match ty {
+            Ty::Optional(_) => ty,
+            ty => Ty::Optional(Box::new(ty)),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Float)
+    }
+}
+
+/// a literal value, floats are compared by their bits
+#[derive(Debug, Clone, Copy)]
+pub enum Constant {
+    Int(i64),
+    Float(f64),
+
+    // `none` of the instruction type
+    None,
+}
+
+impl PartialEq for Constant {
+    fn eq(&self, other: &Constant) -> bool {
+        match (self, other) {
+            (Constant::Int(lhs), Constant::Int(rhs)) => lhs == rhs,
+            (Constant::Float(lhs), Constant::Float(rhs)) => lhs.to_bits() == rhs.to_bits(),
+            (Constant::None, Constant::None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Constant {}
+
+impl core::hash::Hash for Constant {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Constant::Int(value) => (0, *value as u64).hash(state),
+            Constant::Float(value) => (1, value.to_bits()).hash(state),
+            Constant::None => (2, 0u64).hash(state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+
+    // integer division and remainder trap on a zero divisor
+    Div,
+    Rem,
+
+    // compare two numbers, the result is `1` or `0`
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InstKind {
+    Const(Constant),
+
+    // the same value under a binding name
+    Copy(Value),
+    Neg(Value),
+
+    // `index` is the token reported when it traps
+    Binary {
+        op: BinaryOp,
+        lhs: Value,
+        rhs: Value,
+        index: u32,
+    },
+
+    // the fields in declaration order
+    Struct(Vec<Value>),
+    Field {
+        value: Value,
+        field: u32,
+    },
+
+    // a copy of the struct with one field changed
+    SetField {
+        value: Value,
+        field: u32,
+        to: Value,
+    },
+    Variant {
+        variant: u32,
+        payload: Vec<Value>,
+    },
+
+    // the variant index of an enum value
+    Tag(Value),
+    Payload {
+        value: Value,
+        variant: u32,
+        index: u32,
+    },
+
+    // `1` if the optional is `none`
+    IsNone(Value),
+    Wrap(Value),
+    Unwrap(Value),
+    Phi(Vec<(BlockId, Value)>),
+
+    // writes the value and a line break to the output
+    Print(Value),
+}
+
+impl InstKind {
+    /// the values the instruction reads, a phi reads them at its predecessors
+    pub fn operands(&self) -> Vec<Value> {
+        let mut operands = vec![];
+        self.clone().replace(|value| {
+            operands.push(value);
+            value
+        });
+
+        operands
+    }
+
+    /// replaces every operand with `replace(operand)`
+    pub fn replace(&mut self, mut replace: impl FnMut(Value) -> Value) {
+        match self {
+            InstKind::Const(_) => (),
+            InstKind::Copy(value)
+            | InstKind::Neg(value)
+            | InstKind::Field { value, .. }
+            | InstKind::Tag(value)
+            | InstKind::Payload { value, .. }
+            | InstKind::IsNone(value)
+            | InstKind::Wrap(value)
+            | InstKind::Unwrap(value)
+            | InstKind::Print(value) => *value = replace(*value),
+            InstKind::Binary { lhs, rhs, .. } => {
+                *lhs = replace(*lhs);
+                *rhs = replace(*rhs);
+            }
+            InstKind::SetField { value, to, .. } => {
+                *value = replace(*value);
+                *to = replace(*to);
+            }
+            InstKind::Struct(values)
+            | InstKind::Variant {
+                payload: values, ..
+            } => values.iter_mut().for_each(|value| *value = replace(*value)),
+            InstKind::Phi(entries) => entries
+                .iter_mut()
+                .for_each(|(_, value)| *value = replace(*value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub kind: InstKind,
+    pub ty: Ty,
+
+    // the binding the value was declared as, only for printing
+    pub name: Option<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+
+    // `then` when the integer condition is not `0`
+    Branch {
+        cond: Value,
+        then: BlockId,
+        otherwise: BlockId,
+    },
+    Return,
+
+    // a `match` without a matching arm, the checker rules it out
+    Unreachable,
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch {
+                then, otherwise, ..
+            } => vec![*then, *otherwise],
+            Terminator::Return | Terminator::Unreachable => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub instructions: Vec<Value>,
+    pub terminator: Terminator,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: Symbol,
+
+    // indexed by `Value`, an instruction only runs when a block lists it
+    pub instructions: Vec<Instruction>,
+
+    // indexed by `BlockId`, the first one is the entry
+    pub blocks: Vec<Block>,
+}
+
+impl Function {
+    pub fn instruction(&self, value: Value) -> &Instruction {
+        &self.instructions[value.0 as usize]
+    }
+
+    pub fn block(&self, id: BlockId) -> &Block {
+        &self.blocks[id.0 as usize]
+    }
+
+    /// the predecessors of every block, in block order
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut predecessors = vec![vec![]; self.blocks.len()];
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            for successor in block.terminator.successors() {
+                predecessors[successor.0 as usize].push(BlockId(i as u32));
+            }
+        }
+
+        predecessors
+    }
+
+    /// the phis of `to` and their values along the edge from `from`
+    pub fn phi_moves(&self, from: BlockId, to: BlockId) -> Vec<(Value, Value)> {
+        let mut moves = vec![];
+
+        for value in self.block(to).instructions.iter() {
+            if let InstKind::Phi(entries) = &self.instruction(*value).kind {
+                for (block, source) in entries.iter() {
+                    if *block == from {
+                        moves.push((*value, *source));
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// puts the blocks in reverse postorder, so a block is printed after the ones
+    /// that jump to it, and drops the ones that are never reached,
+    /// e.g. after the last arm of a `match`
+    pub fn layout(&mut self) {
+        let order = reverse_postorder(self);
+
+        let mut numbers = vec![None; self.blocks.len()];
+        for (number, block) in order.iter().enumerate() {
+            numbers[block.0 as usize] = Some(BlockId(number as u32));
+        }
+
+        let renumber = |block: BlockId| numbers[block.0 as usize].unwrap();
+        let mut blocks = vec![];
+
+        for block in order.iter() {
+            let mut block = self.blocks[block.0 as usize].clone();
+
+            block.terminator = match block.terminator {
+                Terminator::Jump(target) => Terminator::Jump(renumber(target)),
+                Terminator::Branch {
+                    cond,
+                    then,
+                    otherwise,
+                } => Terminator::Branch {
+                    cond,
+                    then: renumber(then),
+                    otherwise: renumber(otherwise),
+                },
+                terminator => terminator,
+            };
+
+            for value in block.instructions.iter() {
+                if let InstKind::Phi(entries) = &mut self.instructions[value.0 as usize].kind {
+                    entries.retain(|(block, _)| numbers[block.0 as usize].is_some());
+                    entries
+                        .iter_mut()
+                        .for_each(|(block, _)| *block = renumber(*block));
+                }
+            }
+
+            blocks.push(block);
+        }
+
+        self.blocks = blocks;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDecl {
+    pub name: Symbol,
+    pub fields: Vec<(Symbol, Ty)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDecl {
+    pub name: Symbol,
+    pub variants: Vec<(Symbol, Vec<Ty>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub structs: Vec<StructDecl>,
+    pub enums: Vec<EnumDecl>,
+    pub functions: Vec<Function>,
+}
+
+impl Program {
+    pub fn struct_decl(&self, name: Symbol) -> &StructDecl {
+        self.structs.iter().find(|decl| decl.name == name).unwrap()
+    }
+
+    pub fn enum_decl(&self, name: Symbol) -> &EnumDecl {
+        self.enums.iter().find(|decl| decl.name == name).unwrap()
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::Unit => write!(f, "()"),
+            Ty::Int => write!(f, "i64"),
+            Ty::Float => write!(f, "f64"),
+            Ty::Struct(name) | Ty::Enum(name) => write!(f, "{}", name),
+            Ty::Optional(ty) => write!(f, "{}?", ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parser::parse, BlockId, Value};
+
+    // the phis of bb1 swap %2 and %3 on the back edge and rotate %4, %5 and %6,
+    // the moves of an edge happen at once, like a parallel copy
+    const LOOP: &str = "
+fn main {
+bb0:
+    %0: i64 = const 1
+    %1: i64 = const 2
+    jump bb1
+bb1:
+    %2: i64 = phi [bb0: %0], [bb1: %3]
+    %3: i64 = phi [bb0: %1], [bb1: %2]
+    %4: i64 = phi [bb0: %0], [bb1: %5]
+    %5: i64 = phi [bb0: %0], [bb1: %6]
+    %6: i64 = phi [bb0: %1], [bb1: %4]
+    branch %2, bb1, bb2
+bb2:
+    print %3
+    return
+}
+";
+
+    #[test]
+    fn phi_moves() {
+        let program = parse(LOOP).unwrap();
+        let function = &program.functions[0];
+
+        let moves = |from: u32, to: u32| -> Vec<(u32, u32)> {
+            function
+                .phi_moves(BlockId(from), BlockId(to))
+                .into_iter()
+                .map(|(phi, source): (Value, Value)| (phi.0, source.0))
+                .collect()
+        };
+
+        assert_eq!(moves(0, 1), [(2, 0), (3, 1), (4, 0), (5, 0), (6, 1)]);
+
+        // the swap and the cycle, in the order of the phis
+        assert_eq!(moves(1, 1), [(2, 3), (3, 2), (4, 5), (5, 6), (6, 4)]);
+
+        // no phis on the edge
+        assert_eq!(moves(1, 2), []);
+    }
+}
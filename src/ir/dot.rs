@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use super::{printer::instruction, Program, Terminator};
+
+// --- format --- //
+// block: `<function>_bb<n>`, a box with the label, the instructions and the terminator
+//        of the block, one per left aligned line
+//
+// edges go from a block to its successors, the ones of a `branch` are labeled
+// `then` and `else`, every function is a cluster
+
+/// the control flow graphs of a program as a graphviz graph, see `dot -Tsvg`
+pub fn to_dot(program: &Program) -> String {
+    let mut output = String::new();
+
+    output.push_str("digraph cfg {\n");
+    output.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    for function in program.functions.iter() {
+        let name = function.name;
+
+        _ = writeln!(output, "  subgraph cluster_{} {{", name);
+        _ = writeln!(output, "    label=\"fn {}\";", name);
+
+        for (i, block) in function.blocks.iter().enumerate() {
+            let mut label = format!("bb{}:\\l", i);
+
+            for value in block.instructions.iter() {
+                label.push_str(&escape(&instruction(function, *value)));
+                label.push_str("\\l");
+            }
+
+            label.push_str(&escape(&block.terminator.to_string()));
+            label.push_str("\\l");
+
+            _ = writeln!(output, "    {}_bb{} [label=\"{}\"];", name, i, label);
+        }
+
+        for (i, block) in function.blocks.iter().enumerate() {
+            let edges = match block.terminator {
+                Terminator::Branch {
+                    then, otherwise, ..
+                } => {
+                    vec![
+                        (then, " [label=\"then\"]"),
+                        (otherwise, " [label=\"else\"]"),
+                    ]
+                }
+                ref terminator => terminator
+                    .successors()
+                    .into_iter()
+                    .map(|target| (target, ""))
+                    .collect(),
+            };
+
+            for (target, label) in edges {
+                _ = writeln!(
+                    output,
+                    "    {}_bb{} -> {}_{}{};",
+                    name, i, name, target, label
+                );
+            }
+        }
+
+        output.push_str("  }\n");
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// a dot string without the quotes
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,837 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{expressions::ExpressionKind, statements::Statement, view::ExprRef},
+    diagnostics::Diagnostic,
+    lexer::tokens::{Token, TokenValue},
+    semantic::{types::Type, Analysis},
+    utils::interner::Symbol,
+};
+
+use super::{
+    BinaryOp, Block, BlockId, Constant, EnumDecl, Function, InstKind, Instruction, Program,
+    StructDecl, Terminator, Ty, Value,
+};
+
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+struct Lowerer<'a> {
+    // the types the checker found, by token index
+    types: &'a HashMap<u32, Type>,
+
+    structs: Vec<StructDecl>,
+    enums: Vec<EnumDecl>,
+    function: Function,
+
+    // the block new instructions are appended to
+    current: BlockId,
+
+    // bindings -> their current value, assignments rebind them
+    scopes: Vec<HashMap<Symbol, Value>>,
+}
+
+/// lowers a checked program into `main`, `analysis` is the result of the checker
+pub fn lower(stmts: &[Statement], analysis: &Analysis) -> Result<Program> {
+    let mut lowerer = Lowerer {
+        types: &analysis.types,
+        structs: vec![],
+        enums: vec![],
+        function: Function {
+            name: Symbol::intern("main"),
+            instructions: vec![],
+            blocks: vec![],
+        },
+        current: BlockId(0),
+        scopes: vec![HashMap::new()],
+    };
+
+    lowerer.current = lowerer.new_block();
+
+    for stmt in stmts {
+        lowerer.lower_stmt(stmt)?;
+    }
+
+    lowerer.terminate(Terminator::Return);
+    lowerer.function.layout();
+
+    Ok(Program {
+        structs: lowerer.structs,
+        enums: lowerer.enums,
+        functions: vec![lowerer.function],
+    })
+}
+
+/// `Unknown` only remains inside the `none` literal, `T?` of nothing
+fn convert(ty: &Type) -> Ty {
+    match ty.resolved() {
+        Type::Int => Ty::Int,
+        Type::Float => Ty::Float,
+        Type::Struct(name) => Ty::Struct(*name),
+        Type::Enum(name) => Ty::Enum(*name),
+        Type::Optional(ty) => Ty::optional(convert(ty)),
+        Type::Alias(..) | Type::Unknown => Ty::Unit,
+    }
+}
+
+fn error(index: u32, message: String) -> Diagnostic {
+    Diagnostic::error(index, message)
+}
+
+impl Lowerer<'_> {
+    // --- building --- //
+
+    fn emit(&mut self, kind: InstKind, ty: Ty) -> Value {
+        self.emit_named(kind, ty, None)
+    }
+
+    fn emit_named(&mut self, kind: InstKind, ty: Ty, name: Option<Symbol>) -> Value {
+        let value = Value(self.function.instructions.len() as u32);
+
+        self.function
+            .instructions
+            .push(Instruction { kind, ty, name });
+        self.function.blocks[self.current.0 as usize]
+            .instructions
+            .push(value);
+
+        value
+    }
+
+    fn ty(&self, value: Value) -> Ty {
+        self.function.instruction(value).ty.clone()
+    }
+
+    fn new_block(&mut self) -> BlockId {
+        self.function.blocks.push(Block {
+            instructions: vec![],
+            terminator: Terminator::Unreachable,
+        });
+
+        BlockId(self.function.blocks.len() as u32 - 1)
+    }
+
+    fn terminate(&mut self, terminator: Terminator) {
+        self.function.blocks[self.current.0 as usize].terminator = terminator;
+    }
+
+    /// ends the current block with a branch, continues in `then`
+    fn branch(&mut self, cond: Value, then: BlockId, otherwise: BlockId) {
+        self.terminate(Terminator::Branch {
+            cond,
+            then,
+            otherwise,
+        });
+        self.current = then;
+    }
+
+    /// the type the checker recorded for the token at `index`
+    fn type_at(&self, index: u32) -> Result<Ty> {
+        match self.types.get(&index) {
+            Some(ty) => Ok(convert(ty)),
+            None => Err(error(
+                index,
+                "the expression was not type checked".to_string(),
+            )),
+        }
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name).copied())
+    }
+
+    /// turns `value` into `to` where the checker accepts it, `T` into `T?`
+    fn coerce(&mut self, value: Value, to: &Ty, index: u32) -> Result<Value> {
+        let from = self.ty(value);
+
+        match (&from, to) {
+            _ if from == *to => Ok(value),
+            (Ty::Optional(inner), Ty::Optional(_)) if **inner == Ty::Unit => {
+                Ok(self.emit(InstKind::Const(Constant::None), to.clone()))
+            }
+            (_, Ty::Optional(inner)) if from == **inner => {
+                Ok(self.emit(InstKind::Wrap(value), to.clone()))
+            }
+            _ => Err(error(index, format!("cannot lower `{}` as `{}`", from, to))),
+        }
+    }
+
+    /// `phi` of the values the predecessors jump to the current block with
+    fn join(&mut self, entries: Vec<(BlockId, Value)>, ty: Ty, index: u32) -> Result<Value> {
+        match entries.len() {
+            0 => Err(error(
+                index,
+                "no branch reaches the end of the expression".to_string(),
+            )),
+            _ => Ok(self.emit(InstKind::Phi(entries), ty)),
+        }
+    }
+
+    // --- statements --- //
+
+    fn lower_stmt(&mut self, stmt: &Statement) -> Result<()> {
+        use Statement::*;
+
+        let parts = stmt.expressions();
+
+        match stmt {
+            Expression { .. } => {
+                let value = self.lower_expr(parts[0])?;
+                self.emit(InstKind::Print(value), Ty::Unit);
+            }
+            Variable { .. } => {
+                // [E value][E type]?[E symbol]
+                let symbol = parts[parts.len() - 1].node();
+                let ty = self.type_at(symbol.index)?;
+
+                let value = self.lower_expr(parts[0])?;
+                let value = self.coerce(value, &ty, parts[0].index())?;
+
+                let name = symbol.symbol();
+                let value = self.emit_named(InstKind::Copy(value), ty, Some(name));
+                self.scopes.last_mut().unwrap().insert(name, value);
+            }
+            Assignment { .. } => self.lower_assignment(parts[0], parts[1])?,
+            Struct { .. } => self.lower_struct_decl(parts[0])?,
+            Enum { .. } => self.lower_enum_decl(parts[0])?,
+
+            // aliases are resolved by the checker
+            Alias { .. } => (),
+            Error => {
+                return Err(error(
+                    0,
+                    "cannot lower a statement with a syntax error".to_string(),
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lower_struct_decl(&mut self, expr: ExprRef) -> Result<()> {
+        let mut fields = vec![];
+
+        for field in expr.children() {
+            let node = field.node();
+            fields.push((node.symbol(), self.type_at(node.index)?));
+        }
+
+        self.structs.push(StructDecl {
+            name: expr.symbol(),
+            fields,
+        });
+        Ok(())
+    }
+
+    fn lower_enum_decl(&mut self, expr: ExprRef) -> Result<()> {
+        let mut variants = vec![];
+
+        for variant in expr.children() {
+            let mut payload = vec![];
+
+            for ty in variant.children() {
+                payload.push(self.type_at(ty.index())?);
+            }
+
+            variants.push((variant.symbol(), payload));
+        }
+
+        self.enums.push(EnumDecl {
+            name: expr.symbol(),
+            variants,
+        });
+        Ok(())
+    }
+
+    /// `a.b.c = value` is `a = a with b = (a.b with c = value)`
+    fn lower_assignment(&mut self, value: ExprRef, target: ExprRef) -> Result<()> {
+        let mut path = vec![];
+        let mut root = target;
+
+        while *root.kind() == ExpressionKind::Member {
+            if *root.token() != Token::Dot {
+                return Err(error(
+                    root.index(),
+                    "cannot assign through `?.`".to_string(),
+                ));
+            }
+
+            path.push(root.rhs().symbol());
+            root = root.lhs();
+        }
+
+        path.reverse();
+
+        let name = root.symbol();
+        let Some(current) = self.lookup(name) else {
+            return Err(error(root.index(), format!("unknown binding `{}`", name)));
+        };
+
+        // the structs along the path, outermost first
+        let mut parents = vec![];
+        let mut parent = current;
+
+        for field_name in path.iter() {
+            let Ty::Struct(struct_name) = self.ty(parent) else {
+                return Err(error(
+                    target.index(),
+                    "invalid assignment target".to_string(),
+                ));
+            };
+
+            let (field, ty) = self.field(struct_name, *field_name, target.index())?;
+            parents.push((parent, field));
+            parent = self.emit(
+                InstKind::Field {
+                    value: parent,
+                    field,
+                },
+                ty,
+            );
+        }
+
+        let ty = self.ty(parent);
+        let lowered = self.lower_expr(value)?;
+        let mut lowered = self.coerce(lowered, &ty, value.index())?;
+
+        for (parent, field) in parents.into_iter().rev() {
+            let ty = self.ty(parent);
+            let kind = InstKind::SetField {
+                value: parent,
+                field,
+                to: lowered,
+            };
+            lowered = self.emit(kind, ty);
+        }
+
+        let ty = self.ty(current);
+        let lowered = self.emit_named(InstKind::Copy(lowered), ty, Some(name));
+
+        let scope = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find(|scope| scope.contains_key(&name));
+        scope.unwrap().insert(name, lowered);
+
+        Ok(())
+    }
+
+    /// the index and type of a field
+    fn field(&self, name: Symbol, field_name: Symbol, index: u32) -> Result<(u32, Ty)> {
+        let decl = self.structs.iter().find(|decl| decl.name == name);
+        let found = decl.and_then(|decl| {
+            decl.fields
+                .iter()
+                .position(|(name, _)| *name == field_name)
+                .map(|position| (position as u32, decl.fields[position].1.clone()))
+        });
+
+        found.ok_or_else(|| {
+            error(
+                index,
+                format!("unknown field `{}` on `{}`", field_name, name),
+            )
+        })
+    }
+
+    /// the index and payload types of a variant
+    fn variant(&self, name: Symbol, variant_name: Symbol, index: u32) -> Result<(u32, Vec<Ty>)> {
+        let decl = self.enums.iter().find(|decl| decl.name == name);
+        let found = decl.and_then(|decl| {
+            decl.variants
+                .iter()
+                .position(|(name, _)| *name == variant_name)
+                .map(|position| (position as u32, decl.variants[position].1.clone()))
+        });
+
+        found.ok_or_else(|| {
+            error(
+                index,
+                format!("unknown variant `{}` on `{}`", variant_name, name),
+            )
+        })
+    }
+
+    // --- expressions --- //
+
+    fn lower_expr(&mut self, expr: ExprRef) -> Result<Value> {
+        use ExpressionKind::*;
+
+        let node = expr.node();
+
+        match node.kind {
+            Literal => self.lower_literal(expr),
+            Prefix => {
+                let operand = self.lower_expr(expr.operand())?;
+
+                match node.token {
+                    Token::Minus => Ok(self.emit(InstKind::Neg(operand), self.ty(operand))),
+                    _ => Ok(operand),
+                }
+            }
+            Block => self.lower_expr(expr.operand()),
+            Binary => self.lower_binary(expr),
+            Ternary => self.lower_ternary(expr),
+            Member => self.lower_member(expr),
+            Struct => self.lower_struct(expr),
+            Call => self.lower_call(expr),
+            Match => self.lower_match(expr),
+            Sufix | Field | Enum | Variant | Arm => Err(error(
+                node.index,
+                format!("unexpected {:?} expression", node.kind),
+            )),
+        }
+    }
+
+    fn lower_literal(&mut self, expr: ExprRef) -> Result<Value> {
+        let node = expr.node();
+
+        match (&node.token, node.value) {
+            (Token::Number, TokenValue::Int(value)) => {
+                Ok(self.emit(InstKind::Const(Constant::Int(value)), Ty::Int))
+            }
+            (Token::Number, TokenValue::Float(value)) => {
+                Ok(self.emit(InstKind::Const(Constant::Float(value)), Ty::Float))
+            }
+            (Token::Null, _) => {
+                let ty = Ty::optional(Ty::Unit);
+                Ok(self.emit(InstKind::Const(Constant::None), ty))
+            }
+            (Token::Identifier, TokenValue::Symbol(name)) => match self.lookup(name) {
+                Some(value) => Ok(value),
+                None => Err(error(node.index, format!("unknown binding `{}`", name))),
+            },
+            (token, _) => Err(error(
+                node.index,
+                format!("unexpected literal `{:?}`", token),
+            )),
+        }
+    }
+
+    /// a chain like `a + b - c`, the tree is as deep as the chain is long, so it is
+    /// walked with a loop like in the checker
+    fn lower_binary(&mut self, expr: ExprRef) -> Result<Value> {
+        let (first, chain) = expr.chain();
+        let mut lhs = self.lower_expr(first)?;
+
+        for expr in chain {
+            lhs = self.lower_binary_operator(expr, lhs)?;
+        }
+
+        Ok(lhs)
+    }
+
+    /// the operator of a chain whose left side is already lowered to `lhs`
+    fn lower_binary_operator(&mut self, expr: ExprRef, lhs: Value) -> Result<Value> {
+        let node = expr.node();
+
+        let op = match node.token {
+            Token::Plus => BinaryOp::Add,
+            Token::Minus => BinaryOp::Sub,
+            Token::Star => BinaryOp::Mul,
+            Token::Slash => BinaryOp::Div,
+            Token::Percent => BinaryOp::Rem,
+            Token::QuestionQuestion => return self.lower_coalesce(expr, lhs),
+            ref token => return Err(error(node.index, format!("unexpected {}", token))),
+        };
+
+        let rhs = self.lower_expr(expr.rhs())?;
+        let kind = InstKind::Binary {
+            op,
+            lhs,
+            rhs,
+            index: node.index,
+        };
+
+        Ok(self.emit(kind, self.ty(lhs)))
+    }
+
+    /// `Enum.Variant` paths, like the interpreter a binding shadows an enum
+    fn resolve_variant(&self, expr: ExprRef) -> Option<(Symbol, Symbol)> {
+        if *expr.kind() != ExpressionKind::Member || *expr.token() != Token::Dot {
+            return None;
+        }
+
+        let lhs = expr.lhs().node();
+        if lhs.kind != ExpressionKind::Literal || lhs.token != Token::Identifier {
+            return None;
+        }
+
+        let name = lhs.symbol();
+        if self.lookup(name).is_some() || !self.enums.iter().any(|decl| decl.name == name) {
+            return None;
+        }
+
+        Some((name, expr.rhs().symbol()))
+    }
+
+    /// `lhs ?? rhs`, the right side only runs when the left one is `none`
+    fn lower_coalesce(&mut self, expr: ExprRef, lhs: Value) -> Result<Value> {
+        let index = expr.index();
+        let ty = self.type_at(index)?;
+
+        let inner = match self.ty(lhs) {
+            Ty::Optional(inner) => *inner,
+
+            // never `none`, the checker warns about it
+            _ => return self.coerce(lhs, &ty, index),
+        };
+
+        // always `none`
+        if inner == Ty::Unit {
+            let rhs = self.lower_expr(expr.rhs())?;
+            return self.coerce(rhs, &ty, index);
+        }
+
+        let is_none = self.emit(InstKind::IsNone(lhs), Ty::Int);
+        let some = self.new_block();
+        let none = self.new_block();
+        let end = self.new_block();
+
+        // `T? ?? T` unwraps, `T? ?? T?` keeps the optional
+        self.branch(is_none, none, some);
+        let rhs = self.lower_expr(expr.rhs())?;
+        let rhs = self.coerce(rhs, &ty, index)?;
+        let none_end = self.current;
+        self.terminate(Terminator::Jump(end));
+
+        self.current = some;
+        let value = match ty {
+            Ty::Optional(_) => lhs,
+            _ => self.emit(InstKind::Unwrap(lhs), inner),
+        };
+        let value = self.coerce(value, &ty, index)?;
+        let some_end = self.current;
+        self.terminate(Terminator::Jump(end));
+
+        self.current = end;
+        self.join(vec![(none_end, rhs), (some_end, value)], ty, index)
+    }
+
+    /// a number that is not `0` as the `1` or `0` a branch needs
+    fn condition(&mut self, value: Value, index: u32) -> Result<Value> {
+        let zero = match self.ty(value) {
+            Ty::Int => return Ok(value),
+            Ty::Float => Constant::Float(0.0),
+            ty => return Err(error(index, format!("cannot use `{}` as a condition", ty))),
+        };
+
+        let zero = self.emit(InstKind::Const(zero), Ty::Float);
+        let kind = InstKind::Binary {
+            op: BinaryOp::Ne,
+            lhs: value,
+            rhs: zero,
+            index,
+        };
+
+        Ok(self.emit(kind, Ty::Int))
+    }
+
+    fn lower_ternary(&mut self, expr: ExprRef) -> Result<Value> {
+        let index = expr.index();
+        let ty = self.type_at(index)?;
+
+        let cond = self.lower_expr(expr.cond())?;
+        let cond = self.condition(cond, index)?;
+
+        let then = self.new_block();
+        let otherwise = self.new_block();
+        let end = self.new_block();
+
+        self.branch(cond, then, otherwise);
+        let mhs = self.lower_expr(expr.mhs())?;
+        let mhs = self.coerce(mhs, &ty, index)?;
+        let then_end = self.current;
+        self.terminate(Terminator::Jump(end));
+
+        self.current = otherwise;
+        let rhs = self.lower_expr(expr.rhs())?;
+        let rhs = self.coerce(rhs, &ty, index)?;
+        let otherwise_end = self.current;
+        self.terminate(Terminator::Jump(end));
+
+        self.current = end;
+        self.join(vec![(then_end, mhs), (otherwise_end, rhs)], ty, index)
+    }
+
+    fn lower_member(&mut self, expr: ExprRef) -> Result<Value> {
+        let index = expr.index();
+
+        if let Some((name, variant_name)) = self.resolve_variant(expr) {
+            let (variant, _) = self.variant(name, variant_name, index)?;
+            let kind = InstKind::Variant {
+                variant,
+                payload: vec![],
+            };
+            return Ok(self.emit(kind, Ty::Enum(name)));
+        }
+
+        let field_name = expr.rhs().symbol();
+        let lhs = self.lower_expr(expr.lhs())?;
+
+        let Ty::Optional(inner) = self.ty(lhs) else {
+            // `.` and a `?.` on a value that is never `none`
+            let value = self.lower_field(lhs, field_name, index)?;
+            let ty = self.type_at(index)?;
+            return self.coerce(value, &ty, index);
+        };
+
+        // `?.` is `none` for `none`
+        let ty = self.type_at(index)?;
+        let is_none = self.emit(InstKind::IsNone(lhs), Ty::Int);
+
+        let none = self.new_block();
+        let some = self.new_block();
+        let end = self.new_block();
+
+        self.branch(is_none, none, some);
+        let empty = self.emit(InstKind::Const(Constant::None), ty.clone());
+        self.terminate(Terminator::Jump(end));
+
+        self.current = some;
+        let value = self.emit(InstKind::Unwrap(lhs), *inner);
+        let value = self.lower_field(value, field_name, index)?;
+        let value = self.coerce(value, &ty, index)?;
+        let some_end = self.current;
+        self.terminate(Terminator::Jump(end));
+
+        self.current = end;
+        self.join(vec![(none, empty), (some_end, value)], ty, index)
+    }
+
+    fn lower_field(&mut self, value: Value, field_name: Symbol, index: u32) -> Result<Value> {
+        let Ty::Struct(name) = self.ty(value) else {
+            return Err(error(
+                index,
+                format!("no field `{}` on `{}`", field_name, self.ty(value)),
+            ));
+        };
+
+        let (field, ty) = self.field(name, field_name, index)?;
+        Ok(self.emit(InstKind::Field { value, field }, ty))
+    }
+
+    fn lower_struct(&mut self, expr: ExprRef) -> Result<Value> {
+        let name = expr.symbol();
+
+        // evaluated in source order, stored in declaration order
+        let mut values = vec![];
+        for field in expr.children() {
+            let (position, ty) = self.field(name, field.symbol(), field.index())?;
+            let value = self.lower_expr(field.operand())?;
+            let value = self.coerce(value, &ty, field.index())?;
+
+            values.push((position, value));
+        }
+
+        values.sort_by_key(|(position, _)| *position);
+
+        let fields = values.into_iter().map(|(_, value)| value).collect();
+        Ok(self.emit(InstKind::Struct(fields), Ty::Struct(name)))
+    }
+
+    fn lower_call(&mut self, expr: ExprRef) -> Result<Value> {
+        let index = expr.index();
+        let operands = expr.children();
+        let (callee, arguments) = operands.split_first().unwrap();
+
+        let Some((name, variant_name)) = self.resolve_variant(*callee) else {
+            return Err(error(index, "expression is not callable".to_string()));
+        };
+
+        let (variant, types) = self.variant(name, variant_name, index)?;
+
+        let mut payload = vec![];
+        for (argument, ty) in arguments.iter().zip(types.iter()) {
+            let value = self.lower_expr(*argument)?;
+            payload.push(self.coerce(value, ty, argument.index())?);
+        }
+
+        Ok(self.emit(InstKind::Variant { variant, payload }, Ty::Enum(name)))
+    }
+
+    /// tests the arms one after another, the first matching one runs
+    fn lower_match(&mut self, expr: ExprRef) -> Result<Value> {
+        let index = expr.index();
+        let ty = self.type_at(index)?;
+
+        let operands = expr.children();
+        let (subject, arms) = operands.split_first().unwrap();
+        let subject = self.lower_expr(*subject)?;
+
+        let end = self.new_block();
+        let mut entries = vec![];
+
+        for arm in arms {
+            let next = self.new_block();
+            let mut bindings = vec![];
+
+            if self.lower_pattern(arm.lhs(), subject, next, &mut bindings)? {
+                self.scopes.push(HashMap::new());
+
+                for (name, value) in bindings {
+                    let ty = self.ty(value);
+                    let value = self.emit_named(InstKind::Copy(value), ty, Some(name));
+                    self.scopes.last_mut().unwrap().insert(name, value);
+                }
+
+                let value = self.lower_expr(arm.rhs());
+                self.scopes.pop();
+
+                let value = self.coerce(value?, &ty, arm.index())?;
+                entries.push((self.current, value));
+                self.terminate(Terminator::Jump(end));
+            }
+
+            self.current = next;
+        }
+
+        // the checker only accepts exhaustive matches
+        self.terminate(Terminator::Unreachable);
+
+        self.current = end;
+        self.join(entries, ty, index)
+    }
+
+    /// tests `value` against a pattern and jumps to `fail` if it does not match,
+    /// returns `false` if it never matches
+    fn lower_pattern(
+        &mut self,
+        pattern: ExprRef,
+        value: Value,
+        fail: BlockId,
+        bindings: &mut Vec<(Symbol, Value)>,
+    ) -> Result<bool> {
+        use ExpressionKind::*;
+
+        let node = pattern.node();
+
+        match (&node.kind, &node.token) {
+            (Literal, Token::Identifier) => {
+                let name = node.symbol();
+
+                if name.as_str() != "_" {
+                    // `T?` is narrowed to `T` after a `none` arm
+                    let ty = self.type_at(node.index)?;
+                    let value = match (self.ty(value), &ty) {
+                        (Ty::Optional(inner), _) if *inner == ty => {
+                            self.emit(InstKind::Unwrap(value), ty)
+                        }
+                        _ => value,
+                    };
+
+                    bindings.push((name, value));
+                }
+
+                Ok(true)
+            }
+            (Literal, Token::Null) => match self.ty(value) {
+                Ty::Optional(_) => {
+                    let is_none = self.emit(InstKind::IsNone(value), Ty::Int);
+                    let next = self.new_block();
+                    self.branch(is_none, next, fail);
+                    Ok(true)
+                }
+                _ => {
+                    self.terminate(Terminator::Jump(fail));
+                    Ok(false)
+                }
+            },
+            (Literal, Token::Number) => {
+                let value = self.unwrap_or_fail(value, fail);
+
+                let literal = match node.value {
+                    TokenValue::Int(literal) => Constant::Int(literal),
+                    TokenValue::Float(literal) => Constant::Float(literal),
+                    _ => {
+                        return Err(error(
+                            node.index,
+                            "integer literal is too large".to_string(),
+                        ))
+                    }
+                };
+
+                let ty = self.ty(value);
+                let literal = self.emit(InstKind::Const(literal), ty);
+                let kind = InstKind::Binary {
+                    op: BinaryOp::Eq,
+                    lhs: value,
+                    rhs: literal,
+                    index: node.index,
+                };
+                let is_equal = self.emit(kind, Ty::Int);
+
+                let next = self.new_block();
+                self.branch(is_equal, next, fail);
+                Ok(true)
+            }
+            (Member, _) | (Call, _) => {
+                let (path, arguments) = match node.kind {
+                    Call => {
+                        let operands = pattern.children();
+                        let (callee, arguments) = operands.split_first().unwrap();
+                        (*callee, arguments.to_vec())
+                    }
+                    _ => (pattern, vec![]),
+                };
+
+                let Some((name, variant_name)) = self.resolve_variant(path) else {
+                    return Err(error(node.index, "invalid pattern".to_string()));
+                };
+
+                let (variant, types) = self.variant(name, variant_name, node.index)?;
+                let value = self.unwrap_or_fail(value, fail);
+
+                let tag = self.emit(InstKind::Tag(value), Ty::Int);
+                let expected = self.emit(InstKind::Const(Constant::Int(variant as i64)), Ty::Int);
+                let kind = InstKind::Binary {
+                    op: BinaryOp::Eq,
+                    lhs: tag,
+                    rhs: expected,
+                    index: node.index,
+                };
+                let is_variant = self.emit(kind, Ty::Int);
+
+                let next = self.new_block();
+                self.branch(is_variant, next, fail);
+
+                for (i, (argument, ty)) in arguments.iter().zip(types).enumerate() {
+                    let kind = InstKind::Payload {
+                        value,
+                        variant,
+                        index: i as u32,
+                    };
+                    let payload = self.emit(kind, ty);
+
+                    if !self.lower_pattern(*argument, payload, fail, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            _ => Err(error(node.index, "invalid pattern".to_string())),
+        }
+    }
+
+    /// the value inside of an optional, `none` jumps to `fail`
+    fn unwrap_or_fail(&mut self, value: Value, fail: BlockId) -> Value {
+        let Ty::Optional(inner) = self.ty(value) else {
+            return value;
+        };
+
+        let is_none = self.emit(InstKind::IsNone(value), Ty::Int);
+        let next = self.new_block();
+        self.terminate(Terminator::Branch {
+            cond: is_none,
+            then: fail,
+            otherwise: next,
+        });
+        self.current = next;
+
+        self.emit(InstKind::Unwrap(value), *inner)
+    }
+}
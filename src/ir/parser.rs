@@ -0,0 +1,570 @@
+use std::collections::HashMap;
+
+use crate::utils::interner::Symbol;
+
+use super::{
+    BinaryOp, Block, BlockId, Constant, EnumDecl, Function, InstKind, Instruction, Program,
+    StructDecl, Terminator, Ty, Value,
+};
+
+// reads the format of `printer.rs` back, e.g. to run the passes on hand-written ir
+//
+// the values and blocks keep their numbers, a value may be left out, a block not,
+// `print` gets a number after the others, the verifier checks the rest
+
+type Result<T> = std::result::Result<T, String>;
+
+/// a program in the text format, an error names the line
+pub fn parse(text: &str) -> Result<Program> {
+    let mut parser = Parser {
+        types: HashMap::new(),
+        program: Program {
+            structs: vec![],
+            enums: vec![],
+            functions: vec![],
+        },
+    };
+
+    let lines: Vec<Line> = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| Line::new(i + 1, line))
+        .filter(|line| !line.tokens.is_empty())
+        .collect();
+
+    // a field can have the type of an enum declared after it
+    for line in lines.iter() {
+        match line.tokens[..] {
+            ["struct", name, ..] => parser.declare(name, Ty::Struct(Symbol::intern(name))),
+            ["enum", name, ..] => parser.declare(name, Ty::Enum(Symbol::intern(name))),
+            _ => (),
+        }
+    }
+
+    let mut lines = lines.into_iter();
+
+    while let Some(mut line) = lines.next() {
+        let result = match line.peek() {
+            "struct" => parser.parse_struct(&mut line),
+            "enum" => parser.parse_enum(&mut line),
+
+            // the lines of the body report their own errors
+            "fn" => {
+                parser.parse_function(&mut line, &mut lines)?;
+                continue;
+            }
+            token => Err(format!("unexpected `{}`", token)),
+        };
+
+        result.map_err(|message| format!("line {}: {}", line.number, message))?;
+    }
+
+    Ok(parser.program)
+}
+
+struct Parser {
+    // the declared structs and enums
+    types: HashMap<Symbol, Ty>,
+    program: Program,
+}
+
+/// the tokens of a line without its comment
+struct Line<'a> {
+    number: usize,
+    tokens: Vec<&'a str>,
+    position: usize,
+
+    // the text after `//`, the name of a binding
+    comment: Option<&'a str>,
+}
+
+impl<'a> Line<'a> {
+    fn new(number: usize, text: &'a str) -> Line<'a> {
+        let (text, comment) = match text.split_once("//") {
+            Some((text, comment)) => (text, Some(comment.trim())),
+            None => (text, None),
+        };
+
+        let mut tokens = vec![];
+        let mut start = None;
+
+        for (i, char) in text.char_indices() {
+            let is_punctuation = "{}()[],:=?".contains(char);
+
+            if char.is_whitespace() || is_punctuation {
+                if let Some(start) = start.take() {
+                    tokens.push(&text[start..i]);
+                }
+
+                if is_punctuation {
+                    tokens.push(&text[i..i + 1]);
+                }
+            } else if start.is_none() {
+                start = Some(i);
+            }
+        }
+
+        if let Some(start) = start {
+            tokens.push(&text[start..]);
+        }
+
+        Line {
+            number,
+            tokens,
+            position: 0,
+            comment: comment.filter(|comment| !comment.is_empty()),
+        }
+    }
+
+    fn peek(&self) -> &'a str {
+        self.tokens.get(self.position).copied().unwrap_or("")
+    }
+
+    fn next(&mut self) -> Result<&'a str> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+
+        token.ok_or_else(|| "unexpected end of the line".to_string())
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected `{}`, found `{}`", expected, token)),
+        }
+    }
+
+    fn eat(&mut self, expected: &str) -> bool {
+        let is_next = self.peek() == expected;
+        if is_next {
+            self.position += 1;
+        }
+
+        is_next
+    }
+
+    fn end(&self) -> Result<()> {
+        match self.tokens.get(self.position) {
+            Some(token) => Err(format!("unexpected `{}`", token)),
+            None => Ok(()),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, prefix: &str) -> Result<T> {
+        let token = self.next()?;
+
+        token
+            .strip_prefix(prefix)
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| format!("expected `{}<number>`, found `{}`", prefix, token))
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        self.number("%").map(Value)
+    }
+
+    fn block(&mut self) -> Result<BlockId> {
+        self.number("bb").map(BlockId)
+    }
+
+    /// `a, b, c` until the end of the line or `close`
+    fn list<T>(
+        &mut self,
+        close: &str,
+        mut item: impl FnMut(&mut Line<'a>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut items = vec![];
+
+        while self.peek() != close {
+            items.push(item(self)?);
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+}
+
+impl Parser {
+    fn declare(&mut self, name: &str, ty: Ty) {
+        self.types.insert(Symbol::intern(name), ty);
+    }
+
+    /// `i64`, `f64`, `()`, a declared name and `T?`
+    fn parse_type(&self, line: &mut Line) -> Result<Ty> {
+        let ty = match line.next()? {
+            "i64" => Ty::Int,
+            "f64" => Ty::Float,
+            "(" => {
+                line.expect(")")?;
+                Ty::Unit
+            }
+            name => match self.types.get(&Symbol::intern(name)) {
+                Some(ty) => ty.clone(),
+                None => return Err(format!("unknown type `{}`", name)),
+            },
+        };
+
+        match line.eat("?") {
+            true => Ok(Ty::optional(ty)),
+            false => Ok(ty),
+        }
+    }
+
+    /// `struct P { x: i64, y: f64 }`
+    fn parse_struct(&mut self, line: &mut Line) -> Result<()> {
+        line.expect("struct")?;
+        let name = Symbol::intern(line.next()?);
+        line.expect("{")?;
+
+        let fields = line.list("}", |line| {
+            let name = Symbol::intern(line.next()?);
+            line.expect(":")?;
+            Ok((name, self.parse_type(line)?))
+        })?;
+
+        line.expect("}")?;
+        line.end()?;
+
+        self.program.structs.push(StructDecl { name, fields });
+        Ok(())
+    }
+
+    /// `enum Shape { Circle(f64), Empty }`
+    fn parse_enum(&mut self, line: &mut Line) -> Result<()> {
+        line.expect("enum")?;
+        let name = Symbol::intern(line.next()?);
+        line.expect("{")?;
+
+        let variants = line.list("}", |line| {
+            let name = Symbol::intern(line.next()?);
+
+            let mut payload = vec![];
+            if line.eat("(") {
+                payload = line.list(")", |line| self.parse_type(line))?;
+                line.expect(")")?;
+            }
+
+            Ok((name, payload))
+        })?;
+
+        line.expect("}")?;
+        line.end()?;
+
+        self.program.enums.push(EnumDecl { name, variants });
+        Ok(())
+    }
+
+    /// `fn main {`, the blocks and `}`, an error names the line
+    fn parse_function<'a>(
+        &mut self,
+        line: &mut Line<'a>,
+        lines: &mut impl Iterator<Item = Line<'a>>,
+    ) -> Result<()> {
+        let header = line.number;
+        let name = parse_header(line).map_err(|message| format!("line {}: {}", header, message))?;
+
+        let mut body = Body {
+            blocks: vec![],
+            current: None,
+            is_closed: false,
+        };
+
+        for mut line in lines.by_ref() {
+            self.parse_body_line(&mut line, &mut body)
+                .map_err(|message| format!("line {}: {}", line.number, message))?;
+
+            if body.is_closed {
+                break;
+            }
+        }
+
+        let function = body
+            .finish(name)
+            .map_err(|message| format!("line {}: fn {}: {}", header, name, message))?;
+
+        self.program.functions.push(function);
+        Ok(())
+    }
+
+    fn parse_body_line(&self, line: &mut Line, body: &mut Body) -> Result<()> {
+        match line.peek() {
+            "}" => {
+                if let Some((block, _)) = body.current {
+                    return Err(format!("{} has no terminator", block));
+                }
+
+                line.next()?;
+                body.is_closed = true;
+            }
+            token if token.starts_with("bb") => {
+                if let Some((block, _)) = body.current {
+                    return Err(format!("{} has no terminator", block));
+                }
+
+                let block = line.block()?;
+                line.expect(":")?;
+                body.current = Some((block, vec![]));
+            }
+            "jump" | "branch" | "return" | "unreachable" => {
+                let Some((block, parsed)) = body.current.take() else {
+                    return Err("a terminator outside of a block".to_string());
+                };
+
+                let terminator = parse_terminator(line)?;
+                let index = block.0 as usize;
+
+                if body.blocks.len() <= index {
+                    body.blocks.resize(index + 1, None);
+                }
+
+                if body.blocks[index].is_some() {
+                    return Err(format!("{} is defined twice", block));
+                }
+
+                body.blocks[index] = Some((parsed, terminator));
+            }
+            _ => match body.current.as_mut() {
+                Some((_, parsed)) => parsed.push(self.parse_instruction(line)?),
+                None => return Err("an instruction outside of a block".to_string()),
+            },
+        }
+
+        line.end()
+    }
+
+    /// `%n: T = op operands` or `print %n`, the comment is the name
+    fn parse_instruction(&self, line: &mut Line) -> Result<(Option<Value>, Instruction)> {
+        let name = line.comment.map(Symbol::intern);
+
+        // `print` is the only instruction without a value
+        if line.eat("print") {
+            let kind = InstKind::Print(line.value()?);
+            line.end()?;
+
+            return Ok((
+                None,
+                Instruction {
+                    kind,
+                    ty: Ty::Unit,
+                    name,
+                },
+            ));
+        }
+
+        let value = line.value()?;
+        line.expect(":")?;
+        let ty = self.parse_type(line)?;
+        line.expect("=")?;
+
+        let kind = match line.next()? {
+            "const" => match (line.next()?, &ty) {
+                ("none", _) => InstKind::Const(Constant::None),
+                (number, Ty::Float) => match number.parse() {
+                    Ok(number) => InstKind::Const(Constant::Float(number)),
+                    Err(_) => return Err(format!("invalid float `{}`", number)),
+                },
+                (number, _) => match number.parse() {
+                    Ok(number) => InstKind::Const(Constant::Int(number)),
+                    Err(_) => return Err(format!("invalid integer `{}`", number)),
+                },
+            },
+            "copy" => InstKind::Copy(line.value()?),
+            "neg" => InstKind::Neg(line.value()?),
+            "struct" => InstKind::Struct(line.list("", Line::value)?),
+            "field" => {
+                let value = line.value()?;
+                line.expect(",")?;
+                let field = line.number("")?;
+                InstKind::Field { value, field }
+            }
+            "setfield" => {
+                let value = line.value()?;
+                line.expect(",")?;
+                let field = line.number("")?;
+                line.expect(",")?;
+                let to = line.value()?;
+                InstKind::SetField { value, field, to }
+            }
+            "variant" => {
+                let variant = line.number("")?;
+                let payload = match line.eat(",") {
+                    true => line.list("", Line::value)?,
+                    false => vec![],
+                };
+                InstKind::Variant { variant, payload }
+            }
+            "tag" => InstKind::Tag(line.value()?),
+            "payload" => {
+                let value = line.value()?;
+                line.expect(",")?;
+                let variant = line.number("")?;
+                line.expect(",")?;
+                let index = line.number("")?;
+                InstKind::Payload {
+                    value,
+                    variant,
+                    index,
+                }
+            }
+            "isnone" => InstKind::IsNone(line.value()?),
+            "wrap" => InstKind::Wrap(line.value()?),
+            "unwrap" => InstKind::Unwrap(line.value()?),
+            "phi" => InstKind::Phi(line.list("", |line| {
+                line.expect("[")?;
+                let block = line.block()?;
+                line.expect(":")?;
+                let value = line.value()?;
+                line.expect("]")?;
+                Ok((block, value))
+            })?),
+            "print" => InstKind::Print(line.value()?),
+            op => {
+                let op = match op {
+                    "add" => BinaryOp::Add,
+                    "sub" => BinaryOp::Sub,
+                    "mul" => BinaryOp::Mul,
+                    "div" => BinaryOp::Div,
+                    "rem" => BinaryOp::Rem,
+                    "eq" => BinaryOp::Eq,
+                    "ne" => BinaryOp::Ne,
+                    op => return Err(format!("unknown instruction `{}`", op)),
+                };
+
+                let lhs = line.value()?;
+                line.expect(",")?;
+                let rhs = line.value()?;
+
+                // `@12`, the token a trap reports
+                let index = match line.peek().starts_with('@') {
+                    true => line.number("@")?,
+                    false => 0,
+                };
+
+                InstKind::Binary {
+                    op,
+                    lhs,
+                    rhs,
+                    index,
+                }
+            }
+        };
+
+        line.end()?;
+        Ok((Some(value), Instruction { kind, ty, name }))
+    }
+}
+
+fn parse_terminator(line: &mut Line) -> Result<Terminator> {
+    let terminator = match line.next()? {
+        "jump" => Terminator::Jump(line.block()?),
+        "branch" => {
+            let cond = line.value()?;
+            line.expect(",")?;
+            let then = line.block()?;
+            line.expect(",")?;
+            let otherwise = line.block()?;
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            }
+        }
+        "return" => Terminator::Return,
+        _ => Terminator::Unreachable,
+    };
+
+    line.end()?;
+    Ok(terminator)
+}
+
+/// `fn main {`
+fn parse_header(line: &mut Line) -> Result<Symbol> {
+    line.expect("fn")?;
+    let name = Symbol::intern(line.next()?);
+    line.expect("{")?;
+    line.end()?;
+
+    Ok(name)
+}
+
+/// the instructions of a block, `print` has no number yet
+type Parsed = Vec<(Option<Value>, Instruction)>;
+
+/// the blocks of a function while it is parsed
+struct Body {
+    blocks: Vec<Option<(Parsed, Terminator)>>,
+
+    // the block the lines are added to, `None` after its terminator
+    current: Option<(BlockId, Parsed)>,
+    is_closed: bool,
+}
+
+impl Body {
+    fn finish(self, name: Symbol) -> Result<Function> {
+        if !self.is_closed {
+            return Err("missing `}`".to_string());
+        }
+
+        let blocks = self
+            .blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, block)| block.ok_or_else(|| format!("bb{} is missing", i)))
+            .collect::<Result<Vec<(Parsed, Terminator)>>>()?;
+
+        let count = blocks
+            .iter()
+            .flat_map(|(parsed, _)| parsed.iter())
+            .filter_map(|(value, _)| value.map(|value| value.0 as usize + 1))
+            .max()
+            .unwrap_or(0);
+
+        // the numbers that are left out are never listed in a block
+        let placeholder = Instruction {
+            kind: InstKind::Const(Constant::Int(0)),
+            ty: Ty::Int,
+            name: None,
+        };
+
+        let mut function = Function {
+            name,
+            instructions: vec![placeholder; count],
+            blocks: vec![],
+        };
+
+        let mut is_defined = vec![false; count];
+
+        for (parsed, terminator) in blocks {
+            let mut values = vec![];
+
+            for (value, instruction) in parsed {
+                let value = match value {
+                    Some(value) if is_defined[value.0 as usize] => {
+                        return Err(format!("{} is defined twice", value))
+                    }
+                    Some(value) => {
+                        is_defined[value.0 as usize] = true;
+                        function.instructions[value.0 as usize] = instruction;
+                        value
+                    }
+                    None => {
+                        function.instructions.push(instruction);
+                        Value(function.instructions.len() as u32 - 1)
+                    }
+                };
+
+                values.push(value);
+            }
+
+            function.blocks.push(Block {
+                instructions: values,
+                terminator,
+            });
+        }
+
+        Ok(function)
+    }
+}
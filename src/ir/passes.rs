@@ -0,0 +1,113 @@
+use super::{verifier::verify, Program};
+
+#[cfg(test)]
+use super::parser::parse;
+
+pub mod copies;
+pub mod dce;
+pub mod gvn;
+
+/// a rewrite of every function, the output of the program stays the same
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pass {
+    // uses the value of a `copy` and of a `phi` with a single value directly
+    CopyPropagation,
+
+    // global value numbering, reuses the value of an instruction that already
+    // computed the same thing in a dominating block
+    Gvn,
+
+    // removes instructions nothing depends on and blocks that are never reached
+    Dce,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 3] = [Pass::CopyPropagation, Pass::Gvn, Pass::Dce];
+
+    /// the name on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Pass::CopyPropagation => "copy-prop",
+            Pass::Gvn => "gvn",
+            Pass::Dce => "dce",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Pass> {
+        Pass::ALL.into_iter().find(|pass| pass.name() == name)
+    }
+
+    pub fn run(self, program: &mut Program) {
+        for function in program.functions.iter_mut() {
+            match self {
+                Pass::CopyPropagation => copies::run(function),
+                Pass::Gvn => gvn::run(function),
+                Pass::Dce => dce::run(function),
+            }
+        }
+    }
+}
+
+/// runs passes in order, a pass may run more than once
+#[derive(Debug, Clone, PartialEq)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+}
+
+impl PassManager {
+    pub fn new(passes: Vec<Pass>) -> PassManager {
+        PassManager { passes }
+    }
+
+    /// `copy-prop,gvn,dce`, an empty list runs nothing
+    pub fn parse(list: &str) -> Result<PassManager, String> {
+        let passes = list
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(|name| Pass::parse(name).ok_or_else(|| format!("unknown pass `{}`", name)))
+            .collect::<Result<Vec<Pass>, String>>()?;
+
+        Ok(PassManager { passes })
+    }
+
+    /// removes every run of `pass`
+    pub fn disable(&mut self, pass: Pass) {
+        self.passes.retain(|other| *other != pass);
+    }
+
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    /// verifies the program after every pass, an error names the pass that broke it
+    pub fn run(&self, program: &mut Program) -> Result<(), String> {
+        for pass in self.passes.iter() {
+            pass.run(program);
+
+            let errors = verify(program);
+            if !errors.is_empty() {
+                return Err(format!("after {}: {}", pass.name(), errors.join(", ")));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PassManager {
+    /// copies first, so equal values get the same number, dead code last
+    fn default() -> PassManager {
+        PassManager::new(Pass::ALL.to_vec())
+    }
+}
+
+/// the printed program after running `pass` on the program in `text`, for the tests
+/// of the passes
+#[cfg(test)]
+fn after(pass: Pass, text: &str) -> String {
+    let mut program = parse(text).unwrap();
+    pass.run(&mut program);
+
+    assert_eq!(verify(&program), Vec::<String>::new());
+    program.to_string()
+}
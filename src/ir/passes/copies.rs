@@ -0,0 +1,164 @@
+use crate::ir::{Function, InstKind, Terminator, Value};
+
+/// replaces the uses of `copy %n` with `%n`, and of a `phi` that only joins
+/// one value with that value, the unused copies are left to `dce`
+pub fn run(function: &mut Function) {
+    let mut replacements: Vec<Option<Value>> = vec![None; function.instructions.len()];
+
+    // a phi can become trivial once the copies it joins are replaced
+    let mut is_changed = true;
+    while is_changed {
+        is_changed = false;
+
+        for block in function.blocks.iter() {
+            for value in block.instructions.iter() {
+                if replacements[value.0 as usize].is_some() {
+                    continue;
+                }
+
+                let replacement = match &function.instruction(*value).kind {
+                    InstKind::Copy(source) => Some(*source),
+                    InstKind::Phi(entries) => {
+                        let mut sources = entries
+                            .iter()
+                            .map(|(_, source)| resolve(&replacements, *source))
+                            .filter(|source| source != value);
+
+                        // `phi [bb1: %2], [bb2: %3]` where `%3` is the phi itself
+                        let first = sources.next();
+                        match sources.all(|source| Some(source) == first) {
+                            true => first,
+                            false => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+                if let Some(replacement) = replacement {
+                    replacements[value.0 as usize] = Some(replacement);
+                    is_changed = true;
+                }
+            }
+        }
+    }
+
+    for block in function.blocks.iter_mut() {
+        for value in block.instructions.iter() {
+            let instruction = &mut function.instructions[value.0 as usize];
+
+            // the copies keep reading their source, `dce` drops them
+            if replacements[value.0 as usize].is_none() {
+                instruction
+                    .kind
+                    .replace(|operand| resolve(&replacements, operand));
+            }
+        }
+
+        if let Terminator::Branch { cond, .. } = &mut block.terminator {
+            *cond = resolve(&replacements, *cond);
+        }
+    }
+}
+
+/// the value at the end of a chain of copies
+fn resolve(replacements: &[Option<Value>], mut value: Value) -> Value {
+    while let Some(replacement) = replacements[value.0 as usize] {
+        value = replacement;
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::passes::{after, Pass};
+
+    #[test]
+    fn chains_of_copies() {
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 4
+    %1: i64 = copy %0 // x
+    %2: i64 = copy %1 // y
+    %3: i64 = add %2, %1
+    print %3
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::CopyPropagation, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 4
+    %1: i64 = copy %0 // x
+    %2: i64 = copy %1 // y
+    %3: i64 = add %0, %0
+    print %3
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn phis_of_a_single_value() {
+        // %4 joins copies of %0, %5 joins %4 with itself around the loop,
+        // %6 joins two different values and stays, the replaced phis are
+        // left for `dce` like the copies
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 4
+    %1: i64 = copy %0
+    %2: i64 = const 1
+    branch %2, bb1, bb2
+bb1:
+    jump bb3
+bb2:
+    jump bb3
+bb3:
+    %4: i64 = phi [bb1: %1], [bb2: %0]
+    %6: i64 = phi [bb1: %0], [bb2: %2]
+    jump bb4
+bb4:
+    %5: i64 = phi [bb3: %4], [bb4: %5]
+    print %5
+    print %6
+    branch %2, bb4, bb5
+bb5:
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::CopyPropagation, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 4
+    %1: i64 = copy %0
+    %2: i64 = const 1
+    branch %2, bb1, bb2
+bb1:
+    jump bb3
+bb2:
+    jump bb3
+bb3:
+    %4: i64 = phi [bb1: %1], [bb2: %0]
+    %6: i64 = phi [bb1: %0], [bb2: %2]
+    jump bb4
+bb4:
+    %5: i64 = phi [bb3: %4], [bb4: %5]
+    print %0
+    print %6
+    branch %2, bb4, bb5
+bb5:
+    return
+}
+"
+        );
+    }
+}
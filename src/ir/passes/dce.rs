@@ -0,0 +1,177 @@
+use crate::ir::{BinaryOp, Constant, Function, InstKind, Terminator, Ty, Value};
+
+/// folds branches on a constant, drops the blocks that are no longer reached and
+/// every instruction nothing observable depends on
+///
+/// `print` and a `div` or `rem` that may trap stay, like the conditions of branches
+pub fn run(function: &mut Function) {
+    for i in 0..function.blocks.len() {
+        let Terminator::Branch {
+            cond,
+            then,
+            otherwise,
+        } = function.blocks[i].terminator
+        else {
+            continue;
+        };
+
+        let target = match function.instruction(cond).kind {
+            InstKind::Const(Constant::Int(0)) => otherwise,
+            InstKind::Const(Constant::Int(_)) => then,
+            _ if then == otherwise => then,
+            _ => continue,
+        };
+
+        function.blocks[i].terminator = Terminator::Jump(target);
+    }
+
+    // the phis lose the entries of the blocks that are no longer predecessors
+    let predecessors = function.predecessors();
+    for (i, block) in function.blocks.iter().enumerate() {
+        for value in block.instructions.iter() {
+            if let InstKind::Phi(entries) = &mut function.instructions[value.0 as usize].kind {
+                entries.retain(|(block, _)| predecessors[i].contains(block));
+            }
+        }
+    }
+
+    function.layout();
+
+    let mut is_live = vec![false; function.instructions.len()];
+    let mut worklist = vec![];
+
+    for block in function.blocks.iter() {
+        for value in block.instructions.iter() {
+            if has_effect(function, *value) {
+                worklist.push(*value);
+            }
+        }
+
+        if let Terminator::Branch { cond, .. } = block.terminator {
+            worklist.push(cond);
+        }
+    }
+
+    while let Some(value) = worklist.pop() {
+        if is_live[value.0 as usize] {
+            continue;
+        }
+
+        is_live[value.0 as usize] = true;
+        worklist.extend(function.instruction(value).kind.operands());
+    }
+
+    for block in function.blocks.iter_mut() {
+        block.instructions.retain(|value| is_live[value.0 as usize]);
+    }
+}
+
+/// the instructions that must run even if their value is unused
+fn has_effect(function: &Function, value: Value) -> bool {
+    match function.instruction(value).kind {
+        InstKind::Print(_) => true,
+        InstKind::Binary {
+            op: BinaryOp::Div | BinaryOp::Rem,
+            rhs,
+            ..
+        } => {
+            let divisor = &function.instruction(rhs).kind;
+            let is_safe = match divisor {
+                InstKind::Const(Constant::Int(divisor)) => *divisor != 0,
+                _ => false,
+            };
+
+            function.instruction(value).ty == Ty::Int && !is_safe
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::passes::{after, Pass};
+
+    #[test]
+    fn divisions_that_may_trap_stay() {
+        // %2 divides by a constant that is not zero, %5 by a value, %7 is a float
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 6
+    %1: i64 = const 2
+    %2: i64 = div %0, %1 @0 // unused
+    %3: f64 = const 1.5
+    %4: i64 = sub %1, %1
+    %5: i64 = rem %0, %4 @0
+    %6: i64 = const 0
+    %7: f64 = div %3, %3 @0
+    %8: i64 = div %0, %6 @0
+    print %1
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::Dce, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 6
+    %1: i64 = const 2
+    %4: i64 = sub %1, %1
+    %5: i64 = rem %0, %4 @0
+    %6: i64 = const 0
+    %8: i64 = div %0, %6 @0
+    print %1
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn branches_on_constants() {
+        // bb2 is no longer reached, the phi loses its entry and %1 its last use
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 6
+    %1: i64 = const 2
+    %2: i64 = const 1
+    branch %2, bb1, bb2
+bb1:
+    print %0
+    jump bb3
+bb2:
+    print %1
+    jump bb3
+bb3:
+    %3: i64 = phi [bb1: %0], [bb2: %1]
+    print %3
+    branch %0, bb4, bb4
+bb4:
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::Dce, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 6
+    jump bb1
+bb1:
+    print %0
+    jump bb2
+bb2:
+    %3: i64 = phi [bb1: %0]
+    print %3
+    jump bb3
+bb3:
+    return
+}
+"
+        );
+    }
+}
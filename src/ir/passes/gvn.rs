@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use crate::ir::{
+    verifier::dominators, BinaryOp, BlockId, Function, InstKind, Terminator, Ty, Value,
+};
+
+/// numbers the values in dominator tree order, an instruction that computes the
+/// same as one in a dominating block is replaced by it and removed
+///
+/// `div` and `rem` are numbered as well, the dominating one traps first
+pub fn run(function: &mut Function) {
+    let predecessors = function.predecessors();
+    let idoms = dominators(function, &predecessors);
+
+    // the dominator tree
+    let mut children = vec![vec![]; function.blocks.len()];
+    for (i, idom) in idoms.iter().enumerate().skip(1) {
+        if let Some(idom) = idom {
+            children[idom.0 as usize].push(BlockId(i as u32));
+        }
+    }
+
+    let mut numbering = Numbering {
+        function,
+        replacements: vec![None; function.instructions.len()],
+        table: HashMap::new(),
+    };
+
+    numbering.visit(&children);
+
+    let Numbering { replacements, .. } = numbering;
+    let resolve = |value: Value| replacements[value.0 as usize].unwrap_or(value);
+
+    for block in function.blocks.iter_mut() {
+        block
+            .instructions
+            .retain(|value| replacements[value.0 as usize].is_none());
+
+        for value in block.instructions.iter() {
+            function.instructions[value.0 as usize]
+                .kind
+                .replace(resolve);
+        }
+
+        if let Terminator::Branch { cond, .. } = &mut block.terminator {
+            *cond = resolve(*cond);
+        }
+    }
+}
+
+/// what an instruction computes, the token index does not matter
+type Key = (InstKind, Ty);
+
+struct Numbering<'a> {
+    function: &'a Function,
+    replacements: Vec<Option<Value>>,
+
+    // the keys of the instructions in the dominating blocks
+    table: HashMap<Key, Value>,
+}
+
+impl Numbering<'_> {
+    /// walks the dominator tree from the entry, a long chain of joins is a deep tree
+    fn visit(&mut self, children: &[Vec<BlockId>]) {
+        // a block, or the keys to forget when its subtree is done
+        let mut stack = vec![Ok(BlockId(0))];
+
+        while let Some(next) = stack.pop() {
+            let block = match next {
+                Ok(block) => block,
+                Err(inserted) => {
+                    // siblings do not dominate each other
+                    for key in inserted {
+                        self.table.remove(&key);
+                    }
+                    continue;
+                }
+            };
+
+            let mut inserted: Vec<Key> = vec![];
+
+            for value in self.function.block(block).instructions.iter() {
+                let Some(key) = self.key(*value) else {
+                    continue;
+                };
+
+                match self.table.get(&key) {
+                    Some(existing) => self.replacements[value.0 as usize] = Some(*existing),
+                    None => {
+                        self.table.insert(key.clone(), *value);
+                        inserted.push(key);
+                    }
+                }
+            }
+
+            stack.push(Err(inserted));
+            stack.extend(
+                children[block.0 as usize]
+                    .iter()
+                    .rev()
+                    .map(|child| Ok(*child)),
+            );
+        }
+    }
+
+    /// `None` for the instructions that are never replaced
+    fn key(&self, value: Value) -> Option<Key> {
+        let instruction = self.function.instruction(value);
+        let mut kind = instruction.kind.clone();
+
+        // operands that were replaced already
+        kind.replace(|operand| self.replacements[operand.0 as usize].unwrap_or(operand));
+
+        match &mut kind {
+            InstKind::Copy(_) | InstKind::Phi(_) | InstKind::Print(_) => return None,
+            InstKind::Binary {
+                op,
+                lhs,
+                rhs,
+                index,
+            } => {
+                *index = 0;
+
+                let is_commutative = matches!(
+                    op,
+                    BinaryOp::Add | BinaryOp::Mul | BinaryOp::Eq | BinaryOp::Ne
+                );
+
+                if is_commutative && lhs > rhs {
+                    std::mem::swap(lhs, rhs);
+                }
+            }
+            _ => (),
+        }
+
+        Some((kind, instruction.ty.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::passes::{after, Pass};
+
+    #[test]
+    fn same_values_in_a_block() {
+        // %3 swaps the operands of %2, %5 repeats a constant, `sub` does not commute
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 3
+    %1: i64 = const 4
+    %2: i64 = mul %0, %1
+    %3: i64 = mul %1, %0
+    %4: i64 = add %2, %3
+    %5: i64 = const 3
+    %6: i64 = sub %0, %1
+    %7: i64 = sub %1, %5
+    %8: i64 = div %4, %7 @0
+    %9: i64 = div %4, %7 @1
+    print %6
+    print %8
+    print %9
+    print %9
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::Gvn, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 3
+    %1: i64 = const 4
+    %2: i64 = mul %0, %1
+    %4: i64 = add %2, %2
+    %6: i64 = sub %0, %1
+    %7: i64 = sub %1, %0
+    %8: i64 = div %4, %7 @0
+    print %6
+    print %8
+    print %8
+    print %8
+    return
+}
+"
+        );
+    }
+
+    #[test]
+    fn only_dominating_values_are_reused() {
+        // bb1 reuses %2 from the entry, bb3 cannot reuse %4 from bb1
+        let text = "
+fn main {
+bb0:
+    %0: i64 = const 3
+    %1: i64 = const 4
+    %2: i64 = mul %0, %1
+    branch %2, bb1, bb2
+bb1:
+    %3: i64 = mul %0, %1
+    %4: i64 = sub %3, %1
+    print %4
+    jump bb3
+bb2:
+    jump bb3
+bb3:
+    %5: i64 = sub %2, %1
+    print %5
+    return
+}
+";
+
+        assert_eq!(
+            after(Pass::Gvn, text),
+            "\
+fn main {
+bb0:
+    %0: i64 = const 3
+    %1: i64 = const 4
+    %2: i64 = mul %0, %1
+    branch %2, bb1, bb2
+bb1:
+    %4: i64 = sub %2, %1
+    print %4
+    jump bb3
+bb2:
+    jump bb3
+bb3:
+    %5: i64 = sub %2, %1
+    print %5
+    return
+}
+"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use core::fmt;
+
+use super::{BinaryOp, Constant, Function, InstKind, Program, Terminator, Ty, Value};
+
+// --- format --- //
+// struct P { x: i64, y: f64 }
+// enum Shape { Circle(f64), Rect(f64, f64), Empty }
+//
+// fn main {
+// bb0:
+//     %0: i64 = const 1
+//     %1: i64 = copy %0 // x
+//     %2: i64 = div %1, %0 @12
+//     print %2
+//     branch %2, bb1, bb2
+// ...
+// }
+//
+// `@12` is the token index a trapping instruction reports, `// x` the binding
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for decl in self.structs.iter() {
+            let fields: Vec<String> = decl
+                .fields
+                .iter()
+                .map(|(name, ty)| format!("{}: {}", name, ty))
+                .collect();
+
+            writeln!(f, "struct {} {{ {} }}", decl.name, fields.join(", "))?;
+        }
+
+        for decl in self.enums.iter() {
+            let variants: Vec<String> = decl
+                .variants
+                .iter()
+                .map(|(name, payload)| match payload.is_empty() {
+                    true => name.to_string(),
+                    false => format!("{}({})", name, join(payload)),
+                })
+                .collect();
+
+            writeln!(f, "enum {} {{ {} }}", decl.name, variants.join(", "))?;
+        }
+
+        for function in self.functions.iter() {
+            if !self.structs.is_empty() || !self.enums.is_empty() {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", function)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "fn {} {{", self.name)?;
+
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "bb{}:", i)?;
+
+            for value in block.instructions.iter() {
+                writeln!(f, "    {}", instruction(self, *value))?;
+            }
+
+            writeln!(f, "    {}", block.terminator)?;
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+/// a single line like `%2: i64 = add %0, %1`
+pub fn instruction(function: &Function, value: Value) -> String {
+    let instruction = function.instruction(value);
+
+    let text = match &instruction.kind {
+        InstKind::Const(constant) => format!("const {}", constant),
+        InstKind::Copy(value) => format!("copy {}", value),
+        InstKind::Neg(value) => format!("neg {}", value),
+        InstKind::Binary {
+            op,
+            lhs,
+            rhs,
+            index,
+        } => match op {
+            BinaryOp::Div | BinaryOp::Rem => format!("{} {}, {} @{}", op, lhs, rhs, index),
+            _ => format!("{} {}, {}", op, lhs, rhs),
+        },
+        InstKind::Struct(fields) => format!("struct {}", join(fields)).trim_end().to_string(),
+        InstKind::Field { value, field } => format!("field {}, {}", value, field),
+        InstKind::SetField { value, field, to } => format!("setfield {}, {}, {}", value, field, to),
+        InstKind::Variant { variant, payload } => match payload.is_empty() {
+            true => format!("variant {}", variant),
+            false => format!("variant {}, {}", variant, join(payload)),
+        },
+        InstKind::Tag(value) => format!("tag {}", value),
+        InstKind::Payload {
+            value,
+            variant,
+            index,
+        } => {
+            format!("payload {}, {}, {}", value, variant, index)
+        }
+        InstKind::IsNone(value) => format!("isnone {}", value),
+        InstKind::Wrap(value) => format!("wrap {}", value),
+        InstKind::Unwrap(value) => format!("unwrap {}", value),
+        InstKind::Phi(entries) => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(block, value)| format!("[{}: {}]", block, value))
+                .collect();
+
+            format!("phi {}", entries.join(", "))
+        }
+        InstKind::Print(value) => format!("print {}", value),
+    };
+
+    let text = match instruction.ty {
+        Ty::Unit => text,
+        ref ty => format!("{}: {} = {}", value, ty, text),
+    };
+
+    match instruction.name {
+        Some(name) => format!("{} // {}", text, name),
+        None => text,
+    }
+}
+
+fn join(items: &[impl fmt::Display]) -> String {
+    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    items.join(", ")
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Int(value) => write!(f, "{}", value),
+            Constant::Float(value) => write!(f, "{:?}", value),
+            Constant::None => write!(f, "none"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BinaryOp::Add => "add",
+            BinaryOp::Sub => "sub",
+            BinaryOp::Mul => "mul",
+            BinaryOp::Div => "div",
+            BinaryOp::Rem => "rem",
+            BinaryOp::Eq => "eq",
+            BinaryOp::Ne => "ne",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jump {}", target),
+            Terminator::Branch {
+                cond,
+                then,
+                otherwise,
+            } => {
+                write!(f, "branch {}, {}, {}", cond, then, otherwise)
+            }
+            Terminator::Return => write!(f, "return"),
+            Terminator::Unreachable => write!(f, "unreachable"),
+        }
+    }
+}
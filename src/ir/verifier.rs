@@ -0,0 +1,395 @@
+use super::{BinaryOp, BlockId, Constant, Function, InstKind, Program, Terminator, Ty, Value};
+
+/// checks the structure and the types of every function,
+/// returns a message for every broken rule
+pub fn verify(program: &Program) -> Vec<String> {
+    let mut errors = vec![];
+
+    for function in program.functions.iter() {
+        let mut verifier = Verifier {
+            program,
+            function,
+            errors: vec![],
+            blocks: vec![None; function.instructions.len()],
+        };
+
+        verifier.verify();
+
+        for error in verifier.errors {
+            errors.push(format!("fn {}: {}", function.name, error));
+        }
+    }
+
+    errors
+}
+
+struct Verifier<'a> {
+    program: &'a Program,
+    function: &'a Function,
+    errors: Vec<String>,
+
+    // the block and position that define each value
+    blocks: Vec<Option<(BlockId, usize)>>,
+}
+
+impl Verifier<'_> {
+    fn verify(&mut self) {
+        let function = self.function;
+
+        if function.blocks.is_empty() {
+            return self.errors.push("no entry block".to_string());
+        }
+
+        // every instruction is listed once
+        for (i, block) in function.blocks.iter().enumerate() {
+            for (position, value) in block.instructions.iter().enumerate() {
+                match self.blocks.get(value.0 as usize) {
+                    None => self
+                        .errors
+                        .push(format!("bb{}: {} is not defined", i, value)),
+                    Some(Some((other, _))) => self
+                        .errors
+                        .push(format!("bb{}: {} is already listed in {}", i, value, other)),
+                    Some(None) => {
+                        self.blocks[value.0 as usize] = Some((BlockId(i as u32), position))
+                    }
+                }
+            }
+
+            for target in block.terminator.successors() {
+                if target.0 as usize >= function.blocks.len() {
+                    self.errors
+                        .push(format!("bb{}: jumps to the unknown {}", i, target));
+                }
+            }
+        }
+
+        if !self.errors.is_empty() {
+            return;
+        }
+
+        let predecessors = function.predecessors();
+        let dominators = dominators(function, &predecessors);
+
+        for (i, block) in function.blocks.iter().enumerate() {
+            let id = BlockId(i as u32);
+            let mut is_leading = true;
+
+            for (position, value) in block.instructions.iter().enumerate() {
+                let instruction = function.instruction(*value);
+
+                match &instruction.kind {
+                    InstKind::Phi(entries) => {
+                        if !is_leading {
+                            self.error(id, *value, "phi after another instruction");
+                        }
+
+                        let mut sources: Vec<BlockId> =
+                            entries.iter().map(|(block, _)| *block).collect();
+                        let mut expected = predecessors[i].clone();
+                        sources.sort();
+                        expected.sort();
+
+                        if sources != expected {
+                            self.error(id, *value, "phi does not match the predecessors");
+                        }
+
+                        // the value reaches the end of the predecessor
+                        for (source, operand) in entries.iter() {
+                            let length = function.block(*source).instructions.len();
+                            self.check_use(&dominators, *source, length, *value, *operand);
+                        }
+                    }
+                    kind => {
+                        is_leading = false;
+
+                        for operand in kind.operands() {
+                            self.check_use(&dominators, id, position, *value, operand);
+                        }
+                    }
+                }
+
+                self.check_type(id, *value);
+            }
+
+            if let Terminator::Branch { cond, .. } = block.terminator {
+                let length = block.instructions.len();
+
+                if self.check_use(&dominators, id, length, cond, cond) && self.ty(cond) != Ty::Int {
+                    self.errors.push(format!(
+                        "bb{}: branch on {} of `{}`",
+                        i,
+                        cond,
+                        self.ty(cond)
+                    ));
+                }
+            }
+        }
+    }
+
+    fn error(&mut self, block: BlockId, value: Value, message: &str) {
+        self.errors
+            .push(format!("{}: {}: {}", block, value, message));
+    }
+
+    fn ty(&self, value: Value) -> Ty {
+        self.function.instruction(value).ty.clone()
+    }
+
+    /// `operand` is defined before `position` of `block`,
+    /// unreachable blocks are not checked
+    fn check_use(
+        &mut self,
+        dominators: &[Option<BlockId>],
+        block: BlockId,
+        position: usize,
+        user: Value,
+        operand: Value,
+    ) -> bool {
+        let Some(Some((defined, at))) = self.blocks.get(operand.0 as usize).copied() else {
+            self.error(block, user, &format!("uses the undefined {}", operand));
+            return false;
+        };
+
+        if dominators[block.0 as usize].is_none() {
+            return true;
+        }
+
+        let is_dominated = match defined == block {
+            true => at < position,
+            false => dominates(dominators, defined, block),
+        };
+
+        if !is_dominated {
+            self.error(
+                block,
+                user,
+                &format!("{} does not dominate the use", operand),
+            );
+        }
+
+        is_dominated
+    }
+
+    fn check_type(&mut self, block: BlockId, value: Value) {
+        if let Err(message) = self.type_of(value) {
+            self.error(block, value, &message);
+        }
+    }
+
+    /// checks the operand types of `value` against its own type
+    fn type_of(&self, value: Value) -> Result<(), String> {
+        let instruction = self.function.instruction(value);
+        let ty = &instruction.ty;
+
+        let expect = |is_valid: bool, what: &str| match is_valid {
+            true => Ok(()),
+            false => Err(format!("{} of `{}`", what, ty)),
+        };
+
+        match &instruction.kind {
+            InstKind::Const(Constant::Int(_)) => expect(*ty == Ty::Int, "integer constant"),
+            InstKind::Const(Constant::Float(_)) => expect(*ty == Ty::Float, "float constant"),
+            InstKind::Const(Constant::None) => {
+                expect(matches!(ty, Ty::Optional(_)), "none constant")
+            }
+            InstKind::Copy(operand) => expect(self.ty(*operand) == *ty, "copy"),
+            InstKind::Neg(operand) => expect(ty.is_numeric() && self.ty(*operand) == *ty, "neg"),
+            InstKind::Binary { op, lhs, rhs, .. } => {
+                let operand = self.ty(*lhs);
+                let is_valid = operand.is_numeric() && self.ty(*rhs) == operand;
+
+                match op {
+                    BinaryOp::Eq | BinaryOp::Ne => expect(is_valid && *ty == Ty::Int, "compare"),
+                    _ => expect(is_valid && operand == *ty, "arithmetic"),
+                }
+            }
+            InstKind::Struct(fields) => {
+                let Some(decl) = self.struct_fields(ty) else {
+                    return expect(false, "struct");
+                };
+
+                let types: Vec<Ty> = fields.iter().map(|field| self.ty(*field)).collect();
+                expect(types == decl, "struct")
+            }
+            InstKind::Field { value, field } => {
+                let field = self
+                    .struct_fields(&self.ty(*value))
+                    .and_then(|fields| fields.get(*field as usize).cloned());
+
+                expect(field.as_ref() == Some(ty), "field")
+            }
+            InstKind::SetField { value, field, to } => {
+                let field = self
+                    .struct_fields(ty)
+                    .and_then(|fields| fields.get(*field as usize).cloned());
+
+                expect(
+                    self.ty(*value) == *ty && field == Some(self.ty(*to)),
+                    "setfield",
+                )
+            }
+            InstKind::Variant { variant, payload } => {
+                let types: Vec<Ty> = payload.iter().map(|value| self.ty(*value)).collect();
+                expect(self.payload(ty, *variant) == Some(types), "variant")
+            }
+            InstKind::Tag(value) => expect(*ty == Ty::Int && self.is_enum(&self.ty(*value)), "tag"),
+            InstKind::Payload {
+                value,
+                variant,
+                index,
+            } => {
+                let payload = self.payload(&self.ty(*value), *variant);
+                let field = payload.and_then(|payload| payload.get(*index as usize).cloned());
+
+                expect(field.as_ref() == Some(ty), "payload")
+            }
+            InstKind::IsNone(value) => expect(
+                *ty == Ty::Int && matches!(self.ty(*value), Ty::Optional(_)),
+                "isnone",
+            ),
+            InstKind::Wrap(value) => {
+                let operand = self.ty(*value);
+                let is_valid = !matches!(operand, Ty::Optional(_) | Ty::Unit);
+
+                expect(is_valid && Ty::optional(operand) == *ty, "wrap")
+            }
+            InstKind::Unwrap(value) => {
+                expect(self.ty(*value) == Ty::optional(ty.clone()), "unwrap")
+            }
+            InstKind::Phi(entries) => {
+                let is_valid = entries.iter().all(|(_, value)| self.ty(*value) == *ty);
+                expect(is_valid, "phi")
+            }
+            InstKind::Print(value) => {
+                expect(*ty == Ty::Unit && self.ty(*value) != Ty::Unit, "print")
+            }
+        }
+    }
+
+    fn struct_fields(&self, ty: &Ty) -> Option<Vec<Ty>> {
+        let Ty::Struct(name) = ty else {
+            return None;
+        };
+
+        let decl = self
+            .program
+            .structs
+            .iter()
+            .find(|decl| decl.name == *name)?;
+        Some(decl.fields.iter().map(|(_, ty)| ty.clone()).collect())
+    }
+
+    fn payload(&self, ty: &Ty, variant: u32) -> Option<Vec<Ty>> {
+        let Ty::Enum(name) = ty else {
+            return None;
+        };
+
+        let decl = self.program.enums.iter().find(|decl| decl.name == *name)?;
+        decl.variants
+            .get(variant as usize)
+            .map(|(_, payload)| payload.clone())
+    }
+
+    fn is_enum(&self, ty: &Ty) -> bool {
+        matches!(ty, Ty::Enum(name) if self.program.enums.iter().any(|decl| decl.name == *name))
+    }
+}
+
+/// the blocks reachable from the entry, every block before its successors
+/// except along back edges
+pub fn reverse_postorder(function: &Function) -> Vec<BlockId> {
+    let mut order = vec![];
+    let mut visited = vec![false; function.blocks.len()];
+    let mut stack = vec![(BlockId(0), 0)];
+    visited[0] = true;
+
+    while let Some((block, next)) = stack.pop() {
+        // reversed, so `then` comes before `otherwise`
+        let mut successors = function.block(block).terminator.successors();
+        successors.reverse();
+
+        match successors.get(next) {
+            Some(successor) => {
+                stack.push((block, next + 1));
+
+                if !visited[successor.0 as usize] {
+                    visited[successor.0 as usize] = true;
+                    stack.push((*successor, 0));
+                }
+            }
+            None => order.push(block),
+        }
+    }
+
+    order.reverse();
+    order
+}
+
+/// the immediate dominator of every reachable block, the entry is its own
+pub fn dominators(function: &Function, predecessors: &[Vec<BlockId>]) -> Vec<Option<BlockId>> {
+    let order = reverse_postorder(function);
+
+    let mut numbers = vec![usize::MAX; function.blocks.len()];
+    for (number, block) in order.iter().enumerate() {
+        numbers[block.0 as usize] = number;
+    }
+
+    let mut idoms: Vec<Option<BlockId>> = vec![None; function.blocks.len()];
+    idoms[0] = Some(BlockId(0));
+
+    // "a simple, fast dominance algorithm" by Cooper, Harvey and Kennedy
+    let mut is_changed = true;
+    while is_changed {
+        is_changed = false;
+
+        for block in order.iter().skip(1) {
+            let mut idom: Option<BlockId> = None;
+
+            for predecessor in predecessors[block.0 as usize].iter() {
+                if idoms[predecessor.0 as usize].is_none() {
+                    continue;
+                }
+
+                idom = Some(match idom {
+                    None => *predecessor,
+                    Some(mut other) => {
+                        let mut finger = *predecessor;
+
+                        while finger != other {
+                            while numbers[finger.0 as usize] > numbers[other.0 as usize] {
+                                finger = idoms[finger.0 as usize].unwrap();
+                            }
+                            while numbers[other.0 as usize] > numbers[finger.0 as usize] {
+                                other = idoms[other.0 as usize].unwrap();
+                            }
+                        }
+
+                        finger
+                    }
+                });
+            }
+
+            if idoms[block.0 as usize] != idom {
+                idoms[block.0 as usize] = idom;
+                is_changed = true;
+            }
+        }
+    }
+
+    idoms
+}
+
+/// `true` when every path from the entry to `block` passes `dominator`
+pub fn dominates(idoms: &[Option<BlockId>], dominator: BlockId, mut block: BlockId) -> bool {
+    loop {
+        if block == dominator {
+            return true;
+        }
+
+        match idoms[block.0 as usize] {
+            Some(idom) if idom != block => block = idom,
+            _ => return false,
+        }
+    }
+}
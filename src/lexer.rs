@@ -1,8 +1,11 @@
-use pattern::Pattern;
 use tokens::{Token, TokenNode};
 
+pub mod json;
 pub mod tokens;
-mod pattern;
+pub mod trivia;
+pub mod validation;
+mod operators;
+mod scanners;
 mod handlers;
 
 struct Lexer {
@@ -31,116 +34,141 @@ impl Lexer {
     }
 }
 
-pub fn tokenize<'a>(buffer: &'a str) -> Vec<tokens::TokenNode> {
-    let patterns = Pattern::new();
+pub fn tokenize(buffer: &str) -> Vec<tokens::TokenNode> {
     let mut lexer = Lexer::new();
+    run(&mut lexer, buffer, |_| false);
 
-    while lexer.index < buffer.len() {
-        let slice = &buffer[lexer.index..];
-        let index: u32 = lexer.index.try_into().unwrap();
-
-        if let Some(value) = patterns.single_line_comment.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Comment, index);
-
-            // the comment has eaten the new line character
-            // for shy semi, we need to readd it manual
-            lexer.push(TokenNode {
-                index,
-                token: Token::Eol,
-            });
-
-            continue;
-        }
-
-        if let Some(value) = patterns.multi_line_comment.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Comment, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.string.find(&slice) {
-            handlers::default(&mut lexer, value, Token::String, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.identifier.find(&slice) {
-            handlers::identifier(&mut lexer, value, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.number.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Number, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.open_paren.find(&slice) {
-            handlers::default(&mut lexer, value, Token::OpenParen, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.close_paren.find(&slice) {
-            handlers::default(&mut lexer, value, Token::CloseParen, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.equal.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Equal, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.plus.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Plus, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.minus.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Minus, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.star.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Star, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.slash.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Slash, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.percent.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Percent, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.end_of_line.find(&slice) {
-            handlers::end_of_line(&mut lexer, value, index);
-            continue;
-        }
-
-        if let Some(value) = patterns.semi.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Semi, index);
-            continue;
-        }
+    lexer.tokens
+}
 
-        if let Some(value) = patterns.colon.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Colon, index);
-            continue;
-        }
+/// continues lexing at byte `start`, `tokens` are all tokens in front of it,
+/// stops as soon as `is_synced` accepts a new token and returns whether it did
+pub fn relex(
+    buffer: &str,
+    tokens: Vec<TokenNode>,
+    start: usize,
+    is_synced: impl FnMut(&TokenNode) -> bool,
+) -> (Vec<TokenNode>, bool) {
+    let mut lexer = Lexer { tokens, index: start };
+    let is_synced = run(&mut lexer, buffer, is_synced);
+
+    (lexer.tokens, is_synced)
+}
 
-        if let Some(value) = patterns.question.find(&slice) {
-            handlers::default(&mut lexer, value, Token::Question, index);
-            continue;
-        }
+fn run(lexer: &mut Lexer, buffer: &str, mut is_synced: impl FnMut(&TokenNode) -> bool) -> bool {
+    use Token::*;
+
+    let bytes = buffer.as_bytes();
+    let mut count = lexer.tokens.len();
+
+    while lexer.index < bytes.len() {
+        if lexer.tokens.len() > count {
+            count = lexer.tokens.len();
+
+            if is_synced(&lexer.tokens[count - 1]) {
+                return true;
+            }
+        }
+
+        let start = lexer.index;
+
+        // `validation::validate_source` rejects sources beyond `u32::MAX`
+        let index: u32 = start.try_into().unwrap();
+
+        let (token, end) = match bytes[start] {
+            b'"' => match scanners::string(bytes, start) {
+                Some(end) => {
+                    handlers::string(lexer, &buffer[start..end], index);
+                    continue;
+                }
+                // the rest of the line is not lexed as code
+                None => (Error, scanners::rest_of_line(bytes, start)),
+            },
+            b'0'..=b'9' => {
+                let end = scanners::number(bytes, start);
+                handlers::number(lexer, &buffer[start..end], index);
+                continue;
+            }
+            b'\r' | b'\n' => {
+                let end = scanners::end_of_line(bytes, start);
+                handlers::end_of_line(lexer, &buffer[start..end], index);
+                continue;
+            }
+
+            _ => match operators::longest_match(bytes, start) {
+                Some(("//", _)) => {
+                    let end = scanners::rest_of_line(bytes, start);
+                    handlers::comment(lexer, &buffer[start..end], index);
+                    continue;
+                }
+                Some(("/*", _)) => match scanners::block_comment(bytes, start) {
+                    Some(end) => {
+                        handlers::comment(lexer, &buffer[start..end], index);
+                        continue;
+                    }
+                    // the rest of the source is not lexed as code
+                    None => (Error, bytes.len()),
+                },
+                Some((value, token)) => (token.clone(), start + value.len()),
+
+                None => {
+                    if let Some(end) = scanners::identifier(buffer, start) {
+                        handlers::identifier(lexer, &buffer[start..end], index);
+                        continue;
+                    }
+
+                    let end = scanners::whitespace(buffer, start);
+                    if end > start {
+                        handlers::skip(lexer, end - start);
+                        continue;
+                    }
+
+                    let length = buffer[start..].chars().next().unwrap().len_utf8();
+                    (Error, start + length)
+                }
+            },
+        };
+
+        handlers::default(lexer, &buffer[start..end], token, index);
+    }
 
-        if let Some(value) = patterns.whitespace.find(&slice) {
-            handlers::skip(&mut lexer, value.len());
-            continue;
-        }
+    lexer.tokens.len() > count && is_synced(&lexer.tokens[count])
+}
 
-        // @TODO: error nothing matched (at ...)
-        println!("end");
-        break;
-    }
+/// the text of the doc comment starting at `index`, without its markers
+pub fn doc_text(buffer: &str, index: u32) -> String {
+    handlers::doc_text(lexeme(buffer, index, &Token::DocComment))
+}
 
-    lexer.tokens
+/// re-slices the source text of the token starting at `index`
+pub fn lexeme<'a>(buffer: &'a str, index: u32, token: &Token) -> &'a str {
+    let start = index as usize;
+    let bytes = buffer.as_bytes();
+
+    let end = match token {
+        Token::Number => scanners::number(bytes, start),
+        Token::String => scanners::string(bytes, start).unwrap_or(start),
+        Token::Comment | Token::DocComment => match bytes.get(start + 1) {
+            Some(b'/') => scanners::rest_of_line(bytes, start),
+            _ => scanners::block_comment(bytes, start).unwrap_or(start),
+        },
+        Token::Error => match bytes[start] {
+            b'"' => scanners::rest_of_line(bytes, start),
+            b'/' => bytes.len(),
+            _ => start + buffer[start..].chars().next().map_or(0, char::len_utf8),
+        },
+        Token::Eol => scanners::end_of_line(bytes, start),
+        Token::Eof => start,
+        Token::Identifier
+        | Token::Null
+        | Token::Let
+        | Token::Const
+        | Token::Struct
+        | Token::Enum
+        | Token::Match
+        | Token::Type => scanners::identifier(buffer, start).unwrap_or(start),
+        _ => operators::longest_match(bytes, start).map_or(start, |(value, _)| start + value.len()),
+    };
+
+    &buffer[start..end]
 }
@@ -1,4 +1,8 @@
-use super::{tokens::{Token, TokenNode}, Lexer};
+use std::borrow::Cow;
+
+use crate::utils::interner::Symbol;
+
+use super::{tokens::{Token, TokenNode, TokenValue}, Lexer};
 
 pub fn skip(lexer: &mut Lexer, length: usize) {
     lexer.advance(length);
@@ -16,16 +20,85 @@ pub fn identifier(lexer: &mut Lexer, value: &str, index: u32) {
     let token = match value {
         "let" => Token::Let,
         "const" => Token::Const,
-        _ => Token::Identifier
+        "struct" => Token::Struct,
+        "enum" => Token::Enum,
+        "match" => Token::Match,
+        "type" => Token::Type,
+        "none" => Token::Null,
+        _ => {
+            let symbol = TokenValue::Symbol(Symbol::intern(value));
+            return with_value(lexer, value, Token::Identifier, symbol, index);
+        }
     };
 
     default(lexer, value, token, index)
 }
 
+pub fn number(lexer: &mut Lexer, value: &str, index: u32) {
+    let digits = match value.contains('_') {
+        true => Cow::Owned(value.replace('_', "")),
+        false => Cow::Borrowed(value),
+    };
+
+    let number = match value.contains('.') {
+        true => digits.parse().map(TokenValue::Float).ok(),
+        false => digits.parse().map(TokenValue::Int).ok(),
+    };
+
+    // too large integers are reported by the type checker
+    with_value(lexer, value, Token::Number, number.unwrap_or(TokenValue::None), index)
+}
+
+/// the contents are the span without the quotes, only identifiers are interned
+pub fn string(lexer: &mut Lexer, value: &str, index: u32) {
+    default(lexer, value, Token::String, index)
+}
+
+/// `///` and `/** */` are doc comments, `////` and `/***` are not
+pub fn comment(lexer: &mut Lexer, value: &str, index: u32) {
+    let is_doc = match value.as_bytes() {
+        [b'/', b'/', b'/', rest @ ..] => rest.first() != Some(&b'/'),
+        [b'/', b'*', b'*', rest @ ..] => !matches!(rest.first(), Some(b'*' | b'/')),
+        _ => false,
+    };
+
+    match is_doc {
+        true => default(lexer, value, Token::DocComment, index),
+        false => default(lexer, value, Token::Comment, index),
+    }
+}
+
+/// the text of a doc comment without its markers
+pub fn doc_text(value: &str) -> String {
+    match value.strip_prefix("///") {
+        Some(line) => line.strip_prefix(' ').unwrap_or(line).trim_end().to_string(),
+        None => block_doc_text(&value[3..value.len() - 2]),
+    }
+}
+
+/// strips the leading `*` of every line
+fn block_doc_text(body: &str) -> String {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect();
+
+    lines.join("\n").trim().to_string()
+}
+
 pub fn default(lexer: &mut Lexer, value: &str, token: Token, index: u32) {
+    with_value(lexer, value, token, TokenValue::None, index)
+}
+
+fn with_value(lexer: &mut Lexer, value: &str, token: Token, payload: TokenValue, index: u32) {
     let node = TokenNode {
         token,
         index,
+        value: payload,
     };
 
     lexer.push(node);
@@ -0,0 +1,45 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    lexeme,
+    tokens::{TokenNode, TokenValue},
+};
+
+// --- format (version 1) --- //
+// tokens: { "version": 1, "tokens": [token, ...] }
+// token:  { "token", "text", "span": [start, end], "value"? }
+//   token: the `Token`, e.g. "Identifier", line breaks are "Eol"
+//   text:  the source text of the token
+//   span:  byte offsets of the token in the source
+//   value: the integer, float or name of a literal, the text of a doc comment
+
+const VERSION: u64 = 1;
+
+/// the tokens of a source file, `buffer` is the source they were lexed from
+pub fn to_json(buffer: &str, tokens: &[TokenNode]) -> Value {
+    let tokens: Vec<Value> = tokens
+        .iter()
+        .map(|node| {
+            let text = lexeme(buffer, node.index, &node.token);
+            let end = node.index as usize + text.len();
+
+            let mut object = Map::new();
+            object.insert("token".into(), format!("{:?}", node.token).into());
+            object.insert("text".into(), text.into());
+            object.insert("span".into(), json!([node.index, end]));
+
+            match node.value {
+                TokenValue::None => (),
+                TokenValue::Symbol(symbol) => {
+                    _ = object.insert("value".into(), symbol.as_str().into())
+                }
+                TokenValue::Int(value) => _ = object.insert("value".into(), value.into()),
+                TokenValue::Float(value) => _ = object.insert("value".into(), value.into()),
+            }
+
+            Value::Object(object)
+        })
+        .collect();
+
+    json!({ "version": VERSION, "tokens": tokens })
+}
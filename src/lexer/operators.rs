@@ -0,0 +1,94 @@
+use super::tokens::Token::{self, *};
+
+/// punctuators and operators, the longest matching entry wins,
+/// so the order of this table does not matter
+const OPERATORS: &[(&str, Token)] = &[
+    /* --- punctuators --- */
+    ("(", OpenParen),
+    (")", CloseParen),
+    ("{", OpenBrace),
+    ("}", CloseBrace),
+    (",", Comma),
+    (".", Dot),
+    (";", Semi),
+    ("?", Question),
+    ("?.", QuestionDot),
+    (":", Colon),
+    ("=", Equal),
+    ("=>", FatArrow),
+
+    /* --- operators --- */
+    ("+", Plus),
+    ("-", Minus),
+    ("*", Star),
+    ("/", Slash),
+    ("%", Percent),
+    ("??", QuestionQuestion),
+
+    /* --- special characters --- */
+    // the comment body is scanned by the lexer
+    ("//", Comment),
+    ("/*", Comment),
+];
+
+/// finds the longest operator at `start`
+pub fn longest_match(bytes: &[u8], start: usize) -> Option<&'static (&'static str, Token)> {
+    let slice = &bytes[start..];
+    let mut found: Option<&(&str, Token)> = None;
+    let mut length = 0;
+
+    for entry in OPERATORS {
+        let value = entry.0.as_bytes();
+
+        if value.len() > length && slice.starts_with(value) {
+            found = Some(entry);
+            length = value.len();
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::{tokenize, tokens::Token::*};
+
+    use super::longest_match;
+
+    fn matched(text: &str, start: usize) -> Option<&'static str> {
+        longest_match(text.as_bytes(), start).map(|(value, _)| *value)
+    }
+
+    #[test]
+    fn the_longest_operator_wins() {
+        assert_eq!(matched("??", 0), Some("??"));
+        assert_eq!(matched("?.x", 0), Some("?."));
+        assert_eq!(matched("? 1", 0), Some("?"));
+        assert_eq!(matched("=>", 0), Some("=>"));
+        assert_eq!(matched("= >", 0), Some("="));
+        assert_eq!(matched("// c", 0), Some("//"));
+        assert_eq!(matched("/*", 0), Some("/*"));
+        assert_eq!(matched("a / b", 2), Some("/"));
+        assert_eq!(matched("@", 0), None);
+    }
+
+    #[test]
+    fn operators_next_to_each_other() {
+        let tokens: Vec<_> = tokenize("a???.b=>c")
+            .into_iter()
+            .map(|node| node.token)
+            .collect();
+
+        assert_eq!(
+            tokens,
+            [
+                Identifier,
+                QuestionQuestion,
+                QuestionDot,
+                Identifier,
+                FatArrow,
+                Identifier
+            ]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use unicode_xid::UnicodeXID;
+
+// every scanner gets the start of its token and returns the end (exclusive)
+
+/// everything up to the line break, e.g. `// ...`
+pub fn rest_of_line(bytes: &[u8], start: usize) -> usize {
+    match bytes[start..].iter().position(|&byte| byte == b'\n' || byte == b'\r') {
+        Some(length) => start + length,
+        None => bytes.len(),
+    }
+}
+
+/// `/* ... */`, block comments nest, `None` if it is never closed
+pub fn block_comment(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut end = start;
+
+    while end + 1 < bytes.len() {
+        match &bytes[end..end + 2] {
+            b"/*" => depth += 1,
+            b"*/" => depth -= 1,
+            _ => {
+                end += 1;
+                continue;
+            }
+        }
+
+        end += 2;
+
+        if depth == 0 {
+            return Some(end);
+        }
+    }
+
+    None
+}
+
+/// `"..."`, `None` if it is never closed
+pub fn string(bytes: &[u8], start: usize) -> Option<usize> {
+    bytes[start + 1..]
+        .iter()
+        .position(|&byte| byte == b'"')
+        .map(|length| start + 1 + length + 1)
+}
+
+/// `[0-9][0-9_]*(\.[0-9]*)?`
+pub fn number(bytes: &[u8], start: usize) -> usize {
+    let mut end = start + 1;
+
+    while let Some(b'0'..=b'9' | b'_') = bytes.get(end) {
+        end += 1;
+    }
+
+    if let Some(b'.') = bytes.get(end) {
+        end += 1;
+
+        while let Some(b'0'..=b'9') = bytes.get(end) {
+            end += 1;
+        }
+    }
+
+    end
+}
+
+/// `[\r\n]+`
+pub fn end_of_line(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+
+    while let Some(b'\r' | b'\n') = bytes.get(end) {
+        end += 1;
+    }
+
+    end
+}
+
+/// unicode white space, line breaks are left for `end_of_line`
+pub fn whitespace(buffer: &str, start: usize) -> usize {
+    let length: usize = buffer[start..]
+        .chars()
+        .take_while(|c| c.is_whitespace() && !matches!(c, '\r' | '\n'))
+        .map(char::len_utf8)
+        .sum();
+
+    start + length
+}
+
+/// `[\p{XID_Start}_]\p{XID_Continue}*`, `None` if `start` is no identifier
+pub fn identifier(buffer: &str, start: usize) -> Option<usize> {
+    let bytes = buffer.as_bytes();
+    let mut end = start;
+
+    // ascii fast path
+    while let Some(byte) = bytes.get(end) {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => end += 1,
+            b'0'..=b'9' if end > start => end += 1,
+            _ => break,
+        }
+    }
+
+    for c in buffer[end..].chars() {
+        let is_valid = match end == start {
+            true => c.is_xid_start() || c == '_',
+            false => c.is_xid_continue(),
+        };
+
+        if !is_valid {
+            break;
+        }
+
+        end += c.len_utf8();
+    }
+
+    match end > start {
+        true => Some(end),
+        false => None,
+    }
+}
@@ -1,16 +1,27 @@
+use core::fmt;
+
+use crate::utils::interner::Symbol;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     /* --- literals & constants --- */
     Number,
     String,
+    Null, // `none`, not named `None` to avoid clashes with `Option::None`
 
     /* --- punctuators --- */
     OpenParen,
     CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Comma,
+    Dot,
     Semi,
     Question,
+    QuestionDot,
     Colon,
     Equal,
+    FatArrow,
 
     /* --- operators --- */
     Plus,
@@ -18,6 +29,7 @@ pub enum Token {
     Star,
     Slash,
     Percent,
+    QuestionQuestion,
 
     /* --- identifier --- */
     Identifier,
@@ -26,16 +38,92 @@ pub enum Token {
     // Func,
     Let,
     Const,
-    // Type,
+    Struct,
+    Enum,
+    Match,
+    Type,
 
     /* --- misc & special characters --- */
     Comment,
+    DocComment, // `/// ...` and `/** ... */`
+    Error,      // unknown character or unterminated string, see `validation::validate_tokens`
     Eol, // end of line (shy semi)
     Eof, // end of file
 }
 
-#[derive(Debug, Clone)]
+impl Token {
+    /// the source text of keywords, punctuators and operators
+    pub fn text(&self) -> Option<&'static str> {
+        use Token::*;
+
+        let text = match self {
+            Null => "none",
+            OpenParen => "(",
+            CloseParen => ")",
+            OpenBrace => "{",
+            CloseBrace => "}",
+            Comma => ",",
+            Dot => ".",
+            Semi => ";",
+            Question => "?",
+            QuestionDot => "?.",
+            Colon => ":",
+            Equal => "=",
+            FatArrow => "=>",
+            Plus => "+",
+            Minus => "-",
+            Star => "*",
+            Slash => "/",
+            Percent => "%",
+            QuestionQuestion => "??",
+            Let => "let",
+            Const => "const",
+            Struct => "struct",
+            Enum => "enum",
+            Match => "match",
+            Type => "type",
+            _ => return None,
+        };
+
+        Some(text)
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Token::*;
+
+        if let Some(text) = self.text() {
+            return write!(f, "`{}`", text);
+        }
+
+        let text = match self {
+            Number => "number",
+            String => "string",
+            Identifier => "identifier",
+            Comment => "comment",
+            DocComment => "doc comment",
+            Error => "invalid token",
+            Eol => "line break",
+            _ => "end of file",
+        };
+
+        write!(f, "{}", text)
+    }
+}
+
+/// pre-parsed payload of a token
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenValue {
+    None,
+    Symbol(Symbol), // identifiers, strings and doc comments are read from their span
+    Int(i64),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TokenNode {
     pub index: u32,
     pub token: Token,
+    pub value: TokenValue,
 }
\ No newline at end of file
@@ -0,0 +1,26 @@
+use super::{
+    lexeme,
+    tokens::{Token, TokenNode},
+};
+
+/// a comment, comments are not part of the syntax tree
+#[derive(Debug, Clone)]
+pub struct Trivia<'a> {
+    pub index: u32,
+    pub token: Token,
+    pub text: &'a str,
+}
+
+/// collects all comments in source order, doc comments included,
+/// so tools like a formatter can reproduce them
+pub fn trivia<'a>(buffer: &'a str, tokens: &[TokenNode]) -> Vec<Trivia<'a>> {
+    tokens
+        .iter()
+        .filter(|node| matches!(node.token, Token::Comment | Token::DocComment))
+        .map(|node| Trivia {
+            index: node.index,
+            token: node.token.clone(),
+            text: lexeme(buffer, node.index, &node.token),
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+
+use unicode_security::{skeleton, RestrictionLevel, RestrictionLevelDetection};
+
+use crate::diagnostics::Diagnostic;
+
+use super::{
+    lexeme,
+    tokens::{Token, TokenNode, TokenValue},
+};
+
+/// token offsets are stored as `u32`
+const MAX_SOURCE_SIZE: usize = u32::MAX as usize;
+
+/// characters that change the visual order of the source (trojan source)
+fn is_bidi_control(c: char) -> bool {
+    matches!(
+        c,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// characters that are not rendered at all
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// checks the raw source before it is tokenized, any error is fatal
+pub fn validate_source(buffer: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    if buffer.len() > MAX_SOURCE_SIZE {
+        let message = format!(
+            "source is too large: {} bytes, the limit is {} bytes",
+            buffer.len(),
+            MAX_SOURCE_SIZE
+        );
+
+        diagnostics.push(Diagnostic::error(0, message));
+        return diagnostics;
+    }
+
+    for (index, c) in buffer.char_indices() {
+        // a leading byte order mark is fine
+        if index == 0 && c == '\u{FEFF}' {
+            continue;
+        }
+
+        let message = match c {
+            c if is_bidi_control(c) => "unicode bidirectional control character",
+            c if is_invisible(c) => "invisible unicode character",
+            _ => continue,
+        };
+
+        let message = format!("{} U+{:04X} is not allowed", message, c as u32);
+        diagnostics.push(Diagnostic::error(index as u32, message));
+    }
+
+    diagnostics
+}
+
+/// reports the tokens the lexer could not make sense of
+pub fn validate_tokens(buffer: &str, tokens: &[TokenNode]) -> Vec<Diagnostic> {
+    tokens
+        .iter()
+        .filter(|node| node.token == Token::Error)
+        .map(|node| {
+            let text = lexeme(buffer, node.index, &node.token);
+
+            let message = match text.as_bytes()[0] {
+                b'"' => "unterminated string".to_string(),
+                b'/' => "unterminated comment".to_string(),
+                _ => format!("unexpected character `{}`", text.escape_debug()),
+            };
+
+            Diagnostic::error(node.index, message)
+        })
+        .collect()
+}
+
+/// warns about identifiers that look like other identifiers
+pub fn validate_identifiers(tokens: &[TokenNode]) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+
+    // skeleton -> first identifier with that skeleton
+    let mut skeletons: HashMap<String, String> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for node in tokens.iter().filter(|node| node.token == Token::Identifier) {
+        let TokenValue::Symbol(symbol) = node.value else {
+            continue;
+        };
+
+        // every name is reported once, at its first occurrence
+        if !seen.insert(symbol) {
+            continue;
+        }
+
+        let name = symbol.as_str();
+
+        let is_mixed = !name.check_restriction_level(RestrictionLevel::HighlyRestrictive);
+        if is_mixed {
+            let message = format!("identifier `{}` mixes characters of different scripts", name);
+            diagnostics.push(Diagnostic::warning(node.index, message));
+        }
+
+        let existing = skeletons
+            .entry(skeleton(name).collect())
+            .or_insert(name.to_string());
+
+        // plain ascii look-alikes like `rn` and `m` are left alone
+        if existing != name && !(existing.is_ascii() && name.is_ascii()) {
+            let message = format!("identifier `{}` is confusable with `{}`", name, existing);
+            diagnostics.push(Diagnostic::warning(node.index, message));
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{diagnostics::Diagnostic, lexer::tokenize};
+
+    use super::{validate_identifiers, validate_source};
+
+    fn messages(diagnostics: Vec<Diagnostic>) -> Vec<(u32, String)> {
+        diagnostics.into_iter().map(|diagnostic| (diagnostic.index, diagnostic.message)).collect()
+    }
+
+    #[test]
+    fn hidden_characters() {
+        assert_eq!(messages(validate_source("\u{FEFF}let a = 1\n")), []);
+
+        let buffer = "let a\u{200B} = 1 // \u{202E}1 =\n";
+        assert_eq!(
+            messages(validate_source(buffer)),
+            [
+                (5, "invisible unicode character U+200B is not allowed".to_string()),
+                (16, "unicode bidirectional control character U+202E is not allowed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        // `é` precomposed and as `e` with a combining accent
+        let tokens = tokenize("let caf\u{E9} = 1\ncafe\u{301}\n");
+        assert_eq!(tokens[1].value, tokens[5].value);
+        assert_eq!(messages(validate_identifiers(&tokens)), []);
+
+        // a cyrillic `а` in front of latin letters
+        let tokens = tokenize("let pass = 1\nlet p\u{430}ss = 2\n");
+        assert_eq!(
+            messages(validate_identifiers(&tokens)),
+            [
+                (17, "identifier `p\u{430}ss` mixes characters of different scripts".to_string()),
+                (17, "identifier `p\u{430}ss` is confusable with `pass`".to_string()),
+            ]
+        );
+
+        // scripts that are not mixed within a name are fine
+        assert_eq!(messages(validate_identifiers(&tokenize("let été = 日本\n"))), []);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod ast;
+pub mod codegen;
+pub mod diagnostics;
+pub mod formatter;
+pub mod incremental;
+pub mod interpreter;
+pub mod ir;
+pub mod lexer;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod repl;
+pub mod semantic;
+pub mod utils;
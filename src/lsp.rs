@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use positions::LineIndex;
+use serde_json::{json, Value};
+
+use crate::{
+    diagnostics::Diagnostic,
+    incremental::{Document, Edit},
+    lexer::validation::{validate_identifiers, validate_source, validate_tokens},
+    semantic::{analyze, Analysis},
+};
+
+mod features;
+mod positions;
+mod rpc;
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// an open file, reanalyzed after every change
+struct Source {
+    document: Document,
+    analysis: Analysis,
+    lines: LineIndex,
+}
+
+impl Source {
+    fn new(text: String) -> Source {
+        let document = Document::new(text);
+        let analysis = analyze(document.statements());
+        let lines = LineIndex::new(document.buffer());
+
+        Source {
+            document,
+            analysis,
+            lines,
+        }
+    }
+
+    /// applies one `TextDocumentContentChangeEvent`, without a range it replaces the text
+    fn change(&mut self, change: &Value) {
+        let text = change["text"].as_str().unwrap_or_default().to_string();
+
+        match change.get("range") {
+            Some(range) => {
+                let buffer = self.document.buffer();
+                let start = self.lines.offset(buffer, &range["start"]);
+                let end = self.lines.offset(buffer, &range["end"]).max(start);
+
+                self.document.apply(&Edit {
+                    range: start..end,
+                    text,
+                });
+            }
+            None => self.document = Document::new(text),
+        }
+
+        self.lines = LineIndex::new(self.document.buffer());
+    }
+
+    /// lexer, parser and checker diagnostics, in source order
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let buffer = self.document.buffer();
+        let tokens = self.document.tokens();
+
+        let mut diagnostics = validate_source(buffer);
+        diagnostics.extend(validate_tokens(buffer, tokens));
+        diagnostics.extend(validate_identifiers(tokens));
+        diagnostics.extend(self.document.diagnostics().cloned());
+        diagnostics.extend(self.analysis.diagnostics.iter().cloned());
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.index);
+        diagnostics
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    sources: HashMap<String, Source>,
+}
+
+/// runs the language server until the client sends `exit` or closes the input
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::default();
+
+    while let Some(body) = rpc::read(&mut input)? {
+        let message: Value = match serde_json::from_slice(&body) {
+            Ok(message) => message,
+            Err(error) => {
+                let response = failure(&Value::Null, PARSE_ERROR, error.to_string());
+                rpc::write(&mut output, &response)?;
+                continue;
+            }
+        };
+
+        // responses of the client are not needed
+        let Some(method) = message["method"].as_str() else {
+            continue;
+        };
+
+        if method == "exit" {
+            break;
+        }
+
+        let params = &message["params"];
+
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, message)) => failure(id, code, message),
+                };
+
+                rpc::write(&mut output, &response)?;
+            }
+            None => {
+                for notification in server.notify(method, params) {
+                    rpc::write(&mut output, &notification)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn failure(id: &Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Value) -> Result<Value, (i64, String)> {
+        if method == "initialize" {
+            return Ok(features::initialize());
+        }
+
+        if method == "shutdown" {
+            return Ok(Value::Null);
+        }
+
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let source = self.sources.get(uri);
+        let offset = |source: &Source| {
+            let buffer = source.document.buffer();
+            source.lines.offset(buffer, &params["position"])
+        };
+
+        let result = match (method, source) {
+            ("textDocument/hover", Some(source)) => features::hover(source, offset(source)),
+            ("textDocument/definition", Some(source)) => {
+                features::definition(uri, source, offset(source))
+            }
+            ("textDocument/documentSymbol", Some(source)) => features::document_symbols(source),
+            ("textDocument/semanticTokens/full", Some(source)) => features::semantic_tokens(source),
+            (
+                "textDocument/hover"
+                | "textDocument/definition"
+                | "textDocument/documentSymbol"
+                | "textDocument/semanticTokens/full",
+                None,
+            ) => Value::Null,
+            _ => return Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        };
+
+        Ok(result)
+    }
+
+    /// handles a notification, returns the notifications to send back
+    fn notify(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let document = &params["textDocument"];
+        let uri = document["uri"].as_str().unwrap_or_default().to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = document["text"].as_str().unwrap_or_default();
+                self.sources
+                    .insert(uri.clone(), Source::new(text.to_string()));
+            }
+            "textDocument/didChange" => {
+                let Some(source) = self.sources.get_mut(&uri) else {
+                    return vec![];
+                };
+
+                let changes = params["contentChanges"].as_array();
+                for change in changes.into_iter().flatten() {
+                    source.change(change);
+                }
+
+                source.analysis = analyze(source.document.statements());
+            }
+            "textDocument/didClose" => {
+                self.sources.remove(&uri);
+                return vec![features::publish_diagnostics(&uri, None)];
+            }
+            _ => return vec![],
+        }
+
+        vec![features::publish_diagnostics(&uri, self.sources.get(&uri))]
+    }
+}
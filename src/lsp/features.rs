@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ast::{
+        expressions::{ExpressionKind, ExpressionNode},
+        statements::{Statement, StatementFlag},
+        view::ExprRef,
+        visitor::{walk_expr, walk_stmt, Visitor},
+    },
+    diagnostics::Severity,
+    lexer::{
+        doc_text, lexeme,
+        tokens::{Token, TokenNode, TokenValue},
+    },
+    semantic::types::Type,
+    utils::{bit_array::BitArray, interner::Symbol},
+};
+
+use super::Source;
+
+/// semantic token types, indexed by the numbers below
+const TOKEN_TYPES: &[&str] = &[
+    "keyword",
+    "type",
+    "variable",
+    "property",
+    "enumMember",
+    "number",
+    "string",
+    "comment",
+    "operator",
+];
+
+const KEYWORD: u32 = 0;
+const TYPE: u32 = 1;
+const VARIABLE: u32 = 2;
+const PROPERTY: u32 = 3;
+const ENUM_MEMBER: u32 = 4;
+const NUMBER: u32 = 5;
+const STRING: u32 = 6;
+const COMMENT: u32 = 7;
+const OPERATOR: u32 = 8;
+
+/// lsp `SymbolKind`s
+const FIELD: u32 = 8;
+const ENUM: u32 = 10;
+const VARIABLE_SYMBOL: u32 = 13;
+const CONSTANT: u32 = 14;
+const ENUM_MEMBER_SYMBOL: u32 = 22;
+const STRUCT: u32 = 23;
+const TYPE_PARAMETER: u32 = 26;
+
+pub fn initialize() -> Value {
+    json!({
+        "capabilities": {
+            // incremental, every change goes through `Document::apply`
+            "textDocumentSync": 2,
+            "hoverProvider": true,
+            "definitionProvider": true,
+            "documentSymbolProvider": true,
+            "semanticTokensProvider": {
+                "legend": { "tokenTypes": TOKEN_TYPES, "tokenModifiers": [] },
+                "full": true,
+            },
+        },
+        "serverInfo": { "name": "crap", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+pub fn publish_diagnostics(uri: &str, source: Option<&Source>) -> Value {
+    let diagnostics: Vec<Value> = source.map_or(vec![], |source| {
+        source
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                };
+
+                json!({
+                    "range": token_range(source, diagnostic.index),
+                    "severity": severity,
+                    "source": "crap",
+                    "message": diagnostic.message,
+                })
+            })
+            .collect()
+    });
+
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+pub fn hover(source: &Source, offset: usize) -> Value {
+    let Some(node) = token_at(source, offset) else {
+        return Value::Null;
+    };
+
+    let Some(ty) = source.analysis.types.get(&node.index) else {
+        return Value::Null;
+    };
+
+    let name = match (&node.token, node.value) {
+        (Token::Identifier, TokenValue::Symbol(name)) => Some(name),
+        _ => None,
+    };
+
+    let signature = match (ty, name) {
+        (Type::Struct(ty), Some(name)) if *ty == name => format!("struct {}", name),
+        (Type::Enum(ty), Some(name)) if *ty == name => format!("enum {}", name),
+        (Type::Alias(ty, inner), Some(name)) if *ty == name => format!("type {} = {}", name, inner),
+        (_, Some(name)) => format!("{}: {}", name, ty),
+        (_, None) => ty.to_string(),
+    };
+
+    let mut value = format!("```crap\n{}\n```", signature);
+
+    if let Some(docs) = declaration(source, node).and_then(docs) {
+        let docs: Vec<String> = docs
+            .iter()
+            .map(|index| doc_text(source.document.buffer(), *index))
+            .collect();
+        value.push_str("\n\n");
+        value.push_str(&docs.join("\n"));
+    }
+
+    json!({
+        "contents": { "kind": "markdown", "value": value },
+        "range": token_range(source, node.index),
+    })
+}
+
+pub fn definition(uri: &str, source: &Source, offset: usize) -> Value {
+    let Some(node) = token_at(source, offset) else {
+        return Value::Null;
+    };
+
+    match declaration(source, node) {
+        Some(stmt) => {
+            let name = &stmt.expr()[stmt.expr().len() - 1];
+            json!({ "uri": uri, "range": token_range(source, name.index) })
+        }
+        None => Value::Null,
+    }
+}
+
+pub fn document_symbols(source: &Source) -> Value {
+    let stmts = source.document.statements();
+    let mut symbols = vec![];
+
+    for (stmt, tokens) in stmts.iter().zip(source.document.statement_tokens()) {
+        let expr = stmt.expr();
+        let Some(name) = expr.last() else {
+            continue;
+        };
+
+        let (kind, members) = match stmt {
+            Statement::Variable { flags, .. } if StatementFlag::IsConst.has(*flags) => {
+                (CONSTANT, vec![])
+            }
+            Statement::Variable { .. } => (VARIABLE_SYMBOL, vec![]),
+            Statement::Struct { .. } => (STRUCT, members(source, stmt.expressions()[0], FIELD)),
+            Statement::Enum { .. } => {
+                let members = members(source, stmt.expressions()[0], ENUM_MEMBER_SYMBOL);
+                (ENUM, members)
+            }
+            Statement::Alias { .. } => (TYPE_PARAMETER, vec![]),
+            _ => continue,
+        };
+
+        // the statement without the trivia around it
+        let tokens = &source.document.tokens()[tokens];
+        let mut significant = tokens.iter().filter(|node| !is_trivia(node));
+        let start = significant.next().map_or(name.index, |node| node.index);
+        let end = significant.next_back().map_or(start, |node| node.index);
+
+        let buffer = source.document.buffer();
+        let end = end as usize + lexeme(buffer, end, &token(source, end)).len();
+
+        symbols.push(symbol(source, name, kind, members, start as usize, end));
+    }
+
+    Value::Array(symbols)
+}
+
+fn members(source: &Source, expr: ExprRef, kind: u32) -> Vec<Value> {
+    expr.children()
+        .into_iter()
+        .map(|member| {
+            let node = member.node();
+            let (start, end) = token_bounds(source, node.index);
+            symbol(source, node, kind, vec![], start, end)
+        })
+        .collect()
+}
+
+fn symbol(
+    source: &Source,
+    name: &ExpressionNode,
+    kind: u32,
+    members: Vec<Value>,
+    start: usize,
+    end: usize,
+) -> Value {
+    let buffer = source.document.buffer();
+    let mut symbol = json!({
+        "name": name.symbol().as_str(),
+        "kind": kind,
+        "range": source.lines.range(buffer, start, end),
+        "selectionRange": token_range(source, name.index),
+        "children": members,
+    });
+
+    if let Some(ty) = source.analysis.types.get(&name.index) {
+        symbol["detail"] = json!(ty.to_string());
+    }
+
+    symbol
+}
+
+pub fn semantic_tokens(source: &Source) -> Value {
+    let buffer = source.document.buffer();
+    let kinds = identifier_kinds(source);
+
+    let mut data = vec![];
+    let mut previous = (0, 0);
+
+    for node in source.document.tokens() {
+        use Token::*;
+
+        let kind = match node.token {
+            Let | Const | Struct | Enum | Match | Type | Null => KEYWORD,
+            Number => NUMBER,
+            String => STRING,
+            Comment | DocComment => COMMENT,
+            Plus | Minus | Star | Slash | Percent | QuestionQuestion | Question | QuestionDot
+            | Equal | FatArrow => OPERATOR,
+            Identifier => kinds.get(&node.index).copied().unwrap_or(VARIABLE),
+            _ => continue,
+        };
+
+        // clients may not support tokens across lines, split them up
+        let mut start = node.index as usize;
+
+        for line in lexeme(buffer, node.index, &node.token).split_inclusive('\n') {
+            let text = line.trim_end_matches(['\r', '\n']);
+
+            if !text.is_empty() {
+                let (row, column) = source.lines.line_column(buffer, start);
+                let length: usize = text.chars().map(char::len_utf16).sum();
+
+                let delta = match row == previous.0 {
+                    true => column - previous.1,
+                    false => column,
+                };
+
+                data.extend([row - previous.0, delta, length, kind as usize, 0]);
+                previous = (row, column);
+            }
+
+            start += line.len();
+        }
+    }
+
+    json!({ "data": data })
+}
+
+/// semantic token types of identifiers, from where they appear in the syntax tree
+fn identifier_kinds(source: &Source) -> HashMap<u32, u32> {
+    let stmts = source.document.statements();
+
+    let type_names: HashSet<Symbol> = stmts
+        .iter()
+        .filter(|stmt| matches!(stmt, Statement::Struct { .. } | Statement::Enum { .. }))
+        .map(|stmt| stmt.expr()[stmt.expr().len() - 1].symbol())
+        .collect();
+
+    let mut visitor = IdentifierKinds {
+        source,
+        type_names,
+        kinds: HashMap::new(),
+    };
+
+    for stmt in stmts {
+        visitor.visit_stmt(stmt);
+    }
+
+    visitor.kinds
+}
+
+struct IdentifierKinds<'a> {
+    source: &'a Source,
+    type_names: HashSet<Symbol>,
+    kinds: HashMap<u32, u32>,
+}
+
+impl<'a> Visitor<'a> for IdentifierKinds<'_> {
+    fn visit_stmt(&mut self, stmt: &'a Statement) {
+        walk_stmt(self, stmt);
+
+        // every plain name in a type declaration or annotation is a type
+        let types = match stmt {
+            Statement::Struct { .. } | Statement::Enum { .. } | Statement::Alias { .. } => {
+                stmt.expr()
+            }
+            Statement::Variable { flags, .. } if StatementFlag::HasType.has(*flags) => {
+                stmt.expressions()[1].nodes()
+            }
+            _ => &[],
+        };
+
+        for node in types {
+            if node.kind == ExpressionKind::Literal && node.token == Token::Identifier {
+                self.kinds.insert(node.index, TYPE);
+            }
+        }
+    }
+
+    fn visit_expr(&mut self, expr: ExprRef<'a>) {
+        // an operator names nothing, only the operands of a chain are visited and
+        // with a loop, the chain is as deep as it is long
+        if *expr.kind() == ExpressionKind::Binary {
+            let (first, chain) = expr.chain();
+            self.visit_expr(first);
+
+            for expr in chain {
+                self.visit_expr(expr.rhs());
+            }
+
+            return;
+        }
+
+        // children first, a parent knows better what its names are
+        walk_expr(self, expr);
+
+        let node = expr.node();
+        let kind = match node.kind {
+            ExpressionKind::Struct | ExpressionKind::Enum => TYPE,
+            ExpressionKind::Field => PROPERTY,
+            ExpressionKind::Variant => ENUM_MEMBER,
+            _ => VARIABLE,
+        };
+
+        if node.token == Token::Identifier {
+            self.kinds.insert(node.index, kind);
+        }
+
+        if node.kind != ExpressionKind::Member {
+            return;
+        }
+
+        // `Enum.Variant` or `value.field`
+        let lhs = expr.lhs().node();
+        let rhs = expr.rhs().node();
+
+        let is_variant = lhs.kind == ExpressionKind::Literal
+            && lhs.token == Token::Identifier
+            && !self.source.analysis.definitions.contains_key(&lhs.index)
+            && self.type_names.contains(&lhs.symbol());
+
+        match is_variant {
+            true => {
+                self.kinds.insert(lhs.index, TYPE);
+                self.kinds.insert(rhs.index, ENUM_MEMBER);
+            }
+            false => {
+                self.kinds.insert(rhs.index, PROPERTY);
+            }
+        }
+    }
+}
+
+/// the declaration of a binding or type, the name is the last node of its statement
+fn declaration<'a>(source: &'a Source, node: &TokenNode) -> Option<&'a Statement> {
+    let analysis = &source.analysis;
+    let index = analysis
+        .definitions
+        .get(&node.index)
+        .copied()
+        .unwrap_or(node.index);
+
+    let is_type = match (analysis.types.get(&node.index), node.value) {
+        (
+            Some(Type::Struct(ty) | Type::Enum(ty) | Type::Alias(ty, _)),
+            TokenValue::Symbol(name),
+        ) => *ty == name,
+        _ => false,
+    };
+
+    source.document.statements().iter().find(|stmt| {
+        let Some(name) = stmt.expr().last() else {
+            return false;
+        };
+
+        let is_type_stmt = matches!(
+            stmt,
+            Statement::Struct { .. } | Statement::Enum { .. } | Statement::Alias { .. }
+        );
+
+        name.index == index
+            || (is_type && is_type_stmt && TokenValue::Symbol(name.symbol()) == node.value)
+    })
+}
+
+fn docs(stmt: &Statement) -> Option<&[u32]> {
+    match stmt {
+        Statement::Variable { docs, .. }
+        | Statement::Struct { docs, .. }
+        | Statement::Enum { docs, .. }
+        | Statement::Alias { docs, .. } => (!docs.is_empty()).then_some(&docs[..]),
+        _ => None,
+    }
+}
+
+/// the significant token under `offset`, or the one ending right at it
+fn token_at(source: &Source, offset: usize) -> Option<&TokenNode> {
+    let tokens = source.document.tokens();
+    let position = tokens.partition_point(|node| node.index as usize <= offset);
+    let node = tokens.get(position.checked_sub(1)?)?;
+
+    let (_, end) = token_bounds(source, node.index);
+    (!is_trivia(node) && offset <= end).then_some(node)
+}
+
+fn token(source: &Source, index: u32) -> Token {
+    let tokens = source.document.tokens();
+    let position = tokens.partition_point(|node| node.index < index);
+
+    match tokens.get(position) {
+        Some(node) if node.index == index => node.token.clone(),
+        _ => Token::Eof,
+    }
+}
+
+fn token_bounds(source: &Source, index: u32) -> (usize, usize) {
+    let buffer = source.document.buffer();
+    let length = lexeme(buffer, index, &token(source, index)).len();
+
+    (index as usize, index as usize + length)
+}
+
+fn token_range(source: &Source, index: u32) -> Value {
+    let (start, end) = token_bounds(source, index);
+    source.lines.range(source.document.buffer(), start, end)
+}
+
+fn is_trivia(node: &TokenNode) -> bool {
+    matches!(
+        node.token,
+        Token::Eol | Token::Comment | Token::DocComment | Token::Eof
+    )
+}
@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+
+/// converts between byte offsets and lsp positions,
+/// lsp counts lines from 0 and columns in utf-16 code units
+pub struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(buffer: &str) -> LineIndex {
+        let mut starts = vec![0];
+        starts.extend(buffer.match_indices('\n').map(|(i, _)| i + 1));
+
+        LineIndex { starts }
+    }
+
+    /// 0-based line and utf-16 column of `offset`
+    pub fn line_column(&self, buffer: &str, offset: usize) -> (usize, usize) {
+        let offset = offset.min(buffer.len());
+        let line = self.starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.starts[line];
+        let column = buffer[start..offset].chars().map(char::len_utf16).sum();
+
+        (line, column)
+    }
+
+    pub fn position(&self, buffer: &str, offset: usize) -> Value {
+        let (line, character) = self.line_column(buffer, offset);
+        json!({ "line": line, "character": character })
+    }
+
+    pub fn range(&self, buffer: &str, start: usize, end: usize) -> Value {
+        json!({
+            "start": self.position(buffer, start),
+            "end": self.position(buffer, end),
+        })
+    }
+
+    /// byte offset of a `{ line, character }` position, clamped to the buffer
+    pub fn offset(&self, buffer: &str, position: &Value) -> usize {
+        let line = position["line"].as_u64().unwrap_or(0) as usize;
+        let character = position["character"].as_u64().unwrap_or(0) as usize;
+
+        let Some(&start) = self.starts.get(line) else {
+            return buffer.len();
+        };
+
+        let end = self
+            .starts
+            .get(line + 1)
+            .map_or(buffer.len(), |end| end - 1);
+        let mut units = 0;
+
+        for (i, c) in buffer[start..end].char_indices() {
+            if units >= character {
+                return start + i;
+            }
+
+            units += c.len_utf16();
+        }
+
+        end
+    }
+}
@@ -1,29 +1,523 @@
-use std::{fs, io::Result};
+use std::{
+    env, fs,
+    io::{self, Result, Write},
+    process,
+};
 
-use ast::statements::debug_stmt;
-use lexer::tokenize;
-use parser::parse;
-
-mod ast;
-mod lexer;
-mod parser;
-mod utils;
+use crap_lang::{
+    ast::{self, statements::debug_stmt},
+    codegen,
+    diagnostics::{Diagnostic, Severity},
+    formatter::format,
+    interpreter::evaluate,
+    ir::{
+        self,
+        lower::lower,
+        passes::{Pass, PassManager},
+        verifier::verify,
+    },
+    lexer::{
+        self, tokenize,
+        tokens::TokenNode,
+        validation::{validate_identifiers, validate_source, validate_tokens},
+    },
+    lsp,
+    optimizer::{optimize, OptLevel},
+    parser::parse,
+    repl,
+    semantic::{analyze, check},
+};
 
 fn main() -> Result<()> {
-    let buffer = fs::read_to_string("./examples/1.crap")?;
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("lsp") => lsp::serve(io::stdin().lock(), io::stdout().lock()),
+        Some("repl") => {
+            repl::run(io::stdin().lock(), io::stdout().lock(), repl::history_path().as_deref())
+        }
+        Some("fmt") => fmt(&args[1..]),
+        Some(command @ ("ast" | "tokens" | "ir")) => dump(command, &args[1..]),
+        Some("build") => build(&args[1..]),
+        _ => run(&args),
+    }
+}
+
+/// formats the files in place, `--check` only reports the unformatted ones
+fn fmt(args: &[String]) -> Result<()> {
+    let is_check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+
+    if paths.is_empty() {
+        eprintln!("usage: crap fmt [--check] <file>...");
+        process::exit(2);
+    }
+
+    let mut is_clean = true;
+
+    for path in paths {
+        let buffer = fs::read_to_string(path)?;
+
+        match format(&buffer) {
+            Ok(formatted) if formatted == buffer => (),
+            Ok(_) if is_check => {
+                println!("{} is not formatted", path);
+                is_clean = false;
+            }
+            Ok(formatted) => fs::write(path, formatted)?,
+            Err(diagnostics) => {
+                println!("{} cannot be formatted:", path);
+                diagnostics.iter().for_each(|diagnostic| report(&buffer, diagnostic));
+                is_clean = false;
+            }
+        }
+    }
+
+    if !is_clean {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// prints the tokens, the syntax tree or the ir of a file, errors go to stderr
+fn dump(command: &str, args: &[String]) -> Result<()> {
+    let formats: &[&str] = match command {
+        "ast" => &["json", "sexpr", "dot"],
+        "ir" => &["text", "dot"],
+        _ => &["json"],
+    };
+
+    let mut format = formats[0];
+    let mut level = None;
+    let mut is_cfg = false;
+    let mut passes = None;
+    let mut disabled = vec![];
+    let mut path = None;
+    let mut is_valid = true;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => format = args.next().map_or("", String::as_str),
+            arg if arg.starts_with("--format=") => format = &arg["--format=".len()..],
+            "--opt-level" if command != "tokens" => level = args.next().map(String::as_str),
+            arg if arg.starts_with("--opt-level=") && command != "tokens" => {
+                level = Some(&arg["--opt-level=".len()..])
+            }
+            "--cfg" if command == "ast" => is_cfg = true,
+            "--passes" if command == "ir" => passes = Some(args.next().map_or("-", String::as_str)),
+            arg if arg.starts_with("--passes=") && command == "ir" => {
+                passes = Some(&arg["--passes=".len()..])
+            }
+            "--disable-pass" if command == "ir" => {
+                disabled.push(args.next().and_then(|name| Pass::parse(name)))
+            }
+            arg if arg.starts_with("--disable-pass=") && command == "ir" => {
+                disabled.push(Pass::parse(&arg["--disable-pass=".len()..]))
+            }
+            arg if path.is_none() => path = Some(arg),
+            _ => is_valid = false,
+        }
+    }
+
+    let level = match level {
+        Some(level) => OptLevel::parse(level),
+        None => Some(OptLevel::None),
+    };
+
+    // `--cfg` is the control flow graph of the ir
+    let is_valid = is_valid && formats.contains(&format) && (!is_cfg || format == "dot");
+
+    let passes = pass_manager(passes, level, &disabled);
+
+    let (Some(path), Some(level), Some(passes)) = (path.filter(|_| is_valid), level, passes) else {
+        match command {
+            "ast" => eprintln!(
+                "usage: crap ast [--format {}] [--cfg] [--opt-level 0|1|2] <file>",
+                formats.join("|")
+            ),
+            "ir" => eprintln!(
+                "usage: crap ir [--format {}] [--opt-level 0|1|2] [--passes {}] [--disable-pass <pass>] <file>",
+                formats.join("|"),
+                Pass::ALL.map(Pass::name).join(",")
+            ),
+            _ => eprintln!("usage: crap {} [--format {}] <file>", command, formats.join("|")),
+        }
+        process::exit(2);
+    };
+
+    let buffer = fs::read_to_string(path)?;
+
+    // hand-written ir, e.g. to try the passes
+    if command == "ir" && path.ends_with(".ir") {
+        match ir::parser::parse(&buffer) {
+            Ok(program) => print_ir(program, &passes, format),
+            Err(error) => {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            }
+        }
+
+        return Ok(());
+    }
+
+    let diagnostics = validate_source(&buffer);
+    if !diagnostics.is_empty() {
+        diagnostics.iter().for_each(|diagnostic| report_error(&buffer, diagnostic));
+        process::exit(1);
+    }
+
+    let tokens = tokenize(&buffer);
+
+    if command == "tokens" {
+        println!("{:#}", lexer::json::to_json(&buffer, &tokens));
+        return Ok(());
+    }
+
+    let mut diagnostics = validate(&buffer, &tokens);
+
+    // the tree of tokens that did not lex is not worth printing
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        diagnostics.iter().for_each(|diagnostic| report_error(&buffer, diagnostic));
+        process::exit(1);
+    }
+
+    let (mut statements, parser_diagnostics) = parse(tokens);
+    diagnostics.extend(parser_diagnostics);
+
+    if command == "ir" || is_cfg {
+        return dump_ir(&buffer, statements, diagnostics, level, &passes, format);
+    }
+
+    // the optimizer relies on a checked program
+    if level != OptLevel::None {
+        diagnostics.extend(check(&statements));
+
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            diagnostics.iter().for_each(|diagnostic| report_error(&buffer, diagnostic));
+            process::exit(1);
+        }
+
+        statements = optimize(statements, level);
+    }
+
+    match format {
+        "sexpr" => print!("{}", ast::sexpr::to_sexpr(&buffer, &statements)),
+        "dot" => print!("{}", ast::dot::to_dot(&buffer, &statements)),
+        _ => println!("{}", ast::json::to_json(&buffer, &statements)),
+    }
+
+    diagnostics.iter().for_each(|diagnostic| report_error(&buffer, diagnostic));
+
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// the passes run after an optimized lowering, `--passes` picks them and their order
+fn pass_manager(
+    passes: Option<&str>,
+    level: Option<OptLevel>,
+    disabled: &[Option<Pass>],
+) -> Option<PassManager> {
+    let passes = match (passes, level) {
+        (Some(list), _) => PassManager::parse(list).ok(),
+        (None, Some(OptLevel::None)) => Some(PassManager::new(vec![])),
+        (None, _) => Some(PassManager::default()),
+    };
+
+    passes.filter(|_| disabled.iter().all(Option::is_some)).map(|mut passes| {
+        disabled.iter().flatten().for_each(|pass| passes.disable(*pass));
+        passes
+    })
+}
+
+/// lowers a checked program and prints it
+fn dump_ir(
+    buffer: &str,
+    statements: Vec<ast::statements::Statement>,
+    diagnostics: Vec<Diagnostic>,
+    level: OptLevel,
+    passes: &PassManager,
+    format: &str,
+) -> Result<()> {
+    let program = lower_checked(buffer, statements, diagnostics, level);
+    print_ir(program, passes, format);
+    Ok(())
+}
+
+/// checks, optimizes and lowers the statements, exits on errors
+fn lower_checked(
+    buffer: &str,
+    statements: Vec<ast::statements::Statement>,
+    mut diagnostics: Vec<Diagnostic>,
+    level: OptLevel,
+) -> ir::Program {
+    diagnostics.extend(check(&statements));
+    diagnostics.iter().for_each(|diagnostic| report_error(buffer, diagnostic));
+
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        process::exit(1);
+    }
+
+    let statements = optimize(statements, level);
+    let analysis = analyze(&statements);
+
+    match lower(&statements, &analysis) {
+        Ok(program) => program,
+        Err(diagnostic) => {
+            report_error(buffer, &diagnostic);
+            process::exit(1);
+        }
+    }
+}
+
+/// verifies the program, runs the passes and prints it
+fn print_ir(mut program: ir::Program, passes: &PassManager, format: &str) {
+    run_passes(&mut program, passes);
+
+    match format {
+        "dot" => print!("{}", ir::dot::to_dot(&program)),
+        _ => print!("{}", program),
+    }
+}
+
+/// the lowering never fails the verifier, hand-written ir can
+fn run_passes(program: &mut ir::Program, passes: &PassManager) {
+    let errors = verify(program);
+    if !errors.is_empty() {
+        errors.iter().for_each(|error| eprintln!("invalid ir: {}", error));
+        process::exit(1);
+    }
+
+    if let Err(error) = passes.run(program) {
+        eprintln!("invalid ir {}", error);
+        process::exit(1);
+    }
+}
+
+/// compiles a file, or hand-written ir, to the source of a target, an output of
+/// `x86-64` that does not end in `.s` is assembled and linked to an executable
+fn build(args: &[String]) -> Result<()> {
+    const TARGETS: [&str; 4] = ["c", "wat", "wasm", "x86-64"];
+
+    let mut target = None;
+    let mut output = None;
+    let mut level = Some(OptLevel::None);
+    let mut passes = None;
+    let mut disabled = vec![];
+    let mut path = None;
+    let mut is_valid = true;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = args.next().map(String::as_str),
+            arg if arg.starts_with("--target=") => target = Some(&arg["--target=".len()..]),
+            "-o" => output = args.next(),
+            "--opt-level" => level = args.next().and_then(|level| OptLevel::parse(level)),
+            arg if arg.starts_with("--opt-level=") => {
+                level = OptLevel::parse(&arg["--opt-level=".len()..])
+            }
+            "--passes" => passes = Some(args.next().map_or("-", String::as_str)),
+            arg if arg.starts_with("--passes=") => passes = Some(&arg["--passes=".len()..]),
+            "--disable-pass" => disabled.push(args.next().and_then(|name| Pass::parse(name))),
+            arg if arg.starts_with("--disable-pass=") => {
+                disabled.push(Pass::parse(&arg["--disable-pass=".len()..]))
+            }
+            arg if path.is_none() => path = Some(arg),
+            _ => is_valid = false,
+        }
+    }
+
+    let target = target.filter(|target| TARGETS.contains(target));
+    let passes = pass_manager(passes, level, &disabled);
+
+    let (Some(path), Some(target), Some(level), Some(passes)) =
+        (path.filter(|_| is_valid), target, level, passes)
+    else {
+        eprintln!(
+            "usage: crap build --target {} [-o <output>] [--opt-level 0|1|2] [--passes {}] [--disable-pass <pass>] <file>",
+            TARGETS.join("|"),
+            Pass::ALL.map(Pass::name).join(",")
+        );
+        process::exit(2);
+    };
+
+    let buffer = fs::read_to_string(path)?;
+
+    let (mut program, source) = match path.ends_with(".ir") {
+        true => match ir::parser::parse(&buffer) {
+            Ok(program) => (program, None),
+            Err(error) => {
+                eprintln!("error: {}", error);
+                process::exit(1);
+            }
+        },
+        false => {
+            let diagnostics = validate_source(&buffer);
+            if !diagnostics.is_empty() {
+                diagnostics.iter().for_each(|diagnostic| report_error(&buffer, diagnostic));
+                process::exit(1);
+            }
+
+            let tokens = tokenize(&buffer);
+            let mut diagnostics = validate(&buffer, &tokens);
+
+            let (statements, parser_diagnostics) = parse(tokens);
+            diagnostics.extend(parser_diagnostics);
+
+            let program = lower_checked(&buffer, statements, diagnostics, level);
+            (program, Some(buffer.as_str()))
+        }
+    };
+
+    run_passes(&mut program, &passes);
+
+    let code = match target {
+        "c" => Ok(codegen::c::to_c(&program, source).into_bytes()),
+        "wat" => codegen::wasm::to_wat(&program, source).map(String::into_bytes),
+        "x86-64" => Ok(codegen::x86_64::to_asm(&program, source).into_bytes()),
+        _ => codegen::wasm::to_wat(&program, source)
+            .and_then(|wat| wat::parse_str(wat).map_err(|error| error.to_string())),
+    };
+
+    let code = match code {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+
+    match output {
+        Some(output) if target == "x86-64" && !output.ends_with(".s") => link(&code, output),
+        Some(output) => fs::write(output, code),
+        None => io::stdout().write_all(&code),
+    }
+}
+
+/// assembles and links gnu assembler text with the system `as` and `ld`
+fn link(code: &[u8], output: &str) -> Result<()> {
+    let object = format!("{}.o", output);
+
+    let mut assembler =
+        process::Command::new("as").args(["-o", &object]).stdin(process::Stdio::piped()).spawn()?;
+    if let Some(mut stdin) = assembler.stdin.take() {
+        stdin.write_all(code)?;
+    }
+    if !assembler.wait()?.success() {
+        eprintln!("error: as failed");
+        process::exit(1);
+    }
+
+    let status = process::Command::new("ld")
+        .args(["-dynamic-linker", "/lib64/ld-linux-x86-64.so.2", "-o", output, &object])
+        .args(["-lc", "-lm"])
+        .status();
+    fs::remove_file(&object)?;
+
+    if !status?.success() {
+        eprintln!("error: ld failed");
+        process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// checks and runs a file, `--opt-level` also prints the optimized statements
+fn run(args: &[String]) -> Result<()> {
+    let mut level = Some(OptLevel::None);
+    let mut path = None;
+    let mut is_valid = true;
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--opt-level" => level = args.next().and_then(|level| OptLevel::parse(level)),
+            arg if arg.starts_with("--opt-level=") => {
+                level = OptLevel::parse(&arg["--opt-level=".len()..])
+            }
+            arg if path.is_none() => path = Some(arg),
+            _ => is_valid = false,
+        }
+    }
+
+    let Some(level) = level.filter(|_| is_valid) else {
+        eprintln!("usage: crap [--opt-level 0|1|2] [file]");
+        process::exit(2);
+    };
+
+    let buffer = fs::read_to_string(path.unwrap_or("./examples/1.crap"))?;
+
+    let diagnostics = validate_source(&buffer);
+    if !diagnostics.is_empty() {
+        diagnostics.iter().for_each(|diagnostic| report(&buffer, diagnostic));
+        return Ok(());
+    }
 
     let tokens = tokenize(&buffer);
+    let mut diagnostics = validate(&buffer, &tokens);
     // println!("\n--- tokens ---\n");
     // for token in tokens.iter() {
     //     println!("{:?}", &token);
     // }
 
-    let statements = parse(tokens);
+    let (statements, parser_diagnostics) = parse(tokens);
     println!("\n--- statements ---\n");
     for statement in statements.iter() {
-        debug_stmt(statement);
-        print!("\n");
+        debug_stmt(&mut io::stdout(), &buffer, statement)?;
+        println!();
+    }
+
+    diagnostics.extend(parser_diagnostics);
+    diagnostics.extend(check(&statements));
+    if !diagnostics.is_empty() {
+        println!("\n--- diagnostics ---\n");
+    }
+    for diagnostic in diagnostics.iter() {
+        report(&buffer, diagnostic);
+    }
+
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        return Ok(());
+    }
+
+    let statements = optimize(statements, level);
+
+    if level != OptLevel::None {
+        println!("\n--- optimized ---\n");
+        for statement in statements.iter() {
+            debug_stmt(&mut io::stdout(), &buffer, statement)?;
+            println!();
+        }
+    }
+
+    println!("\n--- output ---\n");
+    match evaluate(&statements) {
+        Ok(values) => values.iter().for_each(|value| println!("{}", value)),
+        Err(diagnostic) => report(&buffer, &diagnostic),
     }
 
     Ok(())
 }
+
+/// the errors of the tokens and the warnings about the identifiers
+fn validate(buffer: &str, tokens: &[TokenNode]) -> Vec<Diagnostic> {
+    let mut diagnostics = validate_tokens(buffer, tokens);
+    diagnostics.extend(validate_identifiers(tokens));
+    diagnostics
+}
+
+fn report(buffer: &str, diagnostic: &Diagnostic) {
+    let (line, column) = diagnostic.position(buffer);
+    println!("{}: {} (at {}:{})", diagnostic.severity, diagnostic.message, line, column);
+}
+
+fn report_error(buffer: &str, diagnostic: &Diagnostic) {
+    let (line, column) = diagnostic.position(buffer);
+    eprintln!("{}: {} (at {}:{})", diagnostic.severity, diagnostic.message, line, column);
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use expressions::{fold_expr, Constant};
+
+use crate::{
+    ast::{
+        expressions::{Expression, ExpressionNode, MutExpression},
+        statements::{Statement, StatementFlag},
+        view::ExprRef,
+    },
+    utils::{bit_array::BitArray, interner::Symbol},
+};
+
+mod expressions;
+
+/// how much the optimizer rewrites, the levels include the ones below them
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum OptLevel {
+    // the statements stay as they are
+    None,
+
+    // folds literal subtrees, ternaries with a constant condition and `??`,
+    // removes `(...)`
+    Fold,
+
+    // also applies identities like `x * 1` and propagates `const` bindings
+    Simplify,
+}
+
+impl OptLevel {
+    /// `0`, `1` or `2` of `--opt-level`
+    pub fn parse(level: &str) -> Option<OptLevel> {
+        match level {
+            "0" => Some(OptLevel::None),
+            "1" => Some(OptLevel::Fold),
+            "2" => Some(OptLevel::Simplify),
+            _ => None,
+        }
+    }
+}
+
+struct Optimizer {
+    level: OptLevel,
+
+    // `const` bindings with a constant value, shadowed names are removed
+    constants: HashMap<Symbol, Constant>,
+}
+
+/// rewrites a checked program, its values and errors stay the same
+pub fn optimize(stmts: Vec<Statement>, level: OptLevel) -> Vec<Statement> {
+    if level == OptLevel::None {
+        return stmts;
+    }
+
+    let mut optimizer = Optimizer {
+        level,
+        constants: HashMap::new(),
+    };
+
+    stmts
+        .into_iter()
+        .map(|stmt| optimize_stmt(&mut optimizer, stmt))
+        .collect()
+}
+
+fn optimize_stmt(optimizer: &mut Optimizer, stmt: Statement) -> Statement {
+    use Statement::*;
+
+    // the value is the first subtree of the statements that have one
+    let length = stmt
+        .expressions()
+        .first()
+        .map_or(0, |value| value.nodes().len());
+
+    match stmt {
+        Expression { expr } => Expression {
+            expr: fold_value(optimizer, &expr, length).0,
+        },
+        Variable { expr, flags, docs } => {
+            // [E value][E type]?[E symbol]
+            let name = expr[expr.len() - 1].symbol();
+            let (expr, value) = fold_value(optimizer, &expr, length);
+
+            match (value, StatementFlag::IsConst.has(flags)) {
+                (Some(value), true) => optimizer.constants.insert(name, value),
+                _ => optimizer.constants.remove(&name),
+            };
+
+            Variable { expr, flags, docs }
+        }
+        // [E value][E target]
+        Assignment { expr } => Assignment {
+            expr: fold_value(optimizer, &expr, length).0,
+        },
+        stmt => stmt,
+    }
+}
+
+/// folds the value in front of `nodes[length]`,
+/// the names, types and targets behind it are copied
+fn fold_value(
+    optimizer: &mut Optimizer,
+    nodes: &[ExpressionNode],
+    length: usize,
+) -> (Expression, Option<Constant>) {
+    let mut expr: MutExpression = vec![];
+    let value = fold_expr(optimizer, ExprRef::new(&nodes[..length]), &mut expr);
+
+    expr.extend_from_slice(&nodes[length..]);
+
+    (expr.into(), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{expressions::ExpressionKind, printer::print, statements::Statement},
+        interpreter::evaluate,
+        lexer::tokenize,
+        parser::parse,
+        semantic::check,
+    };
+
+    use super::{optimize, OptLevel};
+
+    /// the statements of a checked `buffer` after optimizing, printed again,
+    /// the values of the program stay the same
+    fn optimized(buffer: &str, level: OptLevel) -> Vec<String> {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert_eq!(check(&stmts), vec![]);
+
+        let values = evaluate(&stmts).unwrap();
+        let stmts = optimize(stmts, level);
+        assert_eq!(evaluate(&stmts).unwrap(), values);
+
+        let has_block = |stmt: &Statement| {
+            stmt.expr()
+                .iter()
+                .any(|node| node.kind == ExpressionKind::Block)
+        };
+        assert!(!stmts.iter().any(has_block), "{:?}", stmts);
+
+        stmts
+            .iter()
+            .map(|stmt| print(buffer, stmt).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn literals_are_folded() {
+        let buffer = "let x = 5\n1 + 2 * 3\nx * (2 - 1) + (x)\n0 ? x : 9\nx ? (4) : 0\n";
+
+        assert_eq!(
+            optimized(buffer, OptLevel::Fold),
+            ["let x = 5", "7", "x * 1 + x", "9", "x ? 4 : 0"]
+        );
+    }
+
+    #[test]
+    fn identities_and_constants() {
+        let buffer = "const k = 2\nlet x = 5\nlet y = 1.5\nk * x + 0\nx * 1\ny + 0.0\nx * 0\n";
+
+        assert_eq!(
+            optimized(buffer, OptLevel::Fold),
+            [
+                "const k = 2",
+                "let x = 5",
+                "let y = 1.5",
+                "k * x + 0",
+                "x * 1",
+                "y + 0.0",
+                "x * 0"
+            ]
+        );
+
+        // `-0.0 + 0.0` is `0.0`, the float identity is left alone
+        assert_eq!(
+            optimized(buffer, OptLevel::Simplify),
+            [
+                "const k = 2",
+                "let x = 5",
+                "let y = 1.5",
+                "2 * x",
+                "x",
+                "y + 0.0",
+                "x * 0"
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowed_constants() {
+        let buffer = "const k = 2\nlet k = 3\nk + 1\nconst n = 4\nmatch 1 { n => n + 1 }\n";
+
+        assert_eq!(
+            optimized(buffer, OptLevel::Simplify),
+            [
+                "const k = 2",
+                "let k = 3",
+                "k + 1",
+                "const n = 4",
+                "match 1 { n => n + 1 }"
+            ]
+        );
+    }
+}
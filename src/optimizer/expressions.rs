@@ -0,0 +1,373 @@
+use crate::{
+    ast::{
+        expressions::{push_root, ExpressionKind, ExpressionNode, MutExpression},
+        view::ExprRef,
+    },
+    lexer::tokens::{Token, TokenNode, TokenValue},
+    utils::interner::Symbol,
+};
+
+use super::{OptLevel, Optimizer};
+
+/// a value that is known before the program runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constant {
+    Int(i64),
+    Float(f64),
+    Null,
+}
+
+impl Constant {
+    fn of(node: &ExpressionNode) -> Option<Constant> {
+        match (&node.token, node.value) {
+            (Token::Number, TokenValue::Int(value)) => Some(Constant::Int(value)),
+            (Token::Number, TokenValue::Float(value)) => Some(Constant::Float(value)),
+            (Token::Null, _) => Some(Constant::Null),
+            _ => None,
+        }
+    }
+
+    /// the same rules as `Value::is_truthy`
+    fn is_truthy(self) -> bool {
+        match self {
+            Constant::Int(value) => value != 0,
+            Constant::Float(value) => value != 0.0,
+            Constant::Null => false,
+        }
+    }
+
+    /// a literal node for the value, at the token `index` it was folded from
+    fn node(self, index: u32) -> ExpressionNode {
+        let (token, value) = match self {
+            Constant::Int(value) => (Token::Number, TokenValue::Int(value)),
+            Constant::Float(value) => (Token::Number, TokenValue::Float(value)),
+            Constant::Null => (Token::Null, TokenValue::None),
+        };
+
+        let node = TokenNode {
+            index,
+            token,
+            value,
+        };
+        ExpressionNode::new(node, 1, ExpressionKind::Literal)
+    }
+}
+
+/// appends the folded subtree to `expr`, returns its value if it is a constant
+pub fn fold_expr(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+
+    match node.kind {
+        Literal => fold_literal_expr(optimizer, node, out),
+
+        // the nesting is already in the tree
+        Block => fold_expr(optimizer, expr.operand(), out),
+        Prefix => fold_prefix_expr(optimizer, expr, out),
+        Binary => fold_binary_expr(optimizer, expr, out),
+        Ternary => fold_ternary_expr(optimizer, expr, out),
+        Member => {
+            // [L][R field]
+            let start = out.len();
+            fold_expr(optimizer, expr.lhs(), out);
+            out.extend_from_slice(expr.rhs().nodes());
+            close(out, start, node);
+            None
+        }
+        Struct => {
+            let start = out.len();
+
+            for field in expr.children() {
+                let field_start = out.len();
+                fold_expr(optimizer, field.operand(), out);
+                close(out, field_start, field.node());
+            }
+
+            close(out, start, node);
+            None
+        }
+        Call => {
+            // [E callee][E argument]..., the callee is an enum path
+            let start = out.len();
+            let operands = expr.children();
+            out.extend_from_slice(operands[0].nodes());
+
+            for argument in &operands[1..] {
+                fold_expr(optimizer, *argument, out);
+            }
+
+            close(out, start, node);
+            None
+        }
+        Match => fold_match_expr(optimizer, expr, out),
+
+        // types, declarations and the parts of their parents
+        Sufix | Field | Enum | Variant | Arm => {
+            out.extend_from_slice(expr.nodes());
+            None
+        }
+    }
+}
+
+/// pushes the root `node` of the nodes from `start` on
+fn close(out: &mut MutExpression, start: usize, node: &ExpressionNode) {
+    let size = out.len() - start + 1;
+    let root = TokenNode {
+        index: node.index,
+        token: node.token.clone(),
+        value: node.value,
+    };
+
+    // the folded subtree is never bigger than the one it was parsed into
+    push_root(out, root, size, node.kind.clone()).unwrap();
+}
+
+fn fold_literal_expr(
+    optimizer: &mut Optimizer,
+    node: &ExpressionNode,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    if node.token == Token::Identifier && optimizer.level >= OptLevel::Simplify {
+        if let Some(constant) = optimizer.constants.get(&node.symbol()) {
+            out.push(constant.node(node.index));
+            return Some(*constant);
+        }
+    }
+
+    out.push(node.clone());
+    Constant::of(node)
+}
+
+fn fold_prefix_expr(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    let node = expr.node();
+    let start = out.len();
+    let operand = fold_expr(optimizer, expr.operand(), out);
+
+    let value = match (&node.token, operand) {
+        (Token::Plus, Some(value @ (Constant::Int(_) | Constant::Float(_)))) => Some(value),
+        (Token::Minus, Some(Constant::Int(value))) => Some(Constant::Int(value.wrapping_neg())),
+        (Token::Minus, Some(Constant::Float(value))) => Some(Constant::Float(-value)),
+        _ => None,
+    };
+
+    match value {
+        Some(value) => {
+            out.truncate(start);
+            out.push(value.node(node.index));
+        }
+        None => close(out, start, node),
+    }
+
+    value
+}
+
+/// the same arithmetic as the interpreter, `None` where it would fail at runtime
+fn evaluate(token: &Token, lhs: Constant, rhs: Constant) -> Option<Constant> {
+    use Constant::*;
+    use Token::*;
+
+    let value = match (token, lhs, rhs) {
+        (Slash | Percent, Int(_), Int(0)) => return None,
+
+        (Plus, Int(lhs), Int(rhs)) => Int(lhs.wrapping_add(rhs)),
+        (Minus, Int(lhs), Int(rhs)) => Int(lhs.wrapping_sub(rhs)),
+        (Star, Int(lhs), Int(rhs)) => Int(lhs.wrapping_mul(rhs)),
+        (Slash, Int(lhs), Int(rhs)) => Int(lhs.wrapping_div(rhs)),
+        (Percent, Int(lhs), Int(rhs)) => Int(lhs.wrapping_rem(rhs)),
+
+        (Plus, Float(lhs), Float(rhs)) => Float(lhs + rhs),
+        (Minus, Float(lhs), Float(rhs)) => Float(lhs - rhs),
+        (Star, Float(lhs), Float(rhs)) => Float(lhs * rhs),
+        (Slash, Float(lhs), Float(rhs)) => Float(lhs / rhs),
+        (Percent, Float(lhs), Float(rhs)) => Float(lhs % rhs),
+
+        _ => return None,
+    };
+
+    // `inf` and `NaN` have no literal
+    match value {
+        Float(value) if !value.is_finite() => None,
+        value => Some(value),
+    }
+}
+
+/// the operand an identity leaves, `x * 1` is `x`
+///
+/// `x + 0.0` is not an identity, `-0.0 + 0.0` is `0.0`
+fn identity(token: &Token, lhs: Option<Constant>, rhs: Option<Constant>) -> Option<Side> {
+    use Constant::*;
+    use Token::*;
+
+    match (token, lhs, rhs) {
+        (Plus | Minus, _, Some(Int(0))) => Some(Side::Lhs),
+        (Minus, _, Some(Float(value))) if value == 0.0 && value.is_sign_positive() => {
+            Some(Side::Lhs)
+        }
+        (Plus, Some(Int(0)), _) => Some(Side::Rhs),
+        (Star | Slash, _, Some(Int(1))) => Some(Side::Lhs),
+        (Star | Slash, _, Some(Float(1.0))) => Some(Side::Lhs),
+        (Star, Some(Int(1)), _) => Some(Side::Rhs),
+        (Star, Some(Float(1.0)), _) => Some(Side::Rhs),
+        _ => None,
+    }
+}
+
+enum Side {
+    Lhs,
+    Rhs,
+}
+
+fn fold_binary_expr(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    let start = out.len();
+    let (first, chain) = expr.chain();
+    let mut value = fold_expr(optimizer, first, out);
+
+    for expr in chain {
+        value = fold_binary_operator(optimizer, expr, value, start, out);
+    }
+
+    value
+}
+
+/// folds a binary operator whose left side is folded into `out[start..]`
+fn fold_binary_operator(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    lhs: Option<Constant>,
+    start: usize,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    let node = expr.node();
+
+    // `??` only needs its right side when the left one is `none`
+    if node.token == Token::QuestionQuestion {
+        match lhs {
+            Some(Constant::Null) => {
+                out.truncate(start);
+                return fold_expr(optimizer, expr.rhs(), out);
+            }
+            Some(value) => return Some(value),
+            None => (),
+        }
+    }
+
+    let middle = out.len();
+    let rhs = fold_expr(optimizer, expr.rhs(), out);
+
+    if let Some(value) = lhs
+        .zip(rhs)
+        .and_then(|(lhs, rhs)| evaluate(&node.token, lhs, rhs))
+    {
+        out.truncate(start);
+        out.push(value.node(node.index));
+        return Some(value);
+    }
+
+    if optimizer.level >= OptLevel::Simplify {
+        match identity(&node.token, lhs, rhs) {
+            Some(Side::Lhs) => {
+                out.truncate(middle);
+                return lhs;
+            }
+            Some(Side::Rhs) => {
+                out.drain(start..middle);
+                return rhs;
+            }
+            None => (),
+        }
+    }
+
+    close(out, start, node);
+    None
+}
+
+fn fold_ternary_expr(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    let node = expr.node();
+
+    // the condition is stored last, [R][M][L]
+    let mut cond = vec![];
+
+    if let Some(value) = fold_expr(optimizer, expr.cond(), &mut cond) {
+        return match value.is_truthy() {
+            true => fold_expr(optimizer, expr.mhs(), out),
+            false => fold_expr(optimizer, expr.rhs(), out),
+        };
+    }
+
+    let start = out.len();
+    fold_expr(optimizer, expr.rhs(), out);
+    fold_expr(optimizer, expr.mhs(), out);
+    out.extend(cond);
+    close(out, start, node);
+
+    None
+}
+
+fn fold_match_expr(
+    optimizer: &mut Optimizer,
+    expr: ExprRef,
+    out: &mut MutExpression,
+) -> Option<Constant> {
+    let start = out.len();
+    let operands = expr.children();
+    let (subject, arms) = operands.split_first().unwrap();
+
+    fold_expr(optimizer, *subject, out);
+
+    for arm in arms {
+        // [L pattern][R value]
+        let arm_start = out.len();
+        out.extend_from_slice(arm.lhs().nodes());
+
+        // the bindings of the pattern shadow constants in the value
+        let mut names = vec![];
+        bindings(arm.lhs(), &mut names);
+
+        let shadowed: Vec<(Symbol, Constant)> = names
+            .into_iter()
+            .filter_map(|name| optimizer.constants.remove(&name).map(|value| (name, value)))
+            .collect();
+
+        fold_expr(optimizer, arm.rhs(), out);
+        optimizer.constants.extend(shadowed);
+
+        close(out, arm_start, arm.node());
+    }
+
+    close(out, start, expr.node());
+    None
+}
+
+/// the names a pattern binds, `_` and enum paths bind nothing
+fn bindings(pattern: ExprRef, names: &mut Vec<Symbol>) {
+    let node = pattern.node();
+
+    match (&node.kind, &node.token) {
+        (ExpressionKind::Literal, Token::Identifier) if node.symbol().as_str() != "_" => {
+            names.push(node.symbol())
+        }
+        (ExpressionKind::Call, _) => {
+            for argument in &pattern.children()[1..] {
+                bindings(*argument, names);
+            }
+        }
+        _ => (),
+    }
+}
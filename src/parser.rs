@@ -1,33 +1,62 @@
+use std::ops::Range;
+
 use statements::parse_stmt;
 
 use crate::{
-    ast::statements::Statement,
-    lexer::tokens::{Token, TokenNode},
+    ast::{
+        expressions::{push_root, ExpressionKind, MutExpression},
+        statements::{Docs, Statement},
+    },
+    diagnostics::Diagnostic,
+    lexer::tokens::{Token, TokenNode, TokenValue},
 };
 
 mod expressions;
-mod precedence;
+pub mod precedence;
 mod statements;
 
-struct Parser {
+/// how deep expressions may nest, every level is a recursion in the checker,
+/// the interpreter, the optimizer and the formatter
+///
+/// only chains of binary operators like `1 + 2 + 3` are not limited,
+/// they are walked with loops
+const MAX_DEPTH: usize = 256;
+
+/// a syntax error aborts the statement, `parse_from` turns it into a diagnostic
+type Result<T> = std::result::Result<T, Diagnostic>;
+
+struct Parser<'a> {
     index: usize,
-    stmts: Vec<Statement>,
-    tokens: Vec<TokenNode>,
+    tokens: &'a [TokenNode],
+
+    // the furthest token the parser has looked at
+    horizon: usize,
+
+    // disables `Name { ... }` literals, e.g. in `match subject { ... }`
+    no_struct_literal: bool,
+
+    // open `(` and `{`, line breaks do not end statements inside of them
+    brackets: usize,
+
+    // the nesting of the expression being parsed, see `MAX_DEPTH`
+    depth: usize,
+
+    diagnostics: Vec<Diagnostic>,
 }
 
-impl Parser {
-    fn new(tokens: Vec<TokenNode>) -> Parser {
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [TokenNode], index: usize) -> Parser<'a> {
         Parser {
-            index: 0,
-            stmts: vec![],
+            index,
             tokens,
+            horizon: index,
+            no_struct_literal: false,
+            brackets: 0,
+            depth: 0,
+            diagnostics: vec![],
         }
     }
 
-    fn push(&mut self, stmt: Statement) {
-        self.stmts.push(stmt);
-    }
-
     fn peek(&mut self) -> TokenNode {
         for i in self.index..self.tokens.len() {
             let node = match self.tokens.get(i) {
@@ -36,35 +65,141 @@ impl Parser {
             };
 
             match node.token {
-                Token::Eol | Token::Comment => continue,
+                Token::Eol | Token::Comment | Token::DocComment | Token::Error => continue,
                 _ => {
                     self.index = i;
+                    self.horizon = self.horizon.max(i);
                     return node.clone()
                 },
             }
         }
 
-        return TokenNode {
+        self.horizon = self.tokens.len();
+
+        TokenNode {
             token: Token::Eof,
-            index: 0,
-        };
+            index: self.tokens.last().map_or(0, |node| node.index),
+            value: TokenValue::None,
+        }
+    }
+
+    /// `true` when a line break separates the peeked token from the previous one
+    fn is_line_start(&self) -> bool {
+        for i in (0..self.index).rev() {
+            match self.tokens[i].token {
+                Token::Eol => return true,
+                Token::Comment | Token::DocComment | Token::Error => continue,
+                _ => return false,
+            }
+        }
+
+        false
+    }
+
+    /// shy semi: a line break ends the statement unless a bracket is still open
+    fn is_statement_end(&self) -> bool {
+        self.brackets == 0 && self.is_line_start()
+    }
+
+    /// doc comments in front of the peeked token
+    fn docs(&self) -> Docs {
+        let mut docs = vec![];
+
+        for node in self.tokens[..self.index].iter().rev() {
+            match node.token {
+                Token::DocComment => docs.push(node.index),
+                Token::Eol | Token::Comment | Token::Error => continue,
+                _ => break,
+            }
+        }
+
+        docs.reverse();
+        docs.into()
     }
 
     fn next(&mut self) -> TokenNode {
         let current = self.peek();
-        self.index += 1;
+
+        // `Eof` is not in the token stream, stay at its end
+        self.index = (self.index + 1).min(self.tokens.len());
+
+        match current.token {
+            Token::OpenParen | Token::OpenBrace => self.brackets += 1,
+            Token::CloseParen | Token::CloseBrace => self.brackets = self.brackets.saturating_sub(1),
+            _ => (),
+        }
 
         current
     }
 
-    fn eat(&mut self, target: Token) -> TokenNode {
+    fn eat(&mut self, target: Token) -> Result<TokenNode> {
         let node = self.next();
 
         if target == node.token {
-            return node;
+            return Ok(node);
         }
 
-        panic!("bad token: {:?}, expected: {:?}", node, target)
+        self.fail(node.index, format!("expected {}, found {}", target, node.token))
+    }
+
+    /// enters one more level of nesting, the caller restores `depth` when it is done
+    fn nest(&mut self, index: u32) -> Result<()> {
+        self.depth += 1;
+
+        if self.depth > MAX_DEPTH {
+            let message = format!("expression is nested more than {} levels deep", MAX_DEPTH);
+            return self.fail(index, message);
+        }
+
+        Ok(())
+    }
+
+    /// pushes the root of a subtree made of all nodes in `expr`
+    fn push_root(
+        &self,
+        expr: &mut MutExpression,
+        node: TokenNode,
+        kind: ExpressionKind,
+    ) -> Result<()> {
+        let index = node.index;
+        let size = expr.len() + 1;
+
+        match push_root(expr, node, size, kind) {
+            Ok(_) => Ok(()),
+            Err(message) => self.fail(index, message),
+        }
+    }
+
+    /// aborts the current statement
+    fn fail<T>(&self, index: u32, message: String) -> Result<T> {
+        Err(Diagnostic::error(index, message))
+    }
+
+    /// skips the rest of a broken statement that started at token `start`, up to
+    /// the next `;`, line break outside of brackets or declaration keyword
+    fn recover(&mut self, start: usize) {
+        use Token::*;
+
+        self.no_struct_literal = false;
+
+        loop {
+            let token = self.peek().token;
+            let has_progress = self.index > start;
+
+            match token {
+                Eof => break,
+                Semi if self.brackets == 0 => {
+                    self.next();
+                    break;
+                }
+                Let | Const | Struct | Enum | Type if has_progress && self.is_line_start() => break,
+                _ if has_progress && self.is_statement_end() => break,
+                _ => self.next(),
+            };
+        }
+
+        self.brackets = 0;
+        self.depth = 0;
     }
 
     fn has_tokens_left(&mut self) -> bool {
@@ -73,13 +208,116 @@ impl Parser {
     }
 }
 
-pub fn parse(tokens: Vec<TokenNode>) -> Vec<Statement> {
-    let mut parser = Parser::new(tokens);
+/// a top level statement with the range of tokens the parser looked at for it
+#[derive(Debug)]
+pub struct ParsedStatement {
+    pub stmt: Statement,
+    pub tokens: Range<usize>,
+    pub diagnostics: Vec<Diagnostic>,
+
+    // the syntax error is at the end of the input, e.g. after `1 +` or `f(`
+    pub is_truncated: bool,
+}
+
+/// parses all statements, the diagnostics are warnings about ambiguous line breaks
+pub fn parse(tokens: Vec<TokenNode>) -> (Vec<Statement>, Vec<Diagnostic>) {
+    let (parsed, _) = parse_from(&tokens, 0, |_| false);
+
+    let mut stmts = vec![];
+    let mut diagnostics = vec![];
 
-    while parser.has_tokens_left() {
-        let stmt = parse_stmt(&mut parser);
-        parser.push(stmt);
+    for statement in parsed {
+        stmts.push(statement.stmt);
+        diagnostics.extend(statement.diagnostics);
     }
 
-    parser.stmts
+    (stmts, diagnostics)
+}
+
+/// parses the statements from token `start` on, `is_synced` is asked for the token
+/// index of every following statement and stops the parser there once it agrees
+pub fn parse_from(
+    tokens: &[TokenNode],
+    start: usize,
+    mut is_synced: impl FnMut(usize) -> bool,
+) -> (Vec<ParsedStatement>, Option<usize>) {
+    let mut parser = Parser::new(tokens, start);
+    let mut parsed = vec![];
+
+    loop {
+        let start = parser.index;
+
+        if is_synced(start) {
+            return (parsed, Some(start));
+        }
+
+        if !parser.has_tokens_left() {
+            return (parsed, None);
+        }
+
+        let first = parser.index;
+        let mut is_truncated = false;
+
+        let stmt = match parse_stmt(&mut parser) {
+            Ok(stmt) => stmt,
+            Err(diagnostic) => {
+                // the parser only looks past the last token when it ran out of input
+                is_truncated = parser.horizon == tokens.len();
+                parser.diagnostics.push(diagnostic);
+                parser.recover(first);
+                Statement::Error
+            }
+        };
+
+        parsed.push(ParsedStatement {
+            stmt,
+            tokens: start..parser.horizon + 1,
+            diagnostics: parser.diagnostics.drain(..).collect(),
+            is_truncated,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::statements::Statement, lexer::tokenize};
+
+    use super::parse;
+
+    /// the number of statements that are not syntax errors and the messages
+    fn statements(buffer: &str) -> (usize, Vec<String>) {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        let count = stmts.iter().filter(|stmt| !matches!(stmt, Statement::Error)).count();
+
+        (count, diagnostics.into_iter().map(|diagnostic| diagnostic.message).collect())
+    }
+
+    #[test]
+    fn line_breaks_end_statements() {
+        assert_eq!(statements("1\n2\n"), (2, vec![]));
+        assert_eq!(statements("1; 2\n"), (2, vec![]));
+
+        // an operator at the end of the line or an open `(` continue the statement
+        assert_eq!(statements("10 + 1 -\n2\n"), (1, vec![]));
+        assert_eq!(statements("(10 + 1\n- 2)\n"), (1, vec![]));
+        assert_eq!(statements("let a =\n1\n"), (1, vec![]));
+    }
+
+    #[test]
+    fn ambiguous_line_starts() {
+        let message = "`-` at the start of a line begins a new statement, \
+                       end the previous line with `;` or an operator to make this explicit";
+        assert_eq!(statements("10 + 1\n- 2 + 1\n"), (2, vec![message.to_string()]));
+        assert_eq!(statements("10 + 1;\n- 2 + 1\n"), (2, vec![]));
+
+        let (count, messages) = statements("10\n* 2\n");
+        assert_eq!(count, 0);
+        assert_eq!(
+            messages[0],
+            "a line cannot start with `*`, move it to the end of the previous line"
+        );
+
+        let message = "expected `;` or a line break, found number";
+        assert_eq!(statements("1 2\n"), (0, vec![message.to_string()]));
+    }
 }
@@ -7,10 +7,10 @@ use crate::{
 
 use super::{
     precedence::{led_power, nud_power, Precedence},
-    Parser,
+    Parser, Result,
 };
 
-pub fn parse_expr(parser: &mut Parser, prev_power: Precedence) -> MutExpression {
+pub fn parse_expr(parser: &mut Parser, prev_power: Precedence) -> Result<MutExpression> {
     use Token::*;
 
     let mut expr: MutExpression = vec![];
@@ -18,15 +18,21 @@ pub fn parse_expr(parser: &mut Parser, prev_power: Precedence) -> MutExpression
     // --- nud --- //
 
     let node = parser.peek();
-    let nud_power = nud_power(&node);
+    let nud_power = nud_power(&node.token);
+
+    let depth = parser.depth;
+    parser.nest(node.index)?;
 
     // nud handler
     match node.token {
-        Number | Identifier => parse_literal_expr(parser, &mut expr),
-        Plus | Minus => parse_prefix_expr(parser, &mut expr, nud_power),
-        OpenParen => parse_block_expr(parser, &mut expr),
+        Number | Identifier | Null => parse_literal_expr(parser, &mut expr)?,
+        Plus | Minus => parse_prefix_expr(parser, &mut expr, nud_power)?,
+        OpenParen => parse_block_expr(parser, &mut expr)?,
+        Match => parse_match_expr(parser, &mut expr)?,
 
-        _ => panic!("nud handler: bad token: {:?}", node),
+        token => {
+            return parser.fail(node.index, format!("expected an expression, found {}", token))
+        }
     };
 
     // --- led --- //
@@ -34,69 +40,154 @@ pub fn parse_expr(parser: &mut Parser, prev_power: Precedence) -> MutExpression
         let node = parser.peek();
         match node.token {
             Eof | Semi => break,
+
+            // a parenthesized expression on a new line is not a call
+            OpenParen if parser.is_line_start() => break,
             _ => (),
         };
 
-        let next_power = led_power(&node);
+        // a line break only continues the expression after an operator
+        if parser.is_statement_end() {
+            break;
+        }
+
+        let next_power = led_power(&node.token);
 
         if next_power <= prev_power {
             break;
         }
 
+        // a binary operator on the left of another one is no deeper for the
+        // consumers of the tree, all other led nodes nest their left side
+        if !matches!(node.token, Plus | Minus | Star | Slash | Percent | QuestionQuestion) {
+            parser.nest(node.index)?;
+        }
+
         // led handler
         match node.token {
-            Plus | Minus => parse_binary_expr(parser, &mut expr, next_power),
-            Star | Slash | Percent => parse_binary_expr(parser, &mut expr, next_power),
-            Question => parse_ternary_expr(parser, &mut expr, next_power),
-
-            _ => panic!("led handler: bad token: {:?}", node),
+            Plus | Minus => parse_binary_expr(parser, &mut expr, next_power)?,
+            Star | Slash | Percent => parse_binary_expr(parser, &mut expr, next_power)?,
+            QuestionQuestion => parse_binary_expr(parser, &mut expr, next_power)?,
+            Question => parse_ternary_expr(parser, &mut expr, next_power)?,
+            Dot | QuestionDot => parse_member_expr(parser, &mut expr)?,
+            OpenParen => parse_call_expr(parser, &mut expr)?,
+
+            token => return parser.fail(node.index, format!("unexpected {}", token)),
         }
     }
 
-    expr
+    parser.depth = depth;
+    Ok(expr)
 }
 
-fn parse_literal_expr(parser: &mut Parser, expr: &mut MutExpression) {
-    let TokenNode { index, token } = parser.next();
-    let literal = ExpressionNode::new(index, 1, token, ExpressionKind::Literal);
+fn parse_literal_expr(parser: &mut Parser, expr: &mut MutExpression) -> Result<()> {
+    let node = parser.next();
+
+    let is_struct = node.token == Token::Identifier && !parser.no_struct_literal;
+
+    if is_struct && parser.peek().token == Token::OpenBrace {
+        return parse_struct_expr(parser, expr, node);
+    }
+
+    let literal = ExpressionNode::new(node, 1, ExpressionKind::Literal);
 
     expr.push(literal);
+    Ok(())
+}
+
+pub fn parse_type_expr(parser: &mut Parser) -> Result<MutExpression> {
+    let name = parser.eat(Token::Identifier)?;
+    let mut expr = vec![ExpressionNode::new(name, 1, ExpressionKind::Literal)];
+    let depth = parser.depth;
+
+    // optional `T?`
+    while let Token::Question = parser.peek().token {
+        let node = parser.next();
+        parser.nest(node.index)?;
+        parser.push_root(&mut expr, node, ExpressionKind::Sufix)?;
+    }
+
+    parser.depth = depth;
+    Ok(expr)
+}
+
+/// parses `{ name: [E], ... }` into a list of `Field` nodes
+pub fn parse_fields(
+    parser: &mut Parser,
+    expr: &mut MutExpression,
+    parse_value: fn(&mut Parser) -> Result<MutExpression>,
+) -> Result<()> {
+    parser.eat(Token::OpenBrace)?;
+
+    while parser.peek().token != Token::CloseBrace {
+        let name = parser.eat(Token::Identifier)?;
+        parser.eat(Token::Colon)?;
+
+        let mut field = parse_value(parser)?;
+        // [E]+[T]
+        parser.push_root(&mut field, name, ExpressionKind::Field)?;
+        expr.extend(field);
+
+        match parser.peek().token {
+            Token::Comma => parser.next(),
+            _ => break,
+        };
+    }
+
+    parser.eat(Token::CloseBrace)?;
+    Ok(())
 }
 
-fn parse_prefix_expr(parser: &mut Parser, expr: &mut MutExpression, power: Precedence) {
-    let TokenNode { index, token } = parser.next();
-    let rhs = parse_expr(parser, power);
+fn parse_struct_expr(parser: &mut Parser, expr: &mut MutExpression, name: TokenNode) -> Result<()> {
+    parse_fields(parser, expr, |parser| parse_expr(parser, Precedence::Default))?;
+
+    parser.push_root(expr, name, ExpressionKind::Struct)
+}
+
+fn parse_prefix_expr(
+    parser: &mut Parser,
+    expr: &mut MutExpression,
+    power: Precedence,
+) -> Result<()> {
+    let node = parser.next();
+    let rhs = parse_expr(parser, power)?;
     expr.extend(rhs);
 
-    let operator = ExpressionNode::new(index, expr.len() + 1, token, ExpressionKind::Prefix);
-    expr.push(operator);
+    parser.push_root(expr, node, ExpressionKind::Prefix)
 }
 
-fn parse_binary_expr(parser: &mut Parser, expr: &mut MutExpression, power: Precedence) {
-    let TokenNode { index, token } = parser.next();
-    let rhs = parse_expr(parser, power);
+fn parse_binary_expr(
+    parser: &mut Parser,
+    expr: &mut MutExpression,
+    power: Precedence,
+) -> Result<()> {
+    let node = parser.next();
+    let rhs = parse_expr(parser, power)?;
 
     expr.extend(rhs);
 
-    let operator = ExpressionNode::new(index, expr.len() + 1, token, ExpressionKind::Binary);
-    expr.push(operator);
+    parser.push_root(expr, node, ExpressionKind::Binary)
 }
 
-fn parse_ternary_expr(parser: &mut Parser, expr: &mut MutExpression, power: Precedence) {
-    let TokenNode { index, token } = parser.next();
+fn parse_ternary_expr(
+    parser: &mut Parser,
+    expr: &mut MutExpression,
+    power: Precedence,
+) -> Result<()> {
+    let node = parser.next();
 
     // [M]
-    let mhs = parse_expr(parser, power.clone());
+    let mhs = parse_expr(parser, power.clone())?;
 
     // eat middle token
-    match token {
-        Token::Question => parser.eat(Token::Colon),
+    match node.token {
+        Token::Question => parser.eat(Token::Colon)?,
 
-        t => panic!("bad token: {:?}", t),
+        token => return parser.fail(node.index, format!("unexpected {}", token)),
     };
 
     // [R]
-    let mut rhs = parse_expr(parser, power.clone());
+    let mut rhs = parse_expr(parser, power.clone())?;
 
     // [R]+[M]
     rhs.extend(mhs);
@@ -107,25 +198,96 @@ fn parse_ternary_expr(parser: &mut Parser, expr: &mut MutExpression, power: Prec
     // [R][M]+[L]
     expr.extend(rhs);
 
-    let operator = ExpressionNode::new(index, expr.len() + 1, token, ExpressionKind::Ternary);
-
     // [R][M][L]+[T]
-    expr.push(operator);
+    parser.push_root(expr, node, ExpressionKind::Ternary)
+}
+
+fn parse_member_expr(parser: &mut Parser, expr: &mut MutExpression) -> Result<()> {
+    let node = parser.next();
+
+    // [R]
+    let field = parser.eat(Token::Identifier)?;
+    expr.push(ExpressionNode::new(field, 1, ExpressionKind::Literal));
+
+    parser.push_root(expr, node, ExpressionKind::Member)
+}
+
+/// parses `( [E], ... )` and appends the arguments to `expr`
+pub fn parse_arguments(
+    parser: &mut Parser,
+    expr: &mut MutExpression,
+    parse_value: fn(&mut Parser) -> Result<MutExpression>,
+) -> Result<()> {
+    parser.eat(Token::OpenParen)?;
+
+    while parser.peek().token != Token::CloseParen {
+        expr.extend(parse_value(parser)?);
+
+        match parser.peek().token {
+            Token::Comma => parser.next(),
+            _ => break,
+        };
+    }
+
+    parser.eat(Token::CloseParen)?;
+    Ok(())
+}
+
+fn parse_call_expr(parser: &mut Parser, expr: &mut MutExpression) -> Result<()> {
+    let node = parser.peek();
+
+    parse_arguments(parser, expr, |parser| parse_expr(parser, Precedence::Default))?;
+
+    parser.push_root(expr, node, ExpressionKind::Call)
 }
 
-fn parse_block_expr(parser: &mut Parser, expr: &mut MutExpression) {
-    let TokenNode { index, token } = parser.next();
+fn parse_match_expr(parser: &mut Parser, expr: &mut MutExpression) -> Result<()> {
+    let node = parser.eat(Token::Match)?;
+
+    // [E subject]
+    let no_struct_literal = mem::replace(&mut parser.no_struct_literal, true);
+    let subject = parse_expr(parser, Precedence::Default);
+    parser.no_struct_literal = no_struct_literal;
+
+    expr.extend(subject?);
+    parser.eat(Token::OpenBrace)?;
+
+    while parser.peek().token != Token::CloseBrace {
+        // [L pattern]
+        let mut arm = parse_expr(parser, Precedence::Default)?;
+        let arrow = parser.eat(Token::FatArrow)?;
+
+        // [L]+[R value]
+        arm.extend(parse_expr(parser, Precedence::Default)?);
+
+        parser.push_root(&mut arm, arrow, ExpressionKind::Arm)?;
+        expr.extend(arm);
+
+        if let Token::Comma = parser.peek().token {
+            parser.next();
+        }
+    }
+
+    parser.eat(Token::CloseBrace)?;
+
+    parser.push_root(expr, node, ExpressionKind::Match)
+}
+
+fn parse_block_expr(parser: &mut Parser, expr: &mut MutExpression) -> Result<()> {
+    let node = parser.next();
+
+    let no_struct_literal = mem::replace(&mut parser.no_struct_literal, false);
     let rhs = parse_expr(parser, Precedence::Default);
+    parser.no_struct_literal = no_struct_literal;
+    let rhs = rhs?;
 
-    match token {
-        Token::OpenParen => parser.eat(Token::CloseParen),
+    match node.token {
+        Token::OpenParen => parser.eat(Token::CloseParen)?,
 
-        t => panic!("bad token: {:?}", t),
+        token => return parser.fail(node.index, format!("unexpected {}", token)),
     };
 
     expr.extend(rhs);
 
-    let operator = ExpressionNode::new(index, expr.len() + 1, token, ExpressionKind::Block);
-
-    expr.push(operator);
+    parser.push_root(expr, node, ExpressionKind::Block)
 }
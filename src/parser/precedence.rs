@@ -1,4 +1,4 @@
-use crate::lexer::tokens::{Token, TokenNode};
+use crate::lexer::tokens::Token;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Precedence {
@@ -7,6 +7,7 @@ pub enum Precedence {
     // Comma,
     // Assignment,
     Ternary,
+    Coalesce,
 
     // Logical_OR,
     // Logical_AND,
@@ -23,16 +24,17 @@ pub enum Precedence {
     Multiplicative,
 
     Unary,
+    Member,
     Primary,
 }
 
-pub fn nud_power(node: &TokenNode) -> Precedence {
+pub fn nud_power(token: &Token) -> Precedence {
     use Precedence::*;
     use Token::*;
 
-    match &node.token {
+    match token {
         // literals
-        Number | Identifier => Primary,
+        Number | Identifier | Null => Primary,
 
         // postfix
         Plus | Minus => Unary,
@@ -42,15 +44,17 @@ pub fn nud_power(node: &TokenNode) -> Precedence {
     }
 }
 
-pub fn led_power(node: &TokenNode) -> Precedence {
+pub fn led_power(token: &Token) -> Precedence {
     use Precedence::*;
     use Token::*;
 
-    match node.token {
+    match token {
         // infix
         Plus | Minus => Additive,
         Star | Slash | Percent => Multiplicative,
         Question | Colon => Ternary,
+        QuestionQuestion => Coalesce,
+        Dot | QuestionDot | OpenParen => Member,
 
         // end of expression
         _ => Default,
@@ -1,51 +1,197 @@
 use crate::{
-    ast::statements::{Statement, StatementFlag},
-    lexer::tokens::Token, utils::bit_array::{BitArray, Byte},
+    ast::{
+        expressions::{ExpressionKind, ExpressionNode, MutExpression},
+        statements::{Docs, Statement, StatementFlag},
+    },
+    diagnostics::Diagnostic,
+    lexer::tokens::{Token, TokenNode},
+    utils::bit_array::{BitArray, Byte},
 };
 
-use super::{expressions::parse_expr, precedence::Precedence, Parser};
+use super::{
+    expressions::{parse_arguments, parse_expr, parse_fields, parse_type_expr},
+    precedence::Precedence,
+    Parser, Result,
+};
 
-pub fn parse_stmt(parser: &mut Parser) -> Statement {
+pub fn parse_stmt(parser: &mut Parser) -> Result<Statement> {
     use Token::*;
 
-    let stmt = match parser.peek().token {
-        Let | Const => parse_variable_stmt(parser),
-        _ => parse_expr_stmt(parser),
+    let token = parser.peek().token;
+    let docs = parser.docs();
+
+    let stmt = match token {
+        Let | Const => parse_variable_stmt(parser, docs)?,
+        Struct => parse_struct_stmt(parser, docs)?,
+        Enum => parse_enum_stmt(parser, docs)?,
+        Type => parse_alias_stmt(parser, docs)?,
+        _ => parse_expr_stmt(parser)?,
     };
 
-    if let Token::Semi = parser.peek().token {
-        parser.next();
+    let node = parser.peek();
+
+    match node.token {
+        Semi => {
+            parser.next();
+        }
+        Eof => (),
+        _ if parser.is_statement_end() => check_line_start(parser, node)?,
+        _ => {
+            let message = format!("expected `;` or a line break, found {}", node.token);
+            return parser.fail(node.index, message);
+        }
     }
 
-    stmt
+    Ok(stmt)
 }
 
-fn parse_variable_stmt(parser: &mut Parser) -> Statement {
+/// the first token of a line that could also have continued the previous one
+fn check_line_start(parser: &mut Parser, node: TokenNode) -> Result<()> {
+    use Token::*;
+
+    match node.token {
+        Plus | Minus | OpenParen => (),
+
+        // these cannot start a statement
+        Star | Slash | Percent | QuestionQuestion | Question | Colon | Dot | QuestionDot
+        | Equal | FatArrow => {
+            let message = format!(
+                "a line cannot start with {}, move it to the end of the previous line",
+                node.token
+            );
+            return parser.fail(node.index, message);
+        }
+        _ => return Ok(()),
+    };
+
+    let message = format!(
+        "{} at the start of a line begins a new statement, \
+         end the previous line with `;` or an operator to make this explicit",
+        node.token
+    );
+
+    parser.diagnostics.push(Diagnostic::warning(node.index, message));
+    Ok(())
+}
+
+fn parse_variable_stmt(parser: &mut Parser, docs: Docs) -> Result<Statement> {
     let node = parser.next();
     let mut flags: Byte = 0;
 
     match node.token {
         Token::Const => flags |= StatementFlag::IsConst.bit(),
         Token::Let => (),
-        _ => panic!("bad token: {:?}", node),
+        token => {
+            return parser.fail(node.index, format!("expected `let` or `const`, found {}", token))
+        }
     };
 
-    let symbol = parse_expr(parser, Precedence::Default);
+    // stops in front of an optional `: type`
+    let symbol = parse_expr(parser, Precedence::Ternary)?;
+
+    let mut ty = vec![];
+    if let Token::Colon = parser.peek().token {
+        parser.next();
+        flags |= StatementFlag::HasType.bit();
+        ty = parse_type_expr(parser)?;
+    }
 
-    parser.eat(Token::Equal);
+    parser.eat(Token::Equal)?;
 
-    let mut value = parse_expr(parser, Precedence::Default);
+    let mut value = parse_expr(parser, Precedence::Default)?;
 
-    // [Value][Symbol]
+    // [Value][Type]?[Symbol]
+    value.extend(ty);
     value.extend(symbol);
 
-    Statement::Variable {
+    Ok(Statement::Variable {
         expr: value.into(),
         flags,
+        docs,
+    })
+}
+
+fn parse_struct_stmt(parser: &mut Parser, docs: Docs) -> Result<Statement> {
+    parser.eat(Token::Struct)?;
+
+    let name = parser.eat(Token::Identifier)?;
+    let mut expr = vec![];
+
+    parse_fields(parser, &mut expr, parse_type_expr)?;
+
+    // [E field]...+[T name]
+    parser.push_root(&mut expr, name, ExpressionKind::Struct)?;
+
+    Ok(Statement::Struct { expr: expr.into(), docs })
+}
+
+fn parse_enum_stmt(parser: &mut Parser, docs: Docs) -> Result<Statement> {
+    parser.eat(Token::Enum)?;
+
+    let name = parser.eat(Token::Identifier)?;
+    let mut expr = vec![];
+
+    parser.eat(Token::OpenBrace)?;
+
+    while parser.peek().token != Token::CloseBrace {
+        let variant = parser.eat(Token::Identifier)?;
+        let mut payload = vec![];
+
+        if let Token::OpenParen = parser.peek().token {
+            parse_arguments(parser, &mut payload, parse_type_expr)?;
+        }
+
+        // [E type]...+[T variant]
+        parser.push_root(&mut payload, variant, ExpressionKind::Variant)?;
+        expr.extend(payload);
+
+        match parser.peek().token {
+            Token::Comma => parser.next(),
+            _ => break,
+        };
+    }
+
+    parser.eat(Token::CloseBrace)?;
+
+    // [E variant]...+[T name]
+    parser.push_root(&mut expr, name, ExpressionKind::Enum)?;
+
+    Ok(Statement::Enum { expr: expr.into(), docs })
+}
+
+fn parse_alias_stmt(parser: &mut Parser, docs: Docs) -> Result<Statement> {
+    parser.eat(Token::Type)?;
+
+    let name = parser.eat(Token::Identifier)?;
+    let symbol = ExpressionNode::new(name, 1, ExpressionKind::Literal);
+
+    parser.eat(Token::Equal)?;
+
+    let mut expr = parse_type_expr(parser)?;
+
+    // [Type]+[Symbol]
+    expr.push(symbol);
+
+    Ok(Statement::Alias { expr: expr.into(), docs })
+}
+
+fn parse_expr_stmt(parser: &mut Parser) -> Result<Statement> {
+    let expr = parse_expr(parser, Precedence::Default)?;
+
+    if parser.peek().token == Token::Equal && !parser.is_line_start() {
+        return parse_assignment_stmt(parser, expr);
     }
+
+    Ok(Statement::Expression { expr: expr.into() })
 }
 
-fn parse_expr_stmt(parser: &mut Parser) -> Statement {
-    let expr = parse_expr(parser, Precedence::Default);
-    Statement::Expression { expr: expr.into() }
+fn parse_assignment_stmt(parser: &mut Parser, target: MutExpression) -> Result<Statement> {
+    parser.eat(Token::Equal)?;
+
+    let mut value = parse_expr(parser, Precedence::Default)?;
+
+    // [Value][Target]
+    value.extend(target);
+
+    Ok(Statement::Assignment { expr: value.into() })
 }
@@ -0,0 +1,308 @@
+use std::{
+    env, fs,
+    fs::OpenOptions,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ast::statements::debug_stmt,
+    diagnostics::{Diagnostic, Severity},
+    interpreter::Interpreter,
+    lexer::{
+        tokenize,
+        validation::{validate_identifiers, validate_source, validate_tokens},
+    },
+    parser::parse_from,
+    semantic::Checker,
+};
+
+const HELP: &str = "\
+:tokens <code>  prints the tokens of the code
+:ast <code>     prints the syntax tree of the code
+:history        prints the lines entered so far, in this session and before
+:help           prints this message
+:quit           leaves the repl, like ctrl-d
+
+an entry continues on the next line after an open bracket or a trailing operator,
+an empty line runs it as it is";
+
+/// the bindings and types declared so far
+struct Session {
+    checker: Checker,
+    interpreter: Interpreter,
+}
+
+/// `crap/history` under `$XDG_DATA_HOME`, or under `~/.local/share` without it
+pub fn history_path() -> Option<PathBuf> {
+    let data = match env::var_os("XDG_DATA_HOME") {
+        Some(data) if !data.is_empty() => PathBuf::from(data),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+
+    Some(data.join("crap").join("history"))
+}
+
+/// reads entries from `input` until it ends, checks and runs them one by one,
+/// the lines of earlier sessions are read from `history` and the new ones are
+/// appended to it
+pub fn run(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    history: Option<&Path>,
+) -> io::Result<()> {
+    let mut session = Session {
+        checker: Checker::new(),
+        interpreter: Interpreter::new(),
+    };
+
+    // the repl works without a history
+    let mut lines: Vec<String> = match history.and_then(|path| fs::read_to_string(path).ok()) {
+        Some(text) => text.lines().map(String::from).collect(),
+        None => vec![],
+    };
+    let mut history = history.and_then(|path| {
+        fs::create_dir_all(path.parent()?).ok()?;
+        OpenOptions::new().create(true).append(true).open(path).ok()
+    });
+
+    let mut entry = String::new();
+
+    loop {
+        let prompt = match entry.is_empty() {
+            true => "> ",
+            false => ". ",
+        };
+        write!(output, "{}", prompt)?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            break;
+        }
+
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        if !line.trim().is_empty() && line.trim() != ":history" {
+            if let Some(history) = history.as_mut() {
+                _ = writeln!(history, "{}", line);
+            }
+            lines.push(line.to_string());
+        }
+
+        if entry.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ":quit" | ":q" => break,
+                ":help" => {
+                    writeln!(output, "{}", HELP)?;
+                    continue;
+                }
+                ":history" => {
+                    for line in lines.iter() {
+                        writeln!(output, "{}", line)?;
+                    }
+                    continue;
+                }
+                command if command.starts_with(':') => {
+                    meta(&mut output, command)?;
+                    continue;
+                }
+                _ => (),
+            }
+        }
+
+        let is_forced = line.trim().is_empty();
+
+        if !entry.is_empty() {
+            entry.push('\n');
+        }
+        entry.push_str(line);
+
+        if !is_forced && is_truncated(&entry) {
+            continue;
+        }
+
+        session.evaluate(&mut output, &entry)?;
+        entry.clear();
+    }
+
+    Ok(())
+}
+
+/// `true` when the entry ends in the middle of a statement
+fn is_truncated(entry: &str) -> bool {
+    let tokens = tokenize(entry);
+    let (parsed, _) = parse_from(&tokens, 0, |_| false);
+
+    parsed.iter().any(|statement| statement.is_truncated)
+}
+
+impl Session {
+    fn evaluate(&mut self, output: &mut impl Write, entry: &str) -> io::Result<()> {
+        let diagnostics = validate_source(entry);
+        if !diagnostics.is_empty() {
+            return report(output, entry, &diagnostics);
+        }
+
+        let tokens = tokenize(entry);
+        let mut diagnostics = validate_tokens(entry, &tokens);
+        diagnostics.extend(validate_identifiers(&tokens));
+
+        let (parsed, _) = parse_from(&tokens, 0, |_| false);
+        let mut stmts = vec![];
+
+        for statement in parsed {
+            diagnostics.extend(statement.diagnostics);
+            stmts.push(statement.stmt);
+        }
+
+        // a broken entry must not leave its bindings or their types behind,
+        // neither for the checker nor for the interpreter
+        let checker = self.checker.clone();
+        let interpreter = self.interpreter.clone();
+
+        for stmt in stmts.iter() {
+            diagnostics.extend(self.checker.check(stmt));
+        }
+
+        report(output, entry, &diagnostics)?;
+
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            self.checker = checker;
+            return Ok(());
+        }
+
+        for stmt in stmts.iter() {
+            match self.interpreter.execute(stmt) {
+                Ok(Some(value)) => writeln!(output, "{}", value)?,
+                Ok(None) => (),
+                Err(diagnostic) => {
+                    self.checker = checker;
+                    self.interpreter = interpreter;
+                    return report(output, entry, &[diagnostic]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `:tokens` and `:ast`, they only print and do not declare anything
+fn meta(output: &mut impl Write, command: &str) -> io::Result<()> {
+    let (name, code) = command.split_once(' ').unwrap_or((command, ""));
+
+    match name {
+        ":tokens" => {
+            for token in tokenize(code).iter() {
+                writeln!(output, "{:?}", token)?;
+            }
+        }
+        ":ast" => {
+            let tokens = tokenize(code);
+            let (parsed, _) = parse_from(&tokens, 0, |_| false);
+
+            for statement in parsed {
+                debug_stmt(output, code, &statement.stmt)?;
+                report(output, code, &statement.diagnostics)?;
+            }
+        }
+        name => writeln!(output, "unknown command `{}`, see `:help`", name)?,
+    }
+
+    Ok(())
+}
+
+fn report(output: &mut impl Write, entry: &str, diagnostics: &[Diagnostic]) -> io::Result<()> {
+    for diagnostic in diagnostics {
+        let (line, column) = diagnostic.position(entry);
+        writeln!(
+            output,
+            "{}: {} (at {}:{})",
+            diagnostic.severity, diagnostic.message, line, column
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{meta, run, Checker, Interpreter, Session};
+
+    /// evaluates the entries one after another and returns what each printed
+    fn session(entries: &[&str]) -> Vec<String> {
+        let mut session = Session {
+            checker: Checker::new(),
+            interpreter: Interpreter::new(),
+        };
+
+        entries
+            .iter()
+            .map(|entry| {
+                let mut output = vec![];
+                session.evaluate(&mut output, entry).unwrap();
+                String::from_utf8(output).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn errors_roll_back_the_whole_entry() {
+        let outputs = session(&[
+            "let a = 1.5",
+            // the checker accepts it, the interpreter does not
+            "let a = 1 / 0",
+            "a + 0.5",
+            "let q = 5; let w = q / 0",
+            "q",
+            "let b: i64 = 1.5",
+            "b",
+        ]);
+
+        assert_eq!(outputs[1], "error: division by zero (at 1:11)\n");
+        assert_eq!(outputs[2], "2.0\n");
+        assert_eq!(outputs[3], "error: division by zero (at 1:22)\n");
+        assert_eq!(outputs[4], "error: unknown binding `q` (at 1:1)\n");
+        assert!(outputs[5].starts_with("error: mismatched types"));
+        assert_eq!(outputs[6], "error: unknown binding `b` (at 1:1)\n");
+    }
+
+    #[test]
+    fn commands_write_to_the_output() {
+        let mut output = vec![];
+        meta(&mut output, ":tokens 1 + x").unwrap();
+        meta(&mut output, ":ast (1 + 2) * x").unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        // three tokens, the printed source, the kind of statement and six nodes
+        assert_eq!(lines.len(), 3 + 2 + 6);
+        assert!(lines[0].starts_with("TokenNode { index: 0, token: Number"));
+        assert_eq!(lines[3], "(1 + 2) * x");
+        assert_eq!(lines[4], "Expression Statement");
+    }
+
+    #[test]
+    fn history_is_kept_between_sessions() {
+        let directory = env::temp_dir().join(format!("crap-repl-{}", process::id()));
+        let path = directory.join("crap").join("history");
+        _ = fs::remove_dir_all(&directory);
+
+        let mut output = vec![];
+        run("let a = 1\n\n:help\n".as_bytes(), &mut output, Some(&path)).unwrap();
+
+        let mut output = vec![];
+        run(":history\n".as_bytes(), &mut output, Some(&path)).unwrap();
+        _ = fs::remove_dir_all(&directory);
+
+        // blank lines and `:history` itself are left out
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, "> let a = 1\n:help\n> \n");
+    }
+}
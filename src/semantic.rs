@@ -0,0 +1,239 @@
+use std::{collections::HashMap, mem};
+
+use statements::check_stmt;
+use types::Type;
+
+use crate::{ast::statements::Statement, diagnostics::Diagnostic, utils::interner::Symbol};
+
+mod expressions;
+mod statements;
+pub mod types;
+
+#[derive(Clone)]
+struct Binding {
+    ty: Type,
+    is_const: bool,
+
+    // token index of the declaration
+    index: u32,
+}
+
+/// everything the checker found out about a program
+#[derive(Clone, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+
+    // token index of an expression or declaration -> its type
+    pub types: HashMap<u32, Type>,
+
+    // token index of a binding use -> token index of its declaration
+    pub definitions: HashMap<u32, u32>,
+}
+
+/// checks statements one after another, e.g. the lines of the repl
+#[derive(Clone)]
+pub struct Checker {
+    scopes: Vec<HashMap<Symbol, Binding>>,
+    structs: HashMap<Symbol, Vec<(Symbol, Type)>>,
+    enums: HashMap<Symbol, Vec<(Symbol, Vec<Type>)>>,
+    aliases: HashMap<Symbol, Type>,
+    analysis: Analysis,
+}
+
+impl Checker {
+    pub fn new() -> Checker {
+        Checker {
+            scopes: vec![HashMap::new()],
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            aliases: HashMap::new(),
+            analysis: Analysis::default(),
+        }
+    }
+
+    /// checks a statement in the scope of the ones before, returns its diagnostics
+    pub fn check(&mut self, stmt: &Statement) -> Vec<Diagnostic> {
+        check_stmt(self, stmt);
+        mem::take(&mut self.analysis.diagnostics)
+    }
+
+    fn error(&mut self, index: u32, message: String) {
+        self.analysis.diagnostics.push(Diagnostic::error(index, message));
+    }
+
+    fn warning(&mut self, index: u32, message: String) {
+        self.analysis.diagnostics.push(Diagnostic::warning(index, message));
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(&name))
+    }
+
+    fn declare(&mut self, name: Symbol, binding: Binding) {
+        self.scopes.last_mut().unwrap().insert(name, binding);
+    }
+
+    fn record(&mut self, index: u32, ty: &Type) {
+        self.analysis.types.insert(index, ty.clone());
+    }
+
+    fn is_type_name(&self, name: Symbol) -> bool {
+        matches!(name.as_str(), "i64" | "f64")
+            || self.structs.contains_key(&name)
+            || self.enums.contains_key(&name)
+            || self.aliases.contains_key(&name)
+    }
+}
+
+impl Default for Checker {
+    fn default() -> Checker {
+        Checker::new()
+    }
+}
+
+pub fn check(stmts: &[Statement]) -> Vec<Diagnostic> {
+    analyze(stmts).diagnostics
+}
+
+/// type checks the program and keeps the inferred types and bindings for tooling
+pub fn analyze(stmts: &[Statement]) -> Analysis {
+    let mut checker = Checker::new();
+
+    for stmt in stmts {
+        check_stmt(&mut checker, stmt);
+    }
+
+    checker.analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{lexer::tokenize, parser::parse};
+
+    use super::check;
+
+    /// the messages of the checker for `buffer`, which has to parse
+    fn messages(buffer: &str) -> Vec<String> {
+        let (stmts, diagnostics) = parse(tokenize(buffer));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        check(&stmts).into_iter().map(|diagnostic| diagnostic.message).collect()
+    }
+
+    const POINT: &str = "struct Point { x: i64, y: i64 }\n";
+
+    #[test]
+    fn struct_literals_and_fields() {
+        let buffer = format!("{}let p = Point {{ x: 1, y: 2 }}\np.x = p.y + 1\np.x\n", POINT);
+        assert_eq!(messages(&buffer), Vec::<String>::new());
+
+        let buffer = format!("{}let p = Point {{ x: 1, z: 2, x: 3 }}\n", POINT);
+        assert_eq!(
+            messages(&buffer),
+            [
+                "unknown field `z` on `Point`",
+                "field `x` is specified more than once",
+                "missing field `y` in `Point`",
+            ]
+        );
+
+        let buffer = format!("{}let p = Point {{ x: 1.5, y: 2 }}\np.z\n", POINT);
+        assert_eq!(
+            messages(&buffer),
+            [
+                "mismatched types: field `x` expects `i64`, found `f64`",
+                "unknown field `z` on `Point`",
+            ]
+        );
+
+        assert_eq!(messages("let n = 1\nn.x\n"), ["no field `x` on type `i64`"]);
+        assert_eq!(messages("Line { a: 1 }\n"), ["unknown struct `Line`"]);
+    }
+
+    const SHAPE: &str = "enum Shape { Circle(f64), Rect(f64, f64), Empty }\n";
+
+    #[test]
+    fn variants_and_matches() {
+        let arms = "Shape.Circle(r) => r, Shape.Rect(w, h) => w * h, Shape.Empty => 0.0";
+        let buffer = format!("{}let s = Shape.Rect(2.0, 3.5)\nmatch s {{ {} }}\n", SHAPE, arms);
+        assert_eq!(messages(&buffer), Vec::<String>::new());
+
+        let buffer = format!("{}match Shape.Circle(1.5) {{ Shape.Circle(r) => r }}\n", SHAPE);
+        let missing = "non-exhaustive match: `Shape.Rect`, `Shape.Empty` not covered";
+        assert_eq!(messages(&buffer), [missing]);
+
+        assert_eq!(messages("match 1 { 3 => 30 }\n"), ["non-exhaustive match: add a `_` arm"]);
+
+        let buffer = format!("{}Shape.Rect(1.0)\nShape.Circle\nShape.Square\n", SHAPE);
+        assert_eq!(
+            messages(&buffer),
+            [
+                "variant of `Shape` expects 2 value(s), found 1",
+                "variant of `Shape` expects 1 value(s)",
+                "unknown variant `Square` on `Shape`",
+            ]
+        );
+
+        let buffer = format!("{}match Shape.Empty {{ Shape.Rect(w, w) => w, _ => 0.0 }}\n", SHAPE);
+        assert_eq!(messages(&buffer), ["`w` is bound more than once in the same pattern"]);
+    }
+
+    #[test]
+    fn aliases() {
+        let buffer = "type Meters = f64\nlet d: Meters = 1.5\nlet e: f64 = d + 1.0\n";
+        assert_eq!(messages(buffer), Vec::<String>::new());
+
+        // the alias keeps its name in the messages
+        let buffer = "type Meters = f64\nlet d: Meters = 1\nlet n: i64 = d\n";
+        assert_eq!(
+            messages(buffer),
+            [
+                "mismatched types: expected `Meters`, found `i64`",
+                "mismatched types: expected `i64`, found `Meters`",
+            ]
+        );
+
+        let buffer = "type Meters = f64\ntype Meters = i64\ntype Speed = Knots\n";
+        assert_eq!(messages(buffer), ["type `Meters` is already declared", "unknown type `Knots`"]);
+    }
+
+    #[test]
+    fn optionals() {
+        let buffer = format!("{}let p: Point? = none\nlet x: i64 = p?.x ?? 0\nx + 1\n", POINT);
+        assert_eq!(messages(&buffer), Vec::<String>::new());
+
+        // the value has to be unwrapped before it is used
+        let buffer = format!("{}let p: Point? = none\np.x\nlet n: i64? = 1\nn + 1\n", POINT);
+        assert_eq!(
+            messages(&buffer),
+            [
+                "`Point?` may be none, use `?.` to access `x`",
+                "`i64?` may be none, unwrap it with `??` or `match` first",
+            ]
+        );
+
+        let buffer = format!("{}let p = Point {{ x: 1, y: 2 }}\np?.x\np.x ?? 3\n", POINT);
+        assert_eq!(
+            messages(&buffer),
+            [
+                "`Point` is never none, use `.` instead of `?.`",
+                "`i64` is never none, the right side of `??` is unused",
+            ]
+        );
+    }
+
+    #[test]
+    fn none_needs_a_type() {
+        assert_eq!(
+            messages("let a = none\na + 1\n"),
+            ["cannot infer type of `none`, add an annotation"]
+        );
+        assert_eq!(messages("let a: i64? = none\nlet b = a\n"), Vec::<String>::new());
+
+        // the placeholder of the type of `none` is not printed
+        assert_eq!(
+            messages("let a: i64 = none\n"),
+            ["mismatched types: expected `i64`, found `none`"]
+        );
+    }
+}
@@ -0,0 +1,603 @@
+use crate::{
+    ast::{
+        expressions::{ExpressionKind, ExpressionNode},
+        view::ExprRef,
+    },
+    lexer::tokens::{Token, TokenValue},
+    utils::interner::Symbol,
+};
+
+use super::{types::Type, Binding, Checker};
+
+/// resolves a type expression, e.g. `i64` or `Point`
+pub fn resolve_type(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+
+    if node.kind == ExpressionKind::Sufix {
+        let ty = Type::optional(resolve_type(checker, expr.operand()));
+        checker.record(node.index, &ty);
+        return ty;
+    }
+
+    let name = node.symbol();
+
+    let ty = match name.as_str() {
+        "i64" => Type::Int,
+        "f64" => Type::Float,
+        _ if checker.structs.contains_key(&name) => Type::Struct(name),
+        _ if checker.enums.contains_key(&name) => Type::Enum(name),
+        _ if checker.aliases.contains_key(&name) => {
+            let ty = checker.aliases[&name].clone();
+            Type::Alias(name, Box::new(ty))
+        }
+        _ => {
+            checker.error(node.index, format!("unknown type `{}`", name));
+            Type::Unknown
+        }
+    };
+
+    checker.record(node.index, &ty);
+    ty
+}
+
+/// type checks a subtree (root node last) and returns its type
+pub fn check_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+
+    let ty = match node.kind {
+        Literal => check_literal_expr(checker, node),
+        Prefix | Sufix => check_unary_expr(checker, expr),
+        Block => check_expr(checker, expr.operand()),
+        Binary => check_binary_expr(checker, expr),
+        Ternary => check_ternary_expr(checker, expr),
+        Member => check_member_expr(checker, expr),
+        Struct => check_struct_expr(checker, expr),
+        Call => check_call_expr(checker, expr),
+        Match => check_match_expr(checker, expr),
+
+        // only reachable through their parent nodes
+        Field => check_expr(checker, expr.operand()),
+        Arm => check_expr(checker, expr.lhs()),
+        Enum | Variant => Type::Unknown,
+    };
+
+    checker.record(node.index, &ty);
+    ty
+}
+
+fn check_literal_expr(checker: &mut Checker, node: &ExpressionNode) -> Type {
+    match (&node.token, node.value) {
+        (Token::Number, TokenValue::Float(_)) => Type::Float,
+        (Token::Number, TokenValue::Int(_)) => Type::Int,
+        (Token::Number, _) => {
+            checker.error(node.index, "integer literal is too large".to_string());
+            Type::Int
+        }
+        (Token::Null, _) => Type::optional(Type::Unknown),
+        (Token::Identifier, TokenValue::Symbol(name)) => match checker.lookup(name) {
+            Some(binding) => {
+                let ty = binding.ty.clone();
+                let index = binding.index;
+
+                checker.analysis.definitions.insert(node.index, index);
+                ty
+            }
+            None => {
+                checker.error(node.index, format!("unknown binding `{}`", name));
+                Type::Unknown
+            }
+        },
+        (token, _) => {
+            checker.error(node.index, format!("unexpected literal `{:?}`", token));
+            Type::Unknown
+        }
+    }
+}
+
+fn expect_numeric(checker: &mut Checker, node: &ExpressionNode, ty: Type) -> Type {
+    if ty.is_numeric() {
+        return ty;
+    }
+
+    let message = match ty.is_optional() {
+        true => format!("`{}` may be none, unwrap it with `??` or `match` first", ty),
+        false => format!("expected a number, found `{}`", ty),
+    };
+
+    checker.error(node.index, message);
+    Type::Unknown
+}
+
+fn check_unary_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+    let ty = check_expr(checker, expr.operand());
+
+    expect_numeric(checker, node, ty)
+}
+
+fn check_binary_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let (first, chain) = expr.chain();
+    let mut ty = check_expr(checker, first);
+
+    for (i, expr) in chain.iter().enumerate() {
+        ty = check_binary_operator(checker, *expr, ty);
+
+        // the root is recorded by `check_expr`
+        if i + 1 < chain.len() {
+            checker.record(expr.index(), &ty);
+        }
+    }
+
+    ty
+}
+
+/// checks the right side of a binary operator whose left side has the type `lhs`
+fn check_binary_operator(checker: &mut Checker, expr: ExprRef, lhs: Type) -> Type {
+    let node = expr.node();
+
+    if node.token == Token::QuestionQuestion {
+        return check_coalesce_expr(checker, node, lhs, expr.rhs());
+    }
+
+    let lhs = expect_numeric(checker, node, lhs);
+    let rhs = check_expr(checker, expr.rhs());
+    let rhs = expect_numeric(checker, node, rhs);
+
+    unify(checker, node, lhs, rhs)
+}
+
+fn check_ternary_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+
+    let condition = check_expr(checker, expr.cond());
+    expect_numeric(checker, node, condition);
+
+    let then = check_expr(checker, expr.mhs());
+    let otherwise = check_expr(checker, expr.rhs());
+
+    unify(checker, node, then, otherwise)
+}
+
+fn check_coalesce_expr(
+    checker: &mut Checker,
+    node: &ExpressionNode,
+    lhs: Type,
+    rhs: ExprRef,
+) -> Type {
+    let rhs = check_expr(checker, rhs);
+
+    match lhs.resolved() {
+        Type::Optional(ty) => unify(checker, node, *ty.clone(), rhs),
+        Type::Unknown => rhs,
+        _ => {
+            let message = format!("`{}` is never none, the right side of `??` is unused", lhs);
+            checker.warning(node.index, message);
+            unify(checker, node, lhs, rhs)
+        }
+    }
+}
+
+fn unify(checker: &mut Checker, node: &ExpressionNode, lhs: Type, rhs: Type) -> Type {
+    if lhs.is_unknown() {
+        return rhs;
+    }
+
+    if rhs.is_unknown() || lhs == rhs {
+        return lhs;
+    }
+
+    // `T` and `T?` unify into `T?`
+    match (lhs.resolved().clone(), rhs.resolved().clone()) {
+        (Type::Optional(lhs), Type::Optional(rhs)) => {
+            Type::optional(unify(checker, node, *lhs, *rhs))
+        }
+        (Type::Optional(lhs), _) => Type::optional(unify(checker, node, *lhs, rhs)),
+        (_, Type::Optional(rhs)) => Type::optional(unify(checker, node, lhs, *rhs)),
+        _ => {
+            let message = format!("mismatched types: `{}` and `{}`", lhs, rhs);
+            checker.error(node.index, message);
+            Type::Unknown
+        }
+    }
+}
+
+fn check_member_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    if let Some((name, payload)) = resolve_variant(checker, expr) {
+        let arity = payload.map_or(0, |payload| payload.len());
+
+        if arity > 0 {
+            let node = expr.node();
+            let message = format!("variant of `{}` expects {} value(s)", name, arity);
+            checker.error(node.index, message);
+        }
+
+        return Type::Enum(name);
+    }
+
+    let node = expr.node();
+    let field = expr.rhs().node();
+    let field_name = field.symbol();
+
+    let mut ty = check_expr(checker, expr.lhs());
+    let is_safe = node.token == Token::QuestionDot;
+
+    match (is_safe, ty.resolved().clone()) {
+        (true, Type::Optional(inner)) => ty = *inner,
+        (true, _) if !ty.is_unknown() => {
+            let message = format!("`{}` is never none, use `.` instead of `?.`", ty);
+            checker.warning(node.index, message);
+        }
+        (false, Type::Optional(_)) => {
+            let message = format!("`{}` may be none, use `?.` to access `{}`", ty, field_name);
+            checker.error(node.index, message);
+            return Type::Unknown;
+        }
+        _ => (),
+    }
+
+    let name = match ty.resolved() {
+        Type::Struct(name) => *name,
+        Type::Unknown => return Type::Unknown,
+        _ => {
+            let message = format!("no field `{}` on type `{}`", field_name, ty);
+            checker.error(field.index, message);
+            return Type::Unknown;
+        }
+    };
+
+    let found = checker.structs[&name]
+        .iter()
+        .find(|(name, _)| *name == field_name)
+        .map(|(_, ty)| ty.clone());
+
+    if let Some(ty) = &found {
+        checker.record(field.index, ty);
+    }
+
+    match found {
+        Some(ty) if is_safe => Type::optional(ty),
+        Some(ty) => ty,
+        None => {
+            let message = format!("unknown field `{}` on `{}`", field_name, name);
+            checker.error(field.index, message);
+            Type::Unknown
+        }
+    }
+}
+
+fn check_struct_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+    let name = node.symbol();
+
+    let declared = match checker.structs.get(&name) {
+        Some(fields) => fields.clone(),
+        None => {
+            checker.error(node.index, format!("unknown struct `{}`", name));
+
+            for field in expr.children() {
+                check_expr(checker, field);
+            }
+
+            return Type::Unknown;
+        }
+    };
+
+    let mut seen: Vec<Symbol> = vec![];
+
+    for field in expr.children() {
+        let field_node = field.node();
+        let field_name = field_node.symbol();
+        let ty = check_expr(checker, field);
+
+        if seen.contains(&field_name) {
+            let message = format!("field `{}` is specified more than once", field_name);
+            checker.error(field_node.index, message);
+            continue;
+        }
+
+        seen.push(field_name);
+
+        match declared.iter().find(|(name, _)| *name == field_name) {
+            Some((_, expected)) if !expected.accepts(&ty) => {
+                let message = format!(
+                    "mismatched types: field `{}` expects `{}`, found `{}`",
+                    field_name, expected, ty
+                );
+                checker.error(field_node.index, message);
+            }
+            Some(_) => (),
+            None => {
+                let message = format!("unknown field `{}` on `{}`", field_name, name);
+                checker.error(field_node.index, message);
+            }
+        }
+    }
+
+    for (field_name, _) in declared.iter() {
+        if !seen.contains(field_name) {
+            let message = format!("missing field `{}` in `{}`", field_name, name);
+            checker.error(node.index, message);
+        }
+    }
+
+    Type::Struct(name)
+}
+
+/// resolves `Enum.Variant` to the enum name and the payload types of the variant,
+/// `None` if `expr` does not name an enum, no payload if the variant is unknown
+fn resolve_variant(
+    checker: &mut Checker,
+    expr: ExprRef,
+) -> Option<(Symbol, Option<Vec<Type>>)> {
+    let node = expr.node();
+    if node.kind != ExpressionKind::Member || node.token != Token::Dot {
+        return None;
+    }
+
+    let lhs = expr.lhs().node();
+    if lhs.kind != ExpressionKind::Literal || lhs.token != Token::Identifier {
+        return None;
+    }
+
+    let name = lhs.symbol();
+    if checker.lookup(name).is_some() || !checker.enums.contains_key(&name) {
+        return None;
+    }
+
+    let variant = expr.rhs().node();
+    let variant_name = variant.symbol();
+
+    let payload = checker.enums[&name]
+        .iter()
+        .find(|(name, _)| *name == variant_name)
+        .map(|(_, payload)| payload.clone());
+
+    if payload.is_none() {
+        let message = format!("unknown variant `{}` on `{}`", variant_name, name);
+        checker.error(variant.index, message);
+    }
+
+    Some((name, payload))
+}
+
+fn check_call_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+    let operands = expr.children();
+    let (callee, arguments) = operands.split_first().unwrap();
+
+    let (name, payload) = match resolve_variant(checker, *callee) {
+        Some((name, Some(payload))) => (name, payload),
+        Some((_, None)) => {
+            for argument in arguments {
+                check_expr(checker, *argument);
+            }
+
+            return Type::Unknown;
+        }
+        None => {
+            let ty = check_expr(checker, *callee);
+
+            if !ty.is_unknown() {
+                checker.error(node.index, format!("`{}` is not callable", ty));
+            }
+
+            return Type::Unknown;
+        }
+    };
+
+    if payload.len() != arguments.len() {
+        let message = format!(
+            "variant of `{}` expects {} value(s), found {}",
+            name,
+            payload.len(),
+            arguments.len()
+        );
+        checker.error(node.index, message);
+    }
+
+    for (argument, expected) in arguments.iter().zip(payload.iter()) {
+        let ty = check_expr(checker, *argument);
+
+        if !expected.accepts(&ty) {
+            let index = argument.index();
+            let message = format!("mismatched types: expected `{}`, found `{}`", expected, ty);
+            checker.error(index, message);
+        }
+    }
+
+    Type::Enum(name)
+}
+
+/// what a single match arm pattern covers
+enum Coverage {
+    All,
+    Variant(Symbol),
+    Null,
+    Partial,
+}
+
+fn check_pattern(
+    checker: &mut Checker,
+    expr: ExprRef,
+    expected: &Type,
+    bindings: &mut Vec<(Symbol, Binding)>,
+) -> Coverage {
+    use ExpressionKind::*;
+
+    let node = expr.node();
+
+    match (&node.kind, &node.token) {
+        (Literal, Token::Identifier) => {
+            let name = node.symbol();
+
+            if name.as_str() == "_" {
+                return Coverage::All;
+            }
+
+            if bindings.iter().any(|(binding, _)| *binding == name) {
+                let message = format!("`{}` is bound more than once in the same pattern", name);
+                checker.error(node.index, message);
+            }
+
+            checker.record(node.index, expected);
+
+            let binding = Binding {
+                ty: expected.clone(),
+                is_const: true,
+                index: node.index,
+            };
+
+            bindings.push((name, binding));
+            Coverage::All
+        }
+        (Literal, Token::Number) => {
+            let ty = check_literal_expr(checker, node);
+
+            if !expected.accepts(&ty) {
+                let message = format!("mismatched types: expected `{}`, found `{}`", expected, ty);
+                checker.error(node.index, message);
+            }
+
+            Coverage::Partial
+        }
+        (Literal, Token::Null) => {
+            if !expected.is_optional() && !expected.is_unknown() {
+                let message = format!("mismatched types: expected `{}`, found `none`", expected);
+                checker.error(node.index, message);
+            }
+
+            Coverage::Null
+        }
+        (Member, _) | (Call, _) => {
+            let (path, arguments) = match node.kind {
+                Call => {
+                    let operands = expr.children();
+                    let (callee, arguments) = operands.split_first().unwrap();
+                    (*callee, arguments.to_vec())
+                }
+                _ => (expr, vec![]),
+            };
+
+            let (name, payload) = match resolve_variant(checker, path) {
+                Some((name, Some(payload))) => (name, payload),
+                Some((_, None)) => return Coverage::Partial,
+                None => {
+                    checker.error(node.index, "invalid pattern".to_string());
+                    return Coverage::Partial;
+                }
+            };
+
+            let ty = Type::Enum(name);
+            if !expected.accepts(&ty) {
+                let message = format!("mismatched types: expected `{}`, found `{}`", expected, ty);
+                checker.error(node.index, message);
+            }
+
+            if payload.len() != arguments.len() {
+                let message = format!(
+                    "variant of `{}` expects {} value(s), found {}",
+                    name,
+                    payload.len(),
+                    arguments.len()
+                );
+                checker.error(node.index, message);
+            }
+
+            let mut coverage = Coverage::All;
+
+            for (argument, expected) in arguments.iter().zip(payload.iter()) {
+                if let Coverage::Variant(_) | Coverage::Null | Coverage::Partial =
+                    check_pattern(checker, *argument, expected, bindings)
+                {
+                    coverage = Coverage::Partial;
+                }
+            }
+
+            let variant = path.rhs();
+
+            match coverage {
+                Coverage::All => Coverage::Variant(variant.symbol()),
+                _ => Coverage::Partial,
+            }
+        }
+        _ => {
+            checker.error(node.index, "invalid pattern".to_string());
+            Coverage::Partial
+        }
+    }
+}
+
+fn check_match_expr(checker: &mut Checker, expr: ExprRef) -> Type {
+    let node = expr.node();
+    let operands = expr.children();
+    let (subject, arms) = operands.split_first().unwrap();
+
+    let subject_ty = check_expr(checker, *subject);
+
+    // narrowed to `T` once a `none` arm handled the empty case of `T?`
+    let mut expected = subject_ty.clone();
+
+    let mut ty = Type::Unknown;
+    let mut exhaustive = false;
+    let mut covered: Vec<Symbol> = vec![];
+    let mut has_none = false;
+
+    for arm in arms {
+        let arm_node = arm.node();
+
+        if exhaustive {
+            checker.warning(arm_node.index, "unreachable match arm".to_string());
+        }
+
+        let mut bindings = vec![];
+        match check_pattern(checker, arm.lhs(), &expected, &mut bindings) {
+            Coverage::All => exhaustive = true,
+            Coverage::Variant(variant) => covered.push(variant),
+            Coverage::Null => has_none = true,
+            Coverage::Partial => (),
+        }
+
+        if let (true, Type::Optional(inner)) = (has_none, expected.resolved()) {
+            expected = *inner.clone();
+        }
+
+        checker.scopes.push(Default::default());
+
+        for (name, binding) in bindings {
+            checker.declare(name, binding);
+        }
+
+        let value = check_expr(checker, arm.rhs());
+        checker.scopes.pop();
+
+        ty = unify(checker, arm_node, ty, value);
+    }
+
+    if exhaustive {
+        return ty;
+    }
+
+    match subject_ty.resolved() {
+        Type::Enum(name) => {
+            let missing: Vec<String> = checker.enums[name]
+                .iter()
+                .filter(|(variant, _)| !covered.contains(variant))
+                .map(|(variant, _)| format!("`{}.{}`", name, variant))
+                .collect();
+
+            if !missing.is_empty() {
+                let message = format!("non-exhaustive match: {} not covered", missing.join(", "));
+                checker.error(node.index, message);
+            }
+        }
+        Type::Unknown => (),
+        _ => {
+            let message = "non-exhaustive match: add a `_` arm".to_string();
+            checker.error(node.index, message);
+        }
+    }
+
+    ty
+}
@@ -0,0 +1,187 @@
+use crate::{
+    ast::{
+        expressions::ExpressionKind,
+        statements::{Statement, StatementFlag},
+        view::ExprRef,
+    },
+    lexer::tokens::Token,
+    utils::{
+        bit_array::{BitArray, Byte},
+        interner::Symbol,
+    },
+};
+
+use super::{
+    expressions::{check_expr, resolve_type},
+    types::Type,
+    Binding, Checker,
+};
+
+pub fn check_stmt(checker: &mut Checker, stmt: &Statement) {
+    use Statement::*;
+
+    let parts = stmt.expressions();
+
+    match stmt {
+        Expression { .. } => {
+            check_expr(checker, parts[0]);
+        }
+        Variable { flags, .. } => check_variable_stmt(checker, &parts, *flags),
+        Assignment { .. } => check_assignment_stmt(checker, parts[0], parts[1]),
+        Struct { .. } => check_struct_stmt(checker, parts[0]),
+        Enum { .. } => check_enum_stmt(checker, parts[0]),
+        Alias { .. } => check_alias_stmt(checker, parts[0], parts[1]),
+
+        // already reported by the parser
+        Error => (),
+    }
+}
+
+/// `[E value][E type]?[E symbol]`
+fn check_variable_stmt(checker: &mut Checker, parts: &[ExprRef], flags: Byte) {
+    let value = parts[0];
+    let mut ty;
+
+    if StatementFlag::HasType.has(flags) {
+        let expected = resolve_type(checker, parts[1]);
+
+        ty = check_expr(checker, value);
+
+        if !expected.accepts(&ty) {
+            let index = value.index();
+            let message = format!("mismatched types: expected `{}`, found `{}`", expected, ty);
+            checker.error(index, message);
+        }
+
+        ty = expected;
+    } else {
+        let reported = checker.analysis.diagnostics.len();
+        ty = check_expr(checker, value);
+
+        // `none` alone does not say what it is the absence of
+        let is_none = matches!(ty.resolved(), Type::Optional(inner) if inner.is_unknown());
+        if is_none && checker.analysis.diagnostics.len() == reported {
+            let message = "cannot infer type of `none`, add an annotation".to_string();
+            checker.error(value.index(), message);
+            ty = Type::Unknown;
+        }
+    }
+
+    let node = parts[parts.len() - 1].node();
+    if node.kind != ExpressionKind::Literal || node.token != Token::Identifier {
+        return checker.error(node.index, "expected a binding name".to_string());
+    }
+
+    let name = node.symbol();
+    let is_const = StatementFlag::IsConst.has(flags);
+
+    checker.record(node.index, &ty);
+    checker.declare(name, Binding { ty, is_const, index: node.index });
+}
+
+fn check_assignment_stmt(checker: &mut Checker, value: ExprRef, target: ExprRef) {
+    let value_ty = check_expr(checker, value);
+
+    // walk down `a.b.c` to the root binding `a`
+    let mut root = target;
+    while *root.kind() == ExpressionKind::Member {
+        root = root.lhs();
+    }
+
+    let node = root.node();
+    if node.kind != ExpressionKind::Literal || node.token != Token::Identifier {
+        let index = target.index();
+        return checker.error(index, "invalid assignment target".to_string());
+    }
+
+    let name = node.symbol();
+    if let Some(Binding { is_const: true, .. }) = checker.lookup(name) {
+        let message = format!("cannot assign to `{}`, it is declared as const", name);
+        checker.error(node.index, message);
+    }
+
+    let target_ty = check_expr(checker, target);
+    if !target_ty.accepts(&value_ty) {
+        let index = value.index();
+        let message = format!("mismatched types: expected `{}`, found `{}`", target_ty, value_ty);
+        checker.error(index, message);
+    }
+}
+
+fn check_struct_stmt(checker: &mut Checker, expr: ExprRef) {
+    let node = expr.node();
+    let name = node.symbol();
+
+    if checker.is_type_name(name) {
+        let message = format!("type `{}` is already declared", name);
+        return checker.error(node.index, message);
+    }
+
+    let mut fields: Vec<(Symbol, Type)> = vec![];
+
+    for field in expr.children() {
+        let field_node = field.node();
+        let field_name = field_node.symbol();
+
+        if fields.iter().any(|(name, _)| *name == field_name) {
+            let message = format!("field `{}` is already declared", field_name);
+            checker.error(field_node.index, message);
+            continue;
+        }
+
+        let ty = resolve_type(checker, field.operand());
+        checker.record(field_node.index, &ty);
+        fields.push((field_name, ty));
+    }
+
+    checker.record(node.index, &Type::Struct(name));
+    checker.structs.insert(name, fields);
+}
+
+fn check_enum_stmt(checker: &mut Checker, expr: ExprRef) {
+    let node = expr.node();
+    let name = node.symbol();
+
+    if checker.is_type_name(name) {
+        let message = format!("type `{}` is already declared", name);
+        return checker.error(node.index, message);
+    }
+
+    let mut variants: Vec<(Symbol, Vec<Type>)> = vec![];
+
+    for variant in expr.children() {
+        let variant_node = variant.node();
+        let variant_name = variant_node.symbol();
+
+        if variants.iter().any(|(name, _)| *name == variant_name) {
+            let message = format!("variant `{}` is already declared", variant_name);
+            checker.error(variant_node.index, message);
+            continue;
+        }
+
+        let payload = variant
+            .children()
+            .into_iter()
+            .map(|ty| resolve_type(checker, ty))
+            .collect();
+
+        variants.push((variant_name, payload));
+    }
+
+    checker.record(node.index, &Type::Enum(name));
+    checker.enums.insert(name, variants);
+}
+
+fn check_alias_stmt(checker: &mut Checker, ty: ExprRef, symbol: ExprRef) {
+    let node = symbol.node();
+    let name = node.symbol();
+
+    if checker.is_type_name(name) {
+        let message = format!("type `{}` is already declared", name);
+        return checker.error(node.index, message);
+    }
+
+    let ty = resolve_type(checker, ty);
+    checker.record(node.index, &Type::Alias(name, Box::new(ty.clone())));
+    checker.aliases.insert(name, ty);
+}
@@ -0,0 +1,86 @@
+use core::fmt;
+
+use crate::utils::interner::Symbol;
+
+#[derive(Debug, Clone)]
+pub enum Type {
+    Int,
+    Float,
+    Struct(Symbol),
+    Enum(Symbol),
+    Optional(Box<Type>),
+
+    // `type Name = Type`, transparent except for its name in diagnostics
+    Alias(Symbol, Box<Type>),
+
+    // already reported, suppresses follow-up errors
+    Unknown,
+}
+
+impl Type {
+    /// `T?`, optionals do not nest
+    pub fn optional(ty: Type) -> Type {
+        match ty.resolved() {
+            Type::Optional(_) => ty,
+            _ => Type::Optional(Box::new(ty)),
+        }
+    }
+
+    /// strips all aliases
+    pub fn resolved(&self) -> &Type {
+        match self {
+            Type::Alias(_, ty) => ty.resolved(),
+            ty => ty,
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self.resolved(), Type::Int | Type::Float | Type::Unknown)
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        matches!(self.resolved(), Type::Unknown)
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self.resolved(), Type::Optional(_))
+    }
+
+    /// `true` when a value of `other` can be used where `self` is expected,
+    /// `T` is implicitly wrapped into `T?`
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self.resolved(), other.resolved()) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Optional(lhs), Type::Optional(rhs)) => lhs.accepts(rhs),
+            (Type::Optional(lhs), _) => lhs.accepts(other),
+            _ => self == other,
+        }
+    }
+}
+
+impl PartialEq for Type {
+    fn eq(&self, other: &Type) -> bool {
+        use Type::*;
+
+        match (self.resolved(), other.resolved()) {
+            (Int, Int) | (Float, Float) | (Unknown, Unknown) => true,
+            (Struct(lhs), Struct(rhs)) | (Enum(lhs), Enum(rhs)) => lhs == rhs,
+            (Optional(lhs), Optional(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "i64"),
+            Type::Float => write!(f, "f64"),
+            Type::Struct(name) | Type::Enum(name) | Type::Alias(name, _) => write!(f, "{}", name),
+            // the type of a bare `none`
+            Type::Optional(ty) if ty.is_unknown() => write!(f, "none"),
+            Type::Optional(ty) => write!(f, "{}?", ty),
+            Type::Unknown => write!(f, "{{unknown}}"),
+        }
+    }
+}
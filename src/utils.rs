@@ -1 +1,2 @@
-pub mod bit_array;
\ No newline at end of file
+pub mod bit_array;
+pub mod interner;
@@ -2,7 +2,7 @@ pub type Byte = u8;
 pub type Bit = u8;
 
 pub const BIT_1: Bit = 0b_0000_0001;
-// pub const BIT_2: Bit = 0b_0000_0010;
+pub const BIT_2: Bit = 0b_0000_0010;
 // pub const BIT_3: Bit = 0b_0000_0100;
 // pub const BIT_4: Bit = 0b_0000_1000;
 // pub const BIT_5: Bit = 0b_0001_0000;
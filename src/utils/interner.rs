@@ -0,0 +1,157 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    hash::{BuildHasherDefault, Hasher},
+    sync::{Mutex, OnceLock},
+};
+
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// an interned identifier or keyword, two symbols are equal if their names are
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// FxHash, names are short and hashing them is on the hot path of the lexer
+#[derive(Default)]
+struct NameHasher(u64);
+
+impl Hasher for NameHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0.rotate_left(5) ^ *byte as u64).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// the map from names to symbols, only interning locks it
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<&'static str, Symbol, BuildHasherDefault<NameHasher>>,
+    count: u32,
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+/// the names by symbol, in chunks of 64, 128, 256... slots that never move once
+/// allocated, a slot is set before its symbol is handed out so reading it needs
+/// no lock
+const CHUNKS: usize = 32;
+const CHUNK: usize = 64;
+
+type Chunk = Box<[OnceLock<&'static str>]>;
+
+static NAMES: [OnceLock<Chunk>; CHUNKS] = [const { OnceLock::new() }; CHUNKS];
+
+/// the chunk of the `index`th name and its slot in there
+fn slot(index: u32) -> (usize, usize) {
+    let index = index as usize;
+    let chunk = (index / CHUNK + 1).ilog2() as usize;
+
+    (chunk, index - CHUNK * ((1 << chunk) - 1))
+}
+
+impl Symbol {
+    /// interns the NFC normalized form of `name`
+    ///
+    /// the names are leaked and live as long as the process, the interner is
+    /// global so that symbols can be compared and printed without a context,
+    /// a program only ever has as many names as its distinct identifiers
+    pub fn intern(name: &str) -> Symbol {
+        let mut interner = interner().lock().unwrap();
+
+        if let Some(symbol) = interner.symbols.get(name) {
+            return *symbol;
+        }
+
+        let name: Cow<str> = match is_nfc(name) {
+            true => Cow::Borrowed(name),
+            false => Cow::Owned(name.nfc().collect()),
+        };
+
+        if let Some(symbol) = interner.symbols.get(&*name) {
+            return *symbol;
+        }
+
+        let name: &'static str = Box::leak(name.into_owned().into_boxed_str());
+        let symbol = Symbol(interner.count);
+        interner.count = interner.count.checked_add(1).unwrap();
+
+        let (chunk, slot) = slot(symbol.0);
+        let names =
+            NAMES[chunk].get_or_init(|| (0..CHUNK << chunk).map(|_| OnceLock::new()).collect());
+        _ = names[slot].set(name);
+        interner.symbols.insert(name, symbol);
+
+        symbol
+    }
+
+    /// the name of the symbol, without locking the interner
+    pub fn as_str(self) -> &'static str {
+        let (chunk, slot) = slot(self.0);
+        // symbols only come from `intern`, which sets the slot first
+        NAMES[chunk].get().and_then(|chunk| chunk[slot].get()).unwrap()
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::{slot, Symbol};
+
+    #[test]
+    fn chunks() {
+        assert_eq!(slot(0), (0, 0));
+        assert_eq!(slot(63), (0, 63));
+        assert_eq!(slot(64), (1, 0));
+        assert_eq!(slot(191), (1, 127));
+        assert_eq!(slot(192), (2, 0));
+        assert_eq!(slot(u32::MAX).0, 26);
+    }
+
+    #[test]
+    fn names_across_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|thread| {
+                thread::spawn(move || {
+                    let names: Vec<String> =
+                        (0..500).map(|i| format!("name_{}", i * 4 + thread)).collect();
+                    let symbols: Vec<Symbol> =
+                        names.iter().map(|name| Symbol::intern(name)).collect();
+                    (names, symbols)
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            let (names, symbols) = thread.join().unwrap();
+            for (name, symbol) in names.iter().zip(symbols) {
+                assert_eq!(symbol.as_str(), name);
+                assert_eq!(Symbol::intern(name), symbol);
+            }
+        }
+
+        // the same name in NFD and NFC
+        assert_eq!(Symbol::intern("e\u{301}"), Symbol::intern("\u{e9}"));
+    }
+}
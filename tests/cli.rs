@@ -0,0 +1,60 @@
+use std::{env, fs, process::Command};
+
+// --- command line --- //
+// runs the `crap` binary on a source written to a temporary file and
+// returns whether it succeeded, what it printed and what it reported
+
+fn crap(args: &[&str], name: &str, source: &str) -> (bool, String, String) {
+    let path = env::temp_dir().join(format!("crap-cli-{}-{}.crap", std::process::id(), name));
+    fs::write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crap")).args(args).arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    (output.status.success(), stdout, stderr)
+}
+
+#[test]
+fn build_reports_lexer_errors() {
+    let (is_success, stdout, stderr) =
+        crap(&["build", "--target", "c"], "build_character", "let x = 1 @+ 2\nx\n");
+    assert!(!is_success);
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, "error: unexpected character `@` (at 1:11)\n");
+
+    let (is_success, stdout, stderr) =
+        crap(&["build", "--target", "c"], "build_string", "let s = \"abc\nx\n");
+    assert!(!is_success);
+    assert!(stdout.is_empty());
+    assert!(stderr.starts_with("error: unterminated string (at 1:9)\n"), "{}", stderr);
+}
+
+#[test]
+fn ir_reports_lexer_errors() {
+    let (is_success, stdout, stderr) = crap(&["ir"], "ir_character", "let x = 1 @+ 2\nx\n");
+    assert!(!is_success);
+    assert!(stdout.is_empty());
+    assert_eq!(stderr, "error: unexpected character `@` (at 1:11)\n");
+
+    let (is_success, stdout, _) = crap(&["ir"], "ir_comment", "let x = 1\nx /* open\n");
+    assert!(!is_success);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn ast_reports_lexer_errors() {
+    for format in ["json", "sexpr", "dot"] {
+        let (is_success, stdout, stderr) =
+            crap(&["ast", "--format", format], "ast_character", "let x = 1 @+ 2\n");
+        assert!(!is_success);
+        assert!(stdout.is_empty(), "{}", stdout);
+        assert_eq!(stderr, "error: unexpected character `@` (at 1:11)\n");
+    }
+
+    // the tokens of such a file can still be dumped
+    let (is_success, stdout, _) = crap(&["tokens"], "tokens_character", "let x = 1 @+ 2\n");
+    assert!(is_success);
+    assert!(stdout.contains("\"Error\""), "{}", stdout);
+}
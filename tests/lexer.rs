@@ -0,0 +1,106 @@
+use std::{env, fmt::Write, fs, path::Path};
+
+use crap_lang::{
+    diagnostics::Diagnostic,
+    lexer::{lexeme, tokenize, validation::validate_tokens},
+};
+
+// --- golden tokens --- //
+// every `examples/*.crap` and `tests/lexer/*.crap` has its tokens in
+// `tests/lexer/<name>.tokens`, one `start end token text` per line, followed by
+// the errors of the tokens as `start severity message`
+//
+// the files were checked against the regex lexer the hand-written one replaced,
+// `BLESS=1 cargo test --test lexer` rewrites them after an intended change
+
+/// the tokens of `buffer` and their errors in the format of the golden files
+fn render(buffer: &str) -> String {
+    let mut output = String::new();
+    let tokens = tokenize(buffer);
+
+    for node in tokens.iter() {
+        let text = lexeme(buffer, node.index, &node.token);
+        let end = node.index as usize + text.len();
+        _ = writeln!(output, "{} {} {:?} {:?}", node.index, end, node.token, text);
+    }
+
+    for diagnostic in validate_tokens(buffer, &tokens) {
+        let Diagnostic { index, severity, message } = diagnostic;
+        _ = writeln!(output, "{} {} {:?}", index, severity, message);
+    }
+
+    output
+}
+
+fn check(name: &str, buffer: &str) -> Option<String> {
+    let golden = Path::new("tests/lexer").join(format!("{}.tokens", name));
+    let actual = render(buffer);
+
+    if env::var_os("BLESS").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return None;
+    }
+
+    match fs::read_to_string(&golden) {
+        Ok(expected) if expected == actual => None,
+        Ok(_) => Some(format!("{}: the tokens differ from {}", name, golden.display())),
+        Err(_) => Some(format!("{}: {} is missing", name, golden.display())),
+    }
+}
+
+/// the `.crap` files of a directory by their name
+fn inputs(directory: &str) -> Vec<(String, String)> {
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "crap"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn examples() {
+    let failures: Vec<String> = inputs("examples")
+        .iter()
+        .filter_map(|(name, buffer)| check(&format!("example_{}", name), buffer))
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn edge_cases() {
+    let failures: Vec<String> = inputs("tests/lexer")
+        .iter()
+        .filter_map(|(name, buffer)| check(name, buffer))
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn unterminated_openers() {
+    let messages = |buffer: &str| -> Vec<(u32, String)> {
+        validate_tokens(buffer, &tokenize(buffer))
+            .into_iter()
+            .map(|diagnostic| (diagnostic.index, diagnostic.message))
+            .collect()
+    };
+
+    assert_eq!(
+        messages("1 /* open /* nested */\nlet x = 2\n"),
+        [(2, "unterminated comment".to_string())]
+    );
+    assert_eq!(
+        messages("let s = \"abc\nlet t = 1 /*"),
+        [(8, "unterminated string".to_string()), (23, "unterminated comment".to_string())]
+    );
+}
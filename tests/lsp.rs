@@ -0,0 +1,231 @@
+use std::io::{BufRead, BufReader, Read};
+
+use crap_lang::lsp::serve;
+use serde_json::{json, Value};
+
+// --- scripted client --- //
+// the messages are framed up front, `serve` runs until `exit`
+// and the framed responses are read back in order
+
+const URI: &str = "file:///main.crap";
+
+const SOURCE: &str = "/// the answer\nlet answer = 42\nlet twice = answer + answer\n";
+
+fn frame(message: &Value) -> String {
+    let body = message.to_string();
+    format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+}
+
+fn request(id: u64, method: &str, params: Value) -> String {
+    frame(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+}
+
+fn notification(method: &str, params: Value) -> String {
+    frame(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+}
+
+fn position(uri: &str, line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": uri },
+        "position": { "line": line, "character": character },
+    })
+}
+
+/// runs the server over `input` and returns the messages it wrote
+fn run(input: &str) -> Vec<Value> {
+    let mut output = vec![];
+    serve(input.as_bytes(), &mut output).unwrap();
+
+    let mut reader = BufReader::new(&output[..]);
+    let mut messages = vec![];
+
+    loop {
+        let mut length = None;
+        let mut line = String::new();
+
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                assert!(length.is_none(), "truncated message");
+                return messages;
+            }
+
+            match line.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse::<usize>().ok(),
+                _ => break,
+            }
+        }
+
+        let mut body = vec![0; length.expect("missing Content-Length header")];
+        reader.read_exact(&mut body).unwrap();
+        messages.push(serde_json::from_slice(&body).unwrap());
+    }
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Value {
+    json!({
+        "start": { "line": start.0, "character": start.1 },
+        "end": { "line": end.0, "character": end.1 },
+    })
+}
+
+#[test]
+fn session() {
+    let document = json!({ "textDocument": { "uri": URI } });
+
+    let input = [
+        request(1, "initialize", json!({ "capabilities": {} })),
+        notification("initialized", json!({})),
+        notification(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": URI, "languageId": "crap", "version": 1, "text": SOURCE },
+            }),
+        ),
+        // `42` becomes `4.2`, then an error is added and removed again
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 2 },
+                "contentChanges": [
+                    { "range": range((1, 14), (1, 14)), "text": "." },
+                    { "range": range((2, 27), (2, 27)), "text": " +" },
+                ],
+            }),
+        ),
+        notification(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": URI, "version": 3 },
+                "contentChanges": [{ "range": range((2, 27), (2, 29)), "text": "" }],
+            }),
+        ),
+        request(2, "textDocument/hover", position(URI, 2, 14)),
+        request(3, "textDocument/definition", position(URI, 2, 23)),
+        request(4, "textDocument/documentSymbol", document.clone()),
+        request(5, "textDocument/semanticTokens/full", document.clone()),
+        request(6, "textDocument/unknown", json!({})),
+        request(7, "shutdown", Value::Null),
+        notification("exit", Value::Null),
+        // nothing after `exit` is answered
+        request(8, "shutdown", Value::Null),
+    ]
+    .concat();
+
+    let messages = run(&input);
+    let mut messages = messages.iter();
+    let mut next = || messages.next().expect("missing message").clone();
+
+    // initialize
+    let response = next();
+    assert_eq!(response["id"], 1);
+    let capabilities = &response["result"]["capabilities"];
+    assert_eq!(capabilities["textDocumentSync"], 2);
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(capabilities["semanticTokensProvider"]["full"], true);
+
+    // didOpen and both didChange publish the diagnostics of the whole file
+    let opened = next();
+    assert_eq!(opened["method"], "textDocument/publishDiagnostics");
+    assert_eq!(opened["params"], json!({ "uri": URI, "diagnostics": [] }));
+
+    let broken = next();
+    let diagnostics = broken["params"]["diagnostics"].as_array().unwrap();
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0]["severity"], 1);
+    assert_eq!(diagnostics[0]["source"], "crap");
+
+    let fixed = next();
+    assert_eq!(fixed["params"], json!({ "uri": URI, "diagnostics": [] }));
+
+    // hover on the first `answer` of the last line, with its doc comment
+    assert_eq!(
+        next(),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "result": {
+                "contents": { "kind": "markdown", "value": "```crap\nanswer: f64\n```\n\nthe answer" },
+                "range": range((2, 12), (2, 18)),
+            },
+        })
+    );
+
+    // the second `answer` goes to its declaration
+    assert_eq!(
+        next(),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "result": { "uri": URI, "range": range((1, 4), (1, 10)) },
+        })
+    );
+
+    let symbols = next();
+    assert_eq!(symbols["id"], 4);
+    assert_eq!(
+        symbols["result"],
+        json!([
+            {
+                "name": "answer",
+                "kind": 13,
+                "range": range((1, 0), (1, 16)),
+                "selectionRange": range((1, 4), (1, 10)),
+                "children": [],
+                "detail": "f64",
+            },
+            {
+                "name": "twice",
+                "kind": 13,
+                "range": range((2, 0), (2, 27)),
+                "selectionRange": range((2, 4), (2, 9)),
+                "children": [],
+                "detail": "f64",
+            },
+        ])
+    );
+
+    // `delta line, delta start, length, type, modifiers` per token
+    let tokens = next();
+    assert_eq!(tokens["id"], 5);
+    #[rustfmt::skip]
+    assert_eq!(
+        tokens["result"]["data"],
+        json!([
+            0, 0, 14, 7, 0, // /// the answer
+            1, 0, 3, 0, 0,  // let
+            0, 4, 6, 2, 0,  // answer
+            0, 7, 1, 8, 0,  // =
+            0, 2, 3, 5, 0,  // 4.2
+            1, 0, 3, 0, 0,  // let
+            0, 4, 5, 2, 0,  // twice
+            0, 6, 1, 8, 0,  // =
+            0, 2, 6, 2, 0,  // answer
+            0, 7, 1, 8, 0,  // +
+            0, 2, 6, 2, 0,  // answer
+        ])
+    );
+
+    let unknown = next();
+    assert_eq!(unknown["id"], 6);
+    assert_eq!(unknown["error"]["code"], -32601);
+
+    assert_eq!(next(), json!({ "jsonrpc": "2.0", "id": 7, "result": null }));
+    assert_eq!(messages.next(), None);
+}
+
+#[test]
+fn malformed_json() {
+    let input = [
+        "Content-Length: 5\r\n\r\n{oops".to_string(),
+        request(1, "shutdown", Value::Null),
+    ]
+    .concat();
+
+    let messages = run(&input);
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["id"], Value::Null);
+    assert_eq!(messages[0]["error"]["code"], -32700);
+    assert_eq!(messages[1]["id"], 1);
+    assert_eq!(messages[1]["result"], Value::Null);
+}
@@ -0,0 +1,158 @@
+use std::io;
+
+use crap_lang::{
+    ast::{
+        dot::to_dot,
+        printer::print,
+        sexpr::to_sexpr,
+        statements::{debug_stmt, Statement},
+    },
+    codegen::{c::to_c, wasm::to_wat, x86_64::to_asm},
+    diagnostics::Diagnostic,
+    formatter::format,
+    interpreter::{evaluate, values::Value},
+    ir::lower::lower,
+    lexer::tokenize,
+    optimizer::{optimize, OptLevel},
+    parser::parse,
+    semantic::{analyze, check},
+};
+
+// --- deep expressions --- //
+// chains of binary operators are walked with loops and may be as long as they like,
+// everything else stops nesting at the parser, the tests run on the small stack of
+// a test thread
+
+const TERMS: usize = 100_000;
+
+fn parse_source(buffer: &str) -> (Vec<Statement>, Vec<Diagnostic>) {
+    parse(tokenize(buffer))
+}
+
+/// checks, evaluates and formats `buffer`, then evaluates it again optimized
+fn run(buffer: &str) -> Vec<Value> {
+    let (stmts, diagnostics) = parse_source(buffer);
+    assert_eq!(diagnostics, vec![]);
+    assert_eq!(check(&stmts), vec![]);
+
+    let values = evaluate(&stmts).unwrap();
+
+    let optimized = optimize(stmts, OptLevel::Simplify);
+    assert_eq!(evaluate(&optimized).unwrap(), values);
+    assert_eq!(format(buffer).unwrap(), buffer);
+
+    values
+}
+
+/// prints, dumps, lowers and compiles `buffer` with every backend
+fn compile(buffer: &str) {
+    let (stmts, diagnostics) = parse_source(buffer);
+    assert_eq!(diagnostics, vec![]);
+
+    for stmt in &stmts {
+        debug_stmt(&mut io::sink(), buffer, stmt).unwrap();
+    }
+
+    // the last statement is the chain, printed as it was written
+    let last = stmts.last().unwrap();
+    assert_eq!(
+        print(buffer, last).to_string(),
+        buffer.lines().last().unwrap()
+    );
+
+    to_sexpr(buffer, &stmts);
+    to_dot(buffer, &stmts);
+
+    let program = lower(&stmts, &analyze(&stmts)).unwrap();
+    to_c(&program, None);
+    to_wat(&program, None).unwrap();
+    to_asm(&program, None);
+}
+
+fn chain(first: &str, operator: &str, operand: &str) -> String {
+    let mut buffer = first.to_string();
+
+    for _ in 1..TERMS {
+        buffer.push_str(operator);
+        buffer.push_str(operand);
+    }
+
+    buffer.push('\n');
+    buffer
+}
+
+#[test]
+fn long_chains() {
+    assert_eq!(run(&chain("1", " + ", "1")), [Value::Int(TERMS as i64)]);
+    assert_eq!(
+        run(&chain("0.5", " + ", "1.5")),
+        [Value::Float(0.5 + 1.5 * (TERMS - 1) as f64)]
+    );
+
+    // `2 * 3` is a chain on the right side of every `+`
+    let mixed = chain("1", " + ", "2 * 3 - 1");
+    assert_eq!(run(&mixed), [Value::Int(1 + 5 * (TERMS - 1) as i64)]);
+}
+
+#[test]
+fn long_chains_compile() {
+    compile(&chain("1", " + ", "1"));
+    compile(&chain("0.5", " + ", "1.5"));
+    compile(&chain("1", " + ", "2 * 3 - 1"));
+    // the `(...)` stay around the weaker chains on the left
+    compile(&format!(
+        "{}1 * 2 + 1{}\n",
+        "(".repeat(100),
+        ") * 2 + 1".repeat(100)
+    ));
+
+    let chain = chain("x", " ?? ", "x");
+    compile(&format!("let x: i64? = none\n{} ?? 7\n", chain.trim_end()));
+}
+
+#[test]
+fn long_coalesce_chain() {
+    let chain = chain("x", " ?? ", "x");
+    let buffer = format!("let x: i64? = none\n{} ?? 7\n", chain.trim_end());
+
+    assert_eq!(run(&buffer), [Value::Int(7)]);
+}
+
+/// `depth` times `open`, a literal and `depth` times `close`
+fn nested(depth: usize, open: &str, close: &str) -> String {
+    format!("{}1{}\n", open.repeat(depth), close.repeat(depth))
+}
+
+#[test]
+fn nesting_up_to_the_limit() {
+    // the literal is one more level
+    assert_eq!(run(&nested(255, "(", ")")), [Value::Int(1)]);
+    assert_eq!(run(&format!("{}-1\n", "- ".repeat(254))), [Value::Int(-1)]);
+    // the right side and the `(...)` are two levels
+    assert_eq!(run(&nested(127, "1 + (", ")")), [Value::Int(128)]);
+    assert_eq!(run(&nested(254, "", " ? 1 : 2")), [Value::Int(1)]);
+}
+
+#[test]
+fn nesting_past_the_limit() {
+    let cases = [
+        nested(256, "(", ")"),
+        nested(100_000, "(", ")"),
+        nested(100_000, "-", ""),
+        nested(100_000, "1 + (", ")"),
+        nested(100_000, "", " ? 1 : 2"),
+        format!("a{}\n", ".x".repeat(100_000)),
+        format!("let x: i64{} = 1\n", "? ".repeat(100_000)),
+    ];
+
+    for buffer in cases {
+        let (stmts, diagnostics) = parse_source(&buffer);
+
+        assert_eq!(stmts.len(), 1);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "expression is nested more than 256 levels deep"
+        );
+    }
+}